@@ -0,0 +1,129 @@
+/*
+   Compares G1 commitments and proof generation with and without the fixed-base table of
+   ProvingKey::precompute_msm_table(). Run with:
+
+   cargo bench --bench msm
+*/
+use ark_bn254::{Bn254, Fr};
+use ark_ec::ProjectiveCurve;
+use ark_ff::{UniformRand, Zero};
+use ark_poly::{
+    univariate::DensePolynomial, EvaluationDomain, GeneralEvaluationDomain, UVPolynomial,
+};
+use ark_std::test_rng;
+use semacaulk::constants::{EXTENDED_DOMAIN_FACTOR, SUBGROUP_SIZE};
+use semacaulk::kzg::{commit, FixedBaseMsmTable};
+use semacaulk::layouter::Layouter;
+use semacaulk::mimc7::init_mimc7;
+use semacaulk::prover::prover::{Prover, WitnessInput};
+use semacaulk::prover::{ProverPrecomputedData, ProvingKey, PublicData};
+use semacaulk::setup::setup;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const ITERATIONS: u32 = 20;
+
+fn main() {
+    let mut rng = test_rng();
+    let log_2_table_size = 10;
+    let table_size: usize = 1 << log_2_table_size;
+    let (mut pk, _) = setup(log_2_table_size, "./11.ptau").unwrap();
+
+    let start = Instant::now();
+    let table = FixedBaseMsmTable::new(&pk.srs_g1[..EXTENDED_DOMAIN_FACTOR * SUBGROUP_SIZE]);
+    println!(
+        "Building the table of {} points with a window of {} bits took {:?}",
+        table.len(),
+        table.window_size(),
+        start.elapsed()
+    );
+
+    // The circuit polynomials have SUBGROUP_SIZE coefficients, and the quotient
+    // EXTENDED_DOMAIN_FACTOR * SUBGROUP_SIZE
+    for num_coeffs in [SUBGROUP_SIZE, EXTENDED_DOMAIN_FACTOR * SUBGROUP_SIZE] {
+        let poly = DensePolynomial::from_coefficients_vec(
+            (0..num_coeffs).map(|_| Fr::rand(&mut rng)).collect(),
+        );
+        let variable = time(|| {
+            let _ = black_box(commit(&pk.srs_g1, &poly));
+        });
+        let fixed = time(|| {
+            let _ = black_box(table.commit(&poly));
+        });
+        print_comparison(
+            &format!("commit() of {} coefficients", num_coeffs),
+            variable,
+            fixed,
+        );
+    }
+
+    let domain = GeneralEvaluationDomain::<Fr>::new(table_size).unwrap();
+    let mimc7 = init_mimc7::<Fr>();
+    let identity_nullifier = Fr::from(100u64);
+    let identity_trapdoor = Fr::from(200u64);
+    let external_nullifier = Fr::from(300u64);
+    let identity_commitment =
+        mimc7.multi_hash(&[identity_nullifier, identity_trapdoor], Fr::zero());
+    let assignment = Layouter::assign(
+        identity_nullifier,
+        identity_trapdoor,
+        external_nullifier,
+        &mimc7.cts,
+        &mut rng,
+    );
+
+    let index = 10;
+    let mut identity_commitments: Vec<_> = (0..table_size).map(|_| Fr::rand(&mut rng)).collect();
+    identity_commitments[index] = identity_commitment;
+    let c = DensePolynomial::from_coefficients_slice(&domain.ifft(&identity_commitments));
+    let precomputed = ProverPrecomputedData::index(&pk, &mimc7.cts, &[index], &c, table_size);
+
+    let witness = WitnessInput {
+        identity_nullifier,
+        identity_trapdoor,
+        identity_commitment,
+        index,
+    };
+    let public_input = PublicData::<Bn254> {
+        accumulator: commit(&pk.srs_g1, &c).into_affine(),
+        external_nullifier,
+        nullifier_hash: mimc7.multi_hash(&[identity_nullifier, external_nullifier], Fr::zero()),
+        signal_hash: Fr::from(888u64),
+    };
+
+    let mut prove = |pk: &ProvingKey<Bn254>| {
+        Prover::prove(
+            pk,
+            &witness,
+            &assignment,
+            &public_input,
+            &precomputed,
+            &mut rng,
+            table_size,
+        );
+    };
+    let variable = time(|| prove(&pk));
+    pk.precompute_msm_table();
+    let fixed = time(|| prove(&pk));
+    print_comparison("Prover::prove()", variable, fixed);
+}
+
+// The mean time of ITERATIONS runs, after one warm-up run
+fn time<F: FnMut()>(mut f: F) -> Duration {
+    f();
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    start.elapsed() / ITERATIONS
+}
+
+fn print_comparison(name: &str, variable: Duration, fixed: Duration) {
+    println!(
+        "{}: {:?} with VariableBaseMSM, {:?} with the fixed-base table ({:.2}x)",
+        name,
+        variable,
+        fixed,
+        variable.as_secs_f64() / fixed.as_secs_f64()
+    );
+}
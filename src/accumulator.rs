@@ -1,9 +1,15 @@
 use super::utils::compute_lagrange_basis_commitments;
 use crate::keccak_tree::KeccakTree;
 use ark_ec::{AffineCurve, PairingEngine, ProjectiveCurve};
-use ark_ff::{PrimeField, ToBytes};
+use ark_ff::{PrimeField, ToBytes, Zero};
+use ark_poly::{
+    univariate::DensePolynomial, EvaluationDomain, GeneralEvaluationDomain, UVPolynomial,
+};
+use ark_std::cfg_iter;
 use ethers::core::utils::keccak256;
 use ethers::types::U256;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::ops::Add;
 
@@ -46,6 +52,163 @@ impl<E: PairingEngine> Accumulator<E> {
         let p = self.point + v_minus_zero_mul_li_comm.into_affine();
         self.point = p
     }
+
+    /*
+     * Add a point (v - zero) * [L_i]_1 which inserts a hidden value v at index i. The point should
+     * first be checked with HiddenInsertion::verify().
+     */
+    pub fn update_with_point(&mut self, point: E::G1Affine) {
+        self.point = self.point + point;
+    }
+
+    // Replace the value old at the given index with new
+    pub fn rotate(&mut self, index: usize, old: E::Fr, new: E::Fr) {
+        assert!(index < self.lagrange_comms.len());
+
+        let delta = compute_rotation_delta::<E>(self.lagrange_comms[index], old, new);
+        self.point = self.point + delta;
+    }
+}
+
+/*
+ * The point which the contract adds to the accumulator to replace the value old at index i with
+ * new, i.e. (new - old) * [L_i]_1.
+ * @param lagrange_comm: The commitment to the Lagrange basis polynomial of the index.
+ * @param old: The value at the index.
+ * @param new: The value which replaces it.
+ */
+pub fn compute_rotation_delta<E: PairingEngine>(
+    lagrange_comm: E::G1Affine,
+    old: E::Fr,
+    new: E::Fr,
+) -> E::G1Affine {
+    lagrange_comm.mul(new - old).into_affine()
+}
+
+/*
+ * Parallel accumulators which share an index space, e.g. identity commitments in column 0 and
+ * member weights in column 1. A tuple lookup proves that a row of values sits at the same hidden
+ * index of every column.
+ */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct TupleAccumulator<E: PairingEngine> {
+    pub columns: Vec<Accumulator<E>>,
+    pub values: Vec<Vec<E::Fr>>,
+}
+
+impl<E: PairingEngine> TupleAccumulator<E> {
+    /*
+     * @param zeros: The value of an empty slot in each column.
+     * @param lagrange_comms: The commitments to the Lagrange basis polynomials.
+     */
+    pub fn new(zeros: &[E::Fr], lagrange_comms: &[E::G1Affine]) -> Self {
+        Self {
+            columns: zeros
+                .iter()
+                .map(|zero| Accumulator::new(*zero, lagrange_comms))
+                .collect(),
+            values: zeros
+                .iter()
+                .map(|zero| vec![*zero; lagrange_comms.len()])
+                .collect(),
+        }
+    }
+
+    pub fn update(&mut self, index: usize, row: &[E::Fr]) {
+        assert_eq!(row.len(), self.columns.len());
+
+        for ((column, values), value) in self
+            .columns
+            .iter_mut()
+            .zip(self.values.iter_mut())
+            .zip(row.iter())
+        {
+            column.update(index, *value);
+            values[index] = *value;
+        }
+    }
+
+    pub fn points(&self) -> Vec<E::G1Affine> {
+        self.columns.iter().map(|column| column.point).collect()
+    }
+
+    /*
+     * The polynomial whose evaluations over the table domain are the values of a column. Its
+     * commitment is the accumulator of the column.
+     */
+    pub fn polynomial(&self, column: usize) -> DensePolynomial<E::Fr> {
+        let domain = GeneralEvaluationDomain::<E::Fr>::new(self.values[column].len()).unwrap();
+        DensePolynomial::from_coefficients_slice(&domain.ifft(&self.values[column]))
+    }
+}
+
+/*
+ * The random linear combination points[0] + beta * points[1] + beta ^ 2 * points[2] + ... of the
+ * accumulators of a tuple lookup. The lookup is performed against this combined accumulator.
+ */
+pub fn combine_accumulators<E: PairingEngine>(points: &[E::G1Affine], beta: E::Fr) -> E::G1Affine {
+    let mut combined = E::G1Projective::zero();
+    for point in points.iter().rev() {
+        combined = combined.mul(beta.into_repr()) + point.into_projective();
+    }
+
+    combined.into_affine()
+}
+
+// The random linear combination of a row of values, which is the value looked up in the combined
+// accumulator
+pub fn combine_values<F: PrimeField>(values: &[F], beta: F) -> F {
+    values
+        .iter()
+        .rev()
+        .fold(F::zero(), |combined, value| combined * beta + value)
+}
+
+/*
+ * The prefix accumulator of the first k slots: the commitment to the polynomial whose
+ * evaluations over the table domain are values[0..k], followed by the zero value. A proof of
+ * membership in the prefix accumulator shows that the member's index is less than k. Verifiers
+ * should compute it themselves from the inserted values.
+ * @param zero: The value of an empty slot.
+ * @param lagrange_comms: The commitments to the Lagrange basis polynomials.
+ * @param values: The values in the accumulator, in index order.
+ * @param k: The number of slots in the prefix.
+ */
+pub fn compute_prefix_accumulator<E: PairingEngine>(
+    zero: E::Fr,
+    lagrange_comms: &[E::G1Affine],
+    values: &[E::Fr],
+    k: usize,
+) -> E::G1Affine {
+    assert!(k <= lagrange_comms.len());
+
+    let mut prefix = Accumulator::<E>::new(zero, lagrange_comms);
+    for (index, value) in values.iter().take(k).enumerate() {
+        prefix.update(index, *value);
+    }
+
+    prefix.point
+}
+
+/*
+ * The polynomial whose commitment is the prefix accumulator of the first k slots. The prover uses
+ * it to precompute the W1 values for a prefix proof.
+ */
+pub fn compute_prefix_polynomial<F: PrimeField>(
+    zero: F,
+    values: &[F],
+    k: usize,
+    table_size: usize,
+) -> DensePolynomial<F> {
+    assert!(k <= table_size);
+
+    let mut evals = vec![zero; table_size];
+    for (eval, value) in evals.iter_mut().zip(values.iter().take(k)) {
+        *eval = *value;
+    }
+
+    let domain = GeneralEvaluationDomain::<F>::new(table_size).unwrap();
+    DensePolynomial::from_coefficients_slice(&domain.ifft(&evals))
 }
 
 // Convert an F value to U256 for use with ethers-rs
@@ -98,38 +261,47 @@ pub fn compute_lagrange_tree<E: PairingEngine>(lagrange_comms: &Vec<E::G1Affine>
 
     assert_eq!(tree.num_leaves(), lagrange_comms.len());
 
-    for (i, p) in lagrange_comms.iter().enumerate() {
-        let mut b = Vec::with_capacity(65);
-        let _ = p.write(&mut b);
+    let leaves: Vec<[u8; 32]> = cfg_iter!(lagrange_comms)
+        .map(compute_lagrange_leaf::<E>)
+        .collect();
+    for (i, leaf) in leaves.into_iter().enumerate() {
+        tree.set(i, leaf);
+    }
 
-        // Slice to the first 64 bytes, since the 65th byte indicates whether the point is the
-        // point at infinity and we don't need it
-        let b = &b.get(0..64).unwrap();
+    tree
+}
 
-        let mut preimage = Vec::with_capacity(64);
-        for i in 0..32 {
-            preimage.push(b[31 - i]);
-        }
-        for i in 0..32 {
-            preimage.push(b[63 - i]);
-        }
+// The leaf of the Lagrange tree of a Lagrange commitment: the keccak256 hash of its X and Y values
+pub fn compute_lagrange_leaf<E: PairingEngine>(p: &E::G1Affine) -> [u8; 32] {
+    let mut b = Vec::with_capacity(65);
+    let _ = p.write(&mut b);
 
-        let leaf = keccak256(preimage);
+    // Slice to the first 64 bytes, since the 65th byte indicates whether the point is the
+    // point at infinity and we don't need it
+    let b = &b.get(0..64).unwrap();
 
-        tree.set(i, leaf);
+    let mut preimage = Vec::with_capacity(64);
+    for i in 0..32 {
+        preimage.push(b[31 - i]);
+    }
+    for i in 0..32 {
+        preimage.push(b[63 - i]);
     }
 
-    tree
+    keccak256(preimage)
 }
 
 #[cfg(test)]
 mod tests {
     use super::{
-        commit_to_lagrange_bases, compute_empty_accumulator, compute_zero_leaf, Accumulator,
+        combine_accumulators, combine_values, commit_to_lagrange_bases, compute_empty_accumulator,
+        compute_prefix_accumulator, compute_prefix_polynomial, compute_rotation_delta,
+        compute_zero_leaf, Accumulator, TupleAccumulator,
     };
-    use crate::kzg::unsafe_setup_g1;
+    use crate::kzg::{commit, unsafe_setup_g1};
     use ark_bn254::{Bn254, Fr};
-    use ark_ff::ToBytes;
+    use ark_ec::ProjectiveCurve;
+    use ark_ff::{ToBytes, Zero};
     use ark_std::{rand::rngs::StdRng, test_rng};
 
     #[test]
@@ -169,4 +341,81 @@ mod tests {
 
         acc.update(0, Fr::from(123));
     }
+
+    #[test]
+    fn test_prefix_accumulator() {
+        let table_size = 8;
+        let mut rng = test_rng();
+
+        let srs_g1 = unsafe_setup_g1::<Bn254, StdRng>(table_size, &mut rng);
+        let zero = compute_zero_leaf::<Fr>();
+        let lagrange_comms = commit_to_lagrange_bases::<Bn254>(table_size, &srs_g1);
+        let values: Vec<Fr> = (1..=5).map(Fr::from).collect();
+
+        let prefix = compute_prefix_accumulator::<Bn254>(zero, &lagrange_comms, &values, 3);
+        let prefix_poly = compute_prefix_polynomial(zero, &values, 3, table_size);
+        assert_eq!(prefix, commit(&srs_g1, &prefix_poly).into_affine());
+
+        // The prefix of all the slots is the accumulator itself
+        let mut acc = Accumulator::<Bn254>::new(zero, &lagrange_comms);
+        for (index, value) in values.iter().enumerate() {
+            acc.update(index, *value);
+        }
+        assert_eq!(
+            compute_prefix_accumulator::<Bn254>(zero, &lagrange_comms, &values, table_size),
+            acc.point
+        );
+    }
+
+    #[test]
+    fn test_rotation_delta() {
+        let table_size = 8;
+        let mut rng = test_rng();
+
+        let srs_g1 = unsafe_setup_g1::<Bn254, StdRng>(table_size, &mut rng);
+        let zero = compute_zero_leaf::<Fr>();
+        let lagrange_comms = commit_to_lagrange_bases::<Bn254>(table_size, &srs_g1);
+
+        let mut acc = Accumulator::<Bn254>::new(zero, &lagrange_comms);
+        acc.update(0, Fr::from(1));
+        acc.update(1, Fr::from(2));
+        acc.update(2, Fr::from(3));
+
+        // Rotating the value at index 1 is the same as having inserted the new value there
+        let mut expected = Accumulator::<Bn254>::new(zero, &lagrange_comms);
+        expected.update(0, Fr::from(1));
+        expected.update(1, Fr::from(20));
+        expected.update(2, Fr::from(3));
+
+        let delta = compute_rotation_delta::<Bn254>(lagrange_comms[1], Fr::from(2), Fr::from(20));
+        assert_eq!(acc.point + delta, expected.point);
+
+        acc.rotate(1, Fr::from(2), Fr::from(20));
+        assert_eq!(acc.point, expected.point);
+    }
+
+    #[test]
+    fn test_tuple_accumulator() {
+        let table_size = 8;
+        let mut rng = test_rng();
+
+        let srs_g1 = unsafe_setup_g1::<Bn254, StdRng>(table_size, &mut rng);
+        let zero = compute_zero_leaf::<Fr>();
+        let lagrange_comms = commit_to_lagrange_bases::<Bn254>(table_size, &srs_g1);
+
+        let mut acc = TupleAccumulator::<Bn254>::new(&[zero, Fr::zero()], &lagrange_comms);
+        acc.update(3, &[Fr::from(123), Fr::from(5)]);
+
+        // The combined accumulator commits to the combined columns
+        let beta = Fr::from(7);
+        let combined_poly = &acc.polynomial(0) + &(&acc.polynomial(1) * beta);
+        assert_eq!(
+            combine_accumulators::<Bn254>(&acc.points(), beta),
+            commit(&srs_g1, &combined_poly).into_affine()
+        );
+        assert_eq!(
+            combine_values(&[Fr::from(123), Fr::from(5)], beta),
+            Fr::from(123 + 5 * 7)
+        );
+    }
 }
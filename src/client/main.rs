@@ -1,10 +1,10 @@
-use ark_bn254::{Bn254, Fq, Fr};
+use ark_bn254::{Bn254, Fq, Fr, G1Affine, G2Affine};
 use ark_ff::PrimeField;
 use ark_poly::{
     univariate::DensePolynomial, EvaluationDomain, GeneralEvaluationDomain, UVPolynomial,
 };
-use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Read};
-use ark_std::{test_rng, Zero};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::test_rng;
 use clap::{arg, command, Parser, Subcommand};
 use clap_num::number_range;
 use ethers::contract::abigen;
@@ -16,13 +16,19 @@ use ethers::signers::{LocalWallet, Signer};
 use semacaulk::prover::prover::{Prover, WitnessInput};
 use semacaulk::{
     accumulator::{compute_lagrange_tree, compute_zero_leaf, Accumulator},
-    bn_solidity_utils::{f_to_u256, u256_to_f, f_to_hex},
+    bn_solidity_utils::{f_to_hex, f_to_u256, u256_to_f},
     contracts::compute_signal_hash,
+    error::Error as SemacaulkError,
+    identity::{rotation_external_nullifier, Identity},
     keccak_tree::flatten_proof,
     layouter::Layouter,
     mimc7::init_mimc7,
-    prover::{Proof as SemacaulkProof, ProverPrecomputedData, PublicData},
-    setup::{g2_str_to_g2, setup},
+    pir::{decode_w1, generate_queries, PirQuery},
+    precompute_cache::{CachedW1, PrecomputeCache},
+    prover::{
+        Proof as SemacaulkProof, ProverPrecomputedData, ProvingKey, PublicData, RotationPublicData,
+    },
+    setup::{load_setup, try_g2_str_to_g2},
     verifier::Verifier as SemacaulkVerifier,
 };
 use std::convert::TryFrom;
@@ -42,6 +48,14 @@ pub enum Error {
     InvalidSk,
     InvalidLog2Capacity,
     InvalidIdNulOrTrap,
+    Identity(SemacaulkError),
+    PrecomputeServer(String),
+    InvalidPrecomputedW1(SemacaulkError),
+    PrecomputeCache(SemacaulkError),
+    NotPrecomputed,
+    CachedValueMismatch,
+    InvalidSetup(SemacaulkError),
+    Rotation(SemacaulkError),
 }
 
 #[derive(Debug, Parser)] // requires `derive` feature
@@ -73,7 +87,8 @@ enum Commands {
         )]
         sk: String,
 
-        /// The powers of tau (PTAU) file containing a phase 1 trusted setup output
+        /// The powers of tau (PTAU) file containing a phase 1 trusted setup output, or a setup
+        /// artifact written by `setup artifact`
         #[arg(short, long, required = true)]
         ptau: String,
 
@@ -96,7 +111,8 @@ enum Commands {
         )]
         sk: String,
 
-        /// The powers of tau (PTAU) file containing a phase 1 trusted setup output
+        /// The powers of tau (PTAU) file containing a phase 1 trusted setup output, or a setup
+        /// artifact written by `setup artifact`
         #[arg(short, long, required = true)]
         ptau: String,
 
@@ -122,13 +138,14 @@ enum Commands {
         #[arg(short, long, required = false, default_value = "http://127.0.0.1:8545")]
         rpc: String,
 
-        /// The powers of tau (PTAU) file containing a phase 1 trusted setup output
+        /// The powers of tau (PTAU) file containing a phase 1 trusted setup output, or a setup
+        /// artifact written by `setup artifact`
         #[arg(short, long, required = true)]
         ptau: String,
 
         /// The Semacaulk contract
-        #[arg(short, long, required = true)]
-        contract: String,
+        #[arg(short, long, required_unless_present = "offline")]
+        contract: Option<String>,
 
         /// The index of the value in the accumulator
         #[arg(short, long, required = false)]
@@ -157,6 +174,92 @@ enum Commands {
         /// If specified, use this semacaulk_precompute endpoint to privately retrieve precomputed data
         #[arg(short, long, required = false)]
         semacaulk_precompute_endpoint: Option<String>,
+
+        /// If specified, retrieve W1 with two-server PIR from these two non-colluding
+        /// semacaulk_precompute endpoints, without revealing the index to either
+        #[arg(
+            long = "pir_endpoints",
+            required = false,
+            num_args = 2,
+            conflicts_with = "semacaulk_precompute_endpoint"
+        )]
+        pir_endpoints: Option<Vec<String>>,
+
+        /// Read precomputed data from, and write it to, this cache directory
+        #[arg(long, required = false)]
+        cache: Option<String>,
+
+        /// Do not connect to the Ethereum node, and prove against the accumulator of the W1 value
+        /// in the cache, which `client precompute` populates
+        #[arg(long, required = false, requires = "cache")]
+        offline: bool,
+    },
+    /// Populate a cache directory with the precomputed data of an index, so that `client prove`
+    /// can later run offline
+    Precompute {
+        /// The Ethereum node URL
+        #[arg(short, long, required = false, default_value = "http://127.0.0.1:8545")]
+        rpc: String,
+
+        /// The powers of tau (PTAU) file containing a phase 1 trusted setup output, or a setup
+        /// artifact written by `setup artifact`
+        #[arg(short, long, required = true)]
+        ptau: String,
+
+        /// The Semacaulk contract
+        #[arg(short, long, required = true)]
+        contract: String,
+
+        /// The index of the value in the accumulator
+        #[arg(short, long, required = true)]
+        index: usize,
+
+        /// The cache directory
+        #[arg(long, required = true)]
+        cache: String,
+
+        /// The capacity of the accumulator expressed in log_2 (e.g. log_2(1024) = 10)
+        #[arg(short, long, required = false, default_value = "10", value_parser=log_2_capacity_range)]
+        log_2_capacity: u8,
+    },
+    /// Build the data of a transaction which replaces an identity with a new one
+    Rotate {
+        /// The Ethereum node URL
+        #[arg(short, long, required = false, default_value = "http://127.0.0.1:8545")]
+        rpc: String,
+
+        /// The powers of tau (PTAU) file containing a phase 1 trusted setup output, or a setup
+        /// artifact written by `setup artifact`
+        #[arg(short, long, required = true)]
+        ptau: String,
+
+        /// The Semacaulk contract
+        #[arg(short, long, required = true)]
+        contract: String,
+
+        /// The index of the identity in the accumulator
+        #[arg(short, long, required = true)]
+        index: usize,
+
+        /// The identity nullifier, in hexadecimal
+        #[arg(long = "id_nul", short = 'n', required = true)]
+        id_nul: String,
+
+        /// The identity trapdoor, in hexadecimal
+        #[arg(long = "id_trap", short = 't', required = true)]
+        id_trap: String,
+
+        /// The new identity nullifier, in hexadecimal
+        #[arg(long = "new_id_nul", required = true)]
+        new_id_nul: String,
+
+        /// The new identity trapdoor, in hexadecimal
+        #[arg(long = "new_id_trap", required = true)]
+        new_id_trap: String,
+
+        /// The capacity of the accumulator expressed in log_2 (e.g. log_2(1024) = 10)
+        #[arg(short, long, required = false, default_value = "10", value_parser=log_2_capacity_range)]
+        log_2_capacity: u8,
     },
     BroadcastSignal {
         /// The Ethereum node URL
@@ -192,6 +295,23 @@ enum Commands {
         #[arg(long = "proof", short, required = true)]
         proof: String,
     },
+    NewIdentity {
+        /// Derive the identity from this secret seed instead of generating it randomly
+        #[arg(long, required = false)]
+        seed: Option<String>,
+
+        /// Derive the identity from a signature by this Ethereum private key
+        #[arg(long, required = false, conflicts_with = "seed")]
+        sk: Option<String>,
+
+        /// Write the identity to an encrypted keystore file in this directory
+        #[arg(long, required = false, requires = "password")]
+        keystore: Option<String>,
+
+        /// The keystore password
+        #[arg(long, required = false)]
+        password: Option<String>,
+    },
 }
 
 #[tokio::main]
@@ -234,18 +354,56 @@ async fn main() {
             id_trap,
             signal,
             semacaulk_precompute_endpoint,
+            pir_endpoints,
+            cache,
+            offline,
             log_2_capacity,
         } => {
             prove(
                 &rpc,
                 &ptau,
-                &contract,
+                contract,
                 index,
                 &ext_nul,
                 &id_nul,
                 &id_trap,
                 &signal,
                 semacaulk_precompute_endpoint,
+                pir_endpoints,
+                cache,
+                offline,
+                log_2_capacity,
+            )
+            .await
+        }
+        Commands::Precompute {
+            rpc,
+            ptau,
+            contract,
+            index,
+            cache,
+            log_2_capacity,
+        } => precompute(&rpc, &ptau, &contract, index, &cache, log_2_capacity).await,
+        Commands::Rotate {
+            rpc,
+            ptau,
+            contract,
+            index,
+            id_nul,
+            id_trap,
+            new_id_nul,
+            new_id_trap,
+            log_2_capacity,
+        } => {
+            rotate(
+                &rpc,
+                &ptau,
+                &contract,
+                index,
+                &id_nul,
+                &id_trap,
+                &new_id_nul,
+                &new_id_trap,
                 log_2_capacity,
             )
             .await
@@ -259,6 +417,12 @@ async fn main() {
             ext_nul,
             proof,
         } => broadcast_signal(&rpc, &sk, &contract, &signal, &nul_hash, &ext_nul, &proof).await,
+        Commands::NewIdentity {
+            seed,
+            sk,
+            keystore,
+            password,
+        } => new_identity(seed, sk, keystore, password).await,
     };
 
     if result.is_err() {
@@ -269,6 +433,41 @@ async fn main() {
             }
             Error::InvalidSk => println!("--sk should be a valid hexadecimal value."),
             Error::InvalidIdNulOrTrap => println!("-n or -t should be a valid hexadecimal value."),
+            Error::Identity(e) => match e {
+                SemacaulkError::IdentityKeystoreError(e) => {
+                    println!("Could not write the keystore: {}", e)
+                }
+                SemacaulkError::IdentityDerivationError(e) => {
+                    println!("Could not derive the identity from the wallet: {}", e)
+                }
+                e => println!("Could not create the identity: {:?}", e),
+            },
+            Error::PrecomputeServer(e) => {
+                println!("Could not fetch W1 from the precompute server: {}", e)
+            }
+            Error::InvalidPrecomputedW1(e) => match e {
+                SemacaulkError::InvalidG2String
+                | SemacaulkError::InvalidPirAnswer
+                | SemacaulkError::G2PointNotOnCurve => {
+                    println!("The precompute server returned a malformed W1 value.")
+                }
+                SemacaulkError::G2PointNotInSubgroup => {
+                    println!("The precompute server returned a W1 value outside the G2 subgroup.")
+                }
+                SemacaulkError::W1PairingCheckFailed => println!(
+                    "The precompute server returned a W1 value which does not match the current accumulator. It may be stale or malicious."
+                ),
+                e => println!("The precomputed W1 value is invalid: {:?}", e),
+            },
+            Error::PrecomputeCache(e) => println!("Could not use the precompute cache: {:?}", e),
+            Error::NotPrecomputed => println!(
+                "The cache has no precomputed data for this index. Run `client precompute` first."
+            ),
+            Error::CachedValueMismatch => println!(
+                "The cached value at this index is not the commitment of this identity."
+            ),
+            Error::InvalidSetup(e) => println!("Could not read the setup artifact: {:?}", e),
+            Error::Rotation(e) => println!("Could not build the rotation proof: {:?}", e),
         };
         process::exit(1);
     }
@@ -354,6 +553,42 @@ fn parse_id_nul_or_trap<F: PrimeField>(s: &str) -> Result<F, Error> {
     Ok(F::read(hex_buf.as_slice()).unwrap())
 }
 
+async fn new_identity(
+    seed: Option<String>,
+    sk: Option<String>,
+    keystore: Option<String>,
+    password: Option<String>,
+) -> Result<(), Error> {
+    let identity = if let Some(seed) = seed {
+        Identity::from_seed(seed.as_bytes())
+    } else if let Some(sk) = sk {
+        let wallet = create_wallet(&parse_sk(&sk)?)?;
+        Identity::from_wallet(&wallet)
+            .await
+            .map_err(Error::Identity)?
+    } else {
+        Identity::random(&mut rand::thread_rng())
+    };
+
+    println!("Identity nullifier: 0x{}", f_to_hex(identity.nullifier));
+    println!("Identity trapdoor: 0x{}", f_to_hex(identity.trapdoor));
+    println!("Identity commitment: 0x{}", f_to_hex(identity.commitment()));
+    println!(
+        "Semaphore identity string: {}",
+        identity.to_semaphore_string()
+    );
+
+    if let Some(dir) = keystore {
+        let name = identity
+            .export_encrypted(&dir, &password.unwrap(), None, &mut rand::thread_rng())
+            .map_err(Error::Identity)?;
+        let path = std::path::Path::new(&dir).join(name);
+        println!("Encrypted identity written to {}", path.display());
+    }
+
+    Ok(())
+}
+
 use semacaulk::contracts::format::proof_for_semacaulk::{format_proof, ProofForSemacaulk};
 async fn broadcast_signal(
     rpc: &str,
@@ -397,13 +632,16 @@ async fn broadcast_signal(
 async fn prove(
     rpc: &str,
     ptau: &str,
-    contract: &str,
+    contract: Option<String>,
     index: usize,
     ext_nul: &str,
     id_nul: &str,
     id_trap: &str,
     signal: &str,
     semacaulk_precompute_endpoint: Option<String>,
+    pir_endpoints: Option<Vec<String>>,
+    cache: Option<String>,
+    offline: bool,
     log_2_capacity: u8,
 ) -> Result<(), Error> {
     let ext_nul = parse_id_nul_or_trap::<Fr>(ext_nul)?;
@@ -411,10 +649,139 @@ async fn prove(
     let id_trap = parse_id_nul_or_trap::<Fr>(id_trap)?;
 
     let table_size = 2u64.pow(log_2_capacity as u32) as usize;
-    let (pk, lagrange_comms) = setup(log_2_capacity as usize, ptau);
+    let (pk, lagrange_comms) =
+        load_setup(log_2_capacity as usize, ptau).map_err(Error::InvalidSetup)?;
+
+    let mimc7 = init_mimc7::<Fr>();
+    let identity = Identity::new(id_nul, id_trap);
+
+    let cache = cache
+        .map(PrecomputeCache::new)
+        .transpose()
+        .map_err(Error::PrecomputeCache)?;
+
+    let (precomputed, accumulator) = if offline {
+        // Prove against the accumulator which the cached W1 value was computed for
+        let cache = cache.as_ref().unwrap();
+        let cached = cache
+            .latest_w1::<Bn254>(&pk, index, table_size)
+            .map_err(Error::PrecomputeCache)?
+            .ok_or(Error::NotPrecomputed)?;
+        if cached.value != identity.commitment() {
+            return Err(Error::CachedValueMismatch);
+        }
+        let (precomputed, _) = cache
+            .load(&pk, &mimc7.cts, index, cached.accumulator, table_size)
+            .map_err(Error::PrecomputeCache)?;
+        (precomputed, cached.accumulator)
+    } else {
+        let (identity_commitments, acc) =
+            fetch_identity_commitments(rpc, &contract.unwrap(), &lagrange_comms, table_size)
+                .await?;
+
+        assert_eq!(identity_commitments[index], identity.commitment());
+
+        let (mut precomputed, w1_cached) = match &cache {
+            Some(cache) => cache
+                .load(&pk, &mimc7.cts, index, acc.point, table_size)
+                .map_err(Error::PrecomputeCache)?,
+            None => {
+                let mut precomputed = ProverPrecomputedData::precompute_fixed(&mimc7.cts);
+                precomputed.precompute_w2(&pk, &[index], table_size);
+                (precomputed, false)
+            }
+        };
+
+        if !w1_cached {
+            fetch_or_compute_w1(
+                &pk,
+                &mut precomputed,
+                &acc,
+                &identity_commitments,
+                index,
+                semacaulk_precompute_endpoint,
+                pir_endpoints,
+                table_size,
+            )
+            .await?;
+
+            if let Some(cache) = &cache {
+                let cached = CachedW1::<Bn254> {
+                    accumulator: acc.point,
+                    value: identity_commitments[index],
+                    w1: precomputed.caulk_plus_precomputed.get_w1_i(&index),
+                };
+                cache
+                    .store_w1(&pk, index, &cached, table_size)
+                    .map_err(Error::PrecomputeCache)?;
+            }
+        }
+        (precomputed, acc.point)
+    };
+
+    let nullifier_hash = identity.nullifier_hash(ext_nul);
 
+    let mut rng = test_rng();
+
+    let assignment = Layouter::assign(id_nul, id_trap, ext_nul, &mimc7.cts, &mut rng);
+
+    let witness = WitnessInput {
+        identity_nullifier: id_nul,
+        identity_trapdoor: id_trap,
+        identity_commitment: identity.commitment(),
+        index,
+    };
+
+    let signal_hash = compute_signal_hash(signal);
+    let signal_hash_f: Fr = u256_to_f(signal_hash);
+
+    let public_input = PublicData::<Bn254> {
+        accumulator,
+        external_nullifier: ext_nul,
+        nullifier_hash,
+        signal_hash: signal_hash_f,
+    };
+
+    let proof: SemacaulkProof<Bn254> = Prover::prove(
+        &pk,
+        &witness,
+        &assignment,
+        &public_input,
+        &precomputed,
+        &mut rng,
+        table_size,
+    );
+
+    let is_valid = SemacaulkVerifier::verify(
+        &proof,
+        pk.srs_g1[table_size],
+        pk.srs_g2[1],
+        accumulator,
+        &public_input,
+    );
+
+    assert!(is_valid);
+    // Serialise and print proof
+    let mut serialised_proof = vec![];
+    let _ = proof.serialize(&mut serialised_proof);
+    let proof_hex = hex::encode(serialised_proof.as_slice());
+    println!("Nullifier hash:\n{}", f_to_hex(nullifier_hash));
+    println!("Serialised proof:\n{}", proof_hex);
+    Ok(())
+}
+
+/*
+ * Read the identity commitments inserted into the contract from its InsertIdentity events, and
+ * check that their accumulator matches the one on chain.
+ */
+async fn fetch_identity_commitments(
+    rpc: &str,
+    contract: &str,
+    lagrange_comms: &[G1Affine],
+    table_size: usize,
+) -> Result<(Vec<Fr>, Accumulator<Bn254>), Error> {
     let zero = compute_zero_leaf::<Fr>();
-    let mut acc = Accumulator::<Bn254>::new(zero, &lagrange_comms);
+    let mut acc = Accumulator::<Bn254>::new(zero, lagrange_comms);
 
     // Use a dummy secret key; we shouldn't be signing any txes anywaya
     let sk = "0000000000000000000000000000000000000000000000000000000000000001";
@@ -438,29 +805,30 @@ async fn prove(
         i += 1;
     }
 
-    let mimc7 = init_mimc7::<Fr>();
-
-    let leaf = mimc7.multi_hash(&[id_nul, id_trap], Fr::zero());
-    assert_eq!(identity_commitments[index], leaf);
-
     let acc_on_chain = semacaulk_contract.get_accumulator().call().await.unwrap();
     assert_eq!(u256_to_f::<Fq>(acc_on_chain.x), acc.point.x);
     assert_eq!(u256_to_f::<Fq>(acc_on_chain.y), acc.point.y);
 
-    let nullifier_hash = mimc7.multi_hash(&[id_nul, ext_nul], Fr::zero());
-
-    let mut rng = test_rng();
-
-    let assignment = Layouter::assign(id_nul, id_trap, ext_nul, &mimc7.cts, &mut rng);
-
-    let domain = GeneralEvaluationDomain::<Fr>::new(table_size).unwrap();
-    let c = DensePolynomial::from_coefficients_slice(&domain.ifft(&identity_commitments));
-
-    let mut precomputed = ProverPrecomputedData::precompute_fixed(&mimc7.cts);
-    precomputed.precompute_w2(&pk, &[index], table_size);
+    Ok((identity_commitments, acc))
+}
 
-    if semacaulk_precompute_endpoint.is_some() {
-        let mut endpoint = semacaulk_precompute_endpoint.unwrap();
+/*
+ * Insert the W1 value of the index into the precomputed data: from a precompute server, with
+ * two-server PIR, or by computing it locally. Values from servers are checked against the
+ * accumulator.
+ */
+#[allow(clippy::too_many_arguments)]
+async fn fetch_or_compute_w1(
+    pk: &ProvingKey<Bn254>,
+    precomputed: &mut ProverPrecomputedData<Bn254>,
+    acc: &Accumulator<Bn254>,
+    identity_commitments: &[Fr],
+    index: usize,
+    semacaulk_precompute_endpoint: Option<String>,
+    pir_endpoints: Option<Vec<String>>,
+    table_size: usize,
+) -> Result<(), Error> {
+    if let Some(mut endpoint) = semacaulk_precompute_endpoint {
         while endpoint.ends_with('/') {
             endpoint.pop();
         }
@@ -468,61 +836,295 @@ async fn prove(
         // Fetch precomputed data
         println!("Fetching precomputed data from the Blyss proxy at {endpoint}...");
         let url = format!("{endpoint}/{index}");
-        let mut res = reqwest::blocking::get(url).unwrap();
-        let mut body = String::new();
-        res.read_to_string(&mut body).unwrap();
-        let w1 = g2_str_to_g2(&body);
-
-        precomputed.update_w1(index, w1);
+        let body = reqwest::get(url)
+            .await
+            .and_then(|res| res.error_for_status())
+            .map_err(|e| Error::PrecomputeServer(e.to_string()))?
+            .text()
+            .await
+            .map_err(|e| Error::PrecomputeServer(e.to_string()))?;
+        let w1 = try_g2_str_to_g2(&body).map_err(Error::InvalidPrecomputedW1)?;
+
+        // Check the untrusted W1 against the accumulator before using it
+        precomputed
+            .update_w1_checked(
+                pk,
+                acc.point,
+                index,
+                identity_commitments[index],
+                w1,
+                table_size,
+            )
+            .map_err(Error::InvalidPrecomputedW1)?;
+    } else if let Some(pir_endpoints) = pir_endpoints {
+        println!("Fetching precomputed data with two-server PIR...");
+        let w1 = fetch_w1_with_pir(&pir_endpoints[0], &pir_endpoints[1], index, table_size).await?;
+        precomputed
+            .update_w1_checked(
+                pk,
+                acc.point,
+                index,
+                identity_commitments[index],
+                w1,
+                table_size,
+            )
+            .map_err(Error::InvalidPrecomputedW1)?;
     } else {
-        precomputed.precompute_w1(&pk, &[index], &c, table_size);
+        let domain = GeneralEvaluationDomain::<Fr>::new(table_size).unwrap();
+        let c = DensePolynomial::from_coefficients_slice(&domain.ifft(identity_commitments));
+        precomputed.precompute_w1(pk, &[index], &c, table_size);
     }
+    Ok(())
+}
+
+/*
+ * Compute the precomputed data of the index for the current accumulator, and store it in the
+ * cache directory, so that `client prove --offline` can use it without the Ethereum node.
+ */
+async fn precompute(
+    rpc: &str,
+    ptau: &str,
+    contract: &str,
+    index: usize,
+    cache: &str,
+    log_2_capacity: u8,
+) -> Result<(), Error> {
+    let table_size = 2u64.pow(log_2_capacity as u32) as usize;
+    let (pk, lagrange_comms) =
+        load_setup(log_2_capacity as usize, ptau).map_err(Error::InvalidSetup)?;
+    let cache = PrecomputeCache::new(cache).map_err(Error::PrecomputeCache)?;
+
+    let (identity_commitments, acc) =
+        fetch_identity_commitments(rpc, contract, &lagrange_comms, table_size).await?;
+
+    // Loading computes and stores the fixed data and W2 if they are missing
+    let mimc7 = init_mimc7::<Fr>();
+    let (mut precomputed, w1_cached) = cache
+        .load(&pk, &mimc7.cts, index, acc.point, table_size)
+        .map_err(Error::PrecomputeCache)?;
+
+    if !w1_cached {
+        fetch_or_compute_w1(
+            &pk,
+            &mut precomputed,
+            &acc,
+            &identity_commitments,
+            index,
+            None,
+            None,
+            table_size,
+        )
+        .await?;
+        let cached = CachedW1::<Bn254> {
+            accumulator: acc.point,
+            value: identity_commitments[index],
+            w1: precomputed.caulk_plus_precomputed.get_w1_i(&index),
+        };
+        cache
+            .store_w1(&pk, index, &cached, table_size)
+            .map_err(Error::PrecomputeCache)?;
+    }
+
+    println!(
+        "Cached the precomputed data of index {} for the accumulator:\n0x{}\n0x{}",
+        index,
+        f_to_hex(acc.point.x),
+        f_to_hex(acc.point.y)
+    );
+    Ok(())
+}
+
+/*
+ * Build the data of a transaction which replaces the identity at the given index with a new
+ * identity: the rotation proof and its public inputs, the accumulator delta, and the Lagrange
+ * basis commitment of the index with its Merkle proof, which the contract checks as in
+ * insertIdentity(). Semacaulk.sol does not yet have a rotation entrypoint, so the data is printed
+ * rather than sent.
+ */
+#[allow(clippy::too_many_arguments)]
+async fn rotate(
+    rpc: &str,
+    ptau: &str,
+    contract: &str,
+    index: usize,
+    id_nul: &str,
+    id_trap: &str,
+    new_id_nul: &str,
+    new_id_trap: &str,
+    log_2_capacity: u8,
+) -> Result<(), Error> {
+    let id_nul = parse_id_nul_or_trap::<Fr>(id_nul)?;
+    let id_trap = parse_id_nul_or_trap::<Fr>(id_trap)?;
+    let new_id_nul = parse_id_nul_or_trap::<Fr>(new_id_nul)?;
+    let new_id_trap = parse_id_nul_or_trap::<Fr>(new_id_trap)?;
+
+    let table_size = 2u64.pow(log_2_capacity as u32) as usize;
+    let (pk, lagrange_comms) =
+        load_setup(log_2_capacity as usize, ptau).map_err(Error::InvalidSetup)?;
+
+    let zero = compute_zero_leaf::<Fr>();
+    let mut acc = Accumulator::<Bn254>::new(zero, &lagrange_comms);
+
+    // Use a dummy secret key; we shouldn't be signing any txes
+    let sk = "0000000000000000000000000000000000000000000000000000000000000001";
+    let client = create_client(rpc, &parse_sk(sk)?).await?;
+    let semacaulk_contract = SemacaulkContract::new(str_to_ethers_address(contract), client);
+    let events = semacaulk_contract
+        .event::<InsertIdentityFilter>()
+        .from_block(0);
+    let num_leaves = semacaulk_contract.get_current_index().call().await.unwrap();
+    let mut stream = events.stream().await.unwrap().take(num_leaves.as_usize());
+
+    let mut identity_commitments: Vec<Fr> = vec![zero; table_size];
+    let mut i = 0;
+    while let Some(Ok(f)) = stream.next().await {
+        let id_comm = u256_to_f(f.identity_commitment);
+        identity_commitments[i] = id_comm;
+        acc.update(i, id_comm);
+        i += 1;
+    }
+
+    let identity = Identity::new(id_nul, id_trap);
+    let new_identity = Identity::new(new_id_nul, new_id_trap);
+    let old_identity_commitment = identity.commitment();
+    let new_identity_commitment = new_identity.commitment();
+
+    assert_eq!(identity_commitments[index], old_identity_commitment);
+
+    let acc_on_chain = semacaulk_contract.get_accumulator().call().await.unwrap();
+    assert_eq!(u256_to_f::<Fq>(acc_on_chain.x), acc.point.x);
+    assert_eq!(u256_to_f::<Fq>(acc_on_chain.y), acc.point.y);
+
+    let mimc7 = init_mimc7::<Fr>();
+    let mut rng = test_rng();
+
+    let external_nullifier = rotation_external_nullifier::<Fr>();
+    let nullifier_hash = identity.nullifier_hash(external_nullifier);
+    let assignment = Layouter::assign(id_nul, id_trap, external_nullifier, &mimc7.cts, &mut rng);
+
+    let domain = GeneralEvaluationDomain::<Fr>::new(table_size).unwrap();
+    let c = DensePolynomial::from_coefficients_slice(&domain.ifft(&identity_commitments));
+    let precomputed = ProverPrecomputedData::precompute_fixed(&mimc7.cts);
 
     let witness = WitnessInput {
         identity_nullifier: id_nul,
         identity_trapdoor: id_trap,
-        identity_commitment: identity_commitments[index],
+        identity_commitment: old_identity_commitment,
         index,
     };
 
-    let signal_hash = compute_signal_hash(signal);
-    let signal_hash_f: Fr = u256_to_f(signal_hash);
-
-    let public_input = PublicData::<Bn254> {
+    let public_input = RotationPublicData::<Bn254> {
         accumulator: acc.point,
-        external_nullifier: ext_nul,
+        index,
+        old_identity_commitment,
+        new_identity_commitment,
         nullifier_hash,
-        signal_hash: signal_hash_f,
     };
 
-    let proof: SemacaulkProof<Bn254> = Prover::prove(
+    let proof = Prover::prove_rotation(
         &pk,
+        &lagrange_comms,
+        &c,
         &witness,
         &assignment,
         &public_input,
         &precomputed,
         &mut rng,
         table_size,
-    );
+    )
+    .map_err(Error::Rotation)?;
 
-    let is_valid = SemacaulkVerifier::verify(
+    assert!(SemacaulkVerifier::verify_rotation(
         &proof,
         pk.srs_g1[table_size],
         pk.srs_g2[1],
-        acc.point,
+        &lagrange_comms,
         &public_input,
-    );
+    ));
+
+    // The delta which the contract adds to the accumulator
+    let l_i = lagrange_comms[index];
+    let delta = public_input
+        .delta(&lagrange_comms)
+        .map_err(Error::Rotation)?;
+
+    let tree = compute_lagrange_tree::<Bn254>(&lagrange_comms);
+    let flattened_proof = flatten_proof(&tree.proof(index).unwrap());
 
-    assert!(is_valid);
-    // Serialise and print proof
     let mut serialised_proof = vec![];
     let _ = proof.serialize(&mut serialised_proof);
-    let proof_hex = hex::encode(serialised_proof.as_slice());
+
+    println!("Index:\n{}", index);
+    println!(
+        "Old identity commitment:\n{}",
+        f_to_hex(old_identity_commitment)
+    );
+    println!(
+        "New identity commitment:\n{}",
+        f_to_hex(new_identity_commitment)
+    );
     println!("Nullifier hash:\n{}", f_to_hex(nullifier_hash));
-    println!("Serialised proof:\n{}", proof_hex);
+    println!(
+        "Lagrange basis commitment:\n{}\n{}",
+        f_to_hex(l_i.x),
+        f_to_hex(l_i.y)
+    );
+    println!("Lagrange basis commitment Merkle proof:");
+    for node in flattened_proof.iter() {
+        println!("0x{}", hex::encode(node));
+    }
+    println!(
+        "Accumulator delta:\n{}\n{}",
+        f_to_hex(delta.x),
+        f_to_hex(delta.y)
+    );
+    println!(
+        "Serialised proof:\n{}",
+        hex::encode(serialised_proof.as_slice())
+    );
     Ok(())
 }
 
+/*
+ * Fetch the W1 value of the index from two non-colluding semacaulk_precompute endpoints with
+ * two-server XOR PIR. Each endpoint receives a hex-encoded query at {endpoint}/pir and returns a
+ * hex-encoded answer.
+ */
+async fn fetch_w1_with_pir(
+    endpoint_a: &str,
+    endpoint_b: &str,
+    index: usize,
+    table_size: usize,
+) -> Result<G2Affine, Error> {
+    let (query_a, query_b) = generate_queries(index, table_size, &mut rand::thread_rng());
+
+    let client = reqwest::Client::new();
+    let answer_a = fetch_pir_answer(&client, endpoint_a, &query_a).await?;
+    let answer_b = fetch_pir_answer(&client, endpoint_b, &query_b).await?;
+
+    decode_w1::<Bn254>(&answer_a, &answer_b).map_err(Error::InvalidPrecomputedW1)
+}
+
+async fn fetch_pir_answer(
+    client: &reqwest::Client,
+    endpoint: &str,
+    query: &PirQuery,
+) -> Result<Vec<u8>, Error> {
+    let url = format!("{}/pir", endpoint.trim_end_matches('/'));
+    let body = client
+        .post(url)
+        .body(query.to_hex())
+        .send()
+        .await
+        .and_then(|res| res.error_for_status())
+        .map_err(|e| Error::PrecomputeServer(e.to_string()))?
+        .text()
+        .await
+        .map_err(|e| Error::PrecomputeServer(e.to_string()))?;
+    hex::decode(body.trim())
+        .map_err(|_| Error::InvalidPrecomputedW1(SemacaulkError::InvalidPirAnswer))
+}
+
 pub fn remove_address_prefix(addr: String) -> String {
     if addr.starts_with("0x") {
         return addr.chars().skip(2).collect::<String>();
@@ -549,7 +1151,8 @@ async fn insert(
     let id_nul = parse_id_nul_or_trap::<Fr>(id_nul)?;
     let id_trap = parse_id_nul_or_trap::<Fr>(id_trap)?;
 
-    let (_pk, lagrange_comms) = setup(log_2_capacity as usize, ptau);
+    let (_pk, lagrange_comms) =
+        load_setup(log_2_capacity as usize, ptau).map_err(Error::InvalidSetup)?;
 
     let client = create_client(rpc, &parse_sk(sk)?).await?;
 
@@ -564,8 +1167,7 @@ async fn insert(
         .unwrap()
         .as_u64() as usize;
 
-    let mimc7 = init_mimc7::<Fr>();
-    let new_leaf = mimc7.multi_hash(&[id_nul, id_trap], Fr::zero());
+    let new_leaf = Identity::new(id_nul, id_trap).commitment();
 
     // Construct the tree of commitments to the Lagrange bases
     let tree = compute_lagrange_tree::<Bn254>(&lagrange_comms);
@@ -600,7 +1202,10 @@ async fn deploy(rpc: &str, sk: &str, ptau: &str, log_2_capacity: u8) -> Result<(
     }
     let client = create_client(rpc, &parse_sk(sk)?).await?;
 
-    let (_pk, lagrange_comms) = setup(log_2_capacity as usize, ptau);
+    // The SRS is not checked here, as verify_srs() takes time linear in its size; check the
+    // ptau file once with `setup verify` before deploying with it
+    let (_, lagrange_comms) =
+        load_setup(log_2_capacity as usize, ptau).map_err(Error::InvalidSetup)?;
 
     let zero = compute_zero_leaf::<Fr>();
     let acc = Accumulator::<Bn254>::new(zero, &lagrange_comms);
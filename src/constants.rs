@@ -3,3 +3,11 @@ pub const SUBGROUP_SIZE: usize = 128;
 pub const EXTENDED_DOMAIN_FACTOR: usize = 8;
 pub const DUMMY_VALUE: u64 = 9999;
 pub const MIMC_SEED: &str = "mimc";
+pub const POSEIDON_WIDTH: usize = 3;
+pub const POSEIDON_FULL_ROUNDS: usize = 8;
+pub const POSEIDON_PARTIAL_ROUNDS: usize = 57;
+pub const NUMBER_OF_POSEIDON_ROUNDS: usize = POSEIDON_FULL_ROUNDS + POSEIDON_PARTIAL_ROUNDS;
+pub const POSEIDON_SINGLE_WIDTH: usize = 2;
+pub const POSEIDON_SINGLE_PARTIAL_ROUNDS: usize = 56;
+pub const NUMBER_OF_POSEIDON_SINGLE_ROUNDS: usize =
+    POSEIDON_FULL_ROUNDS + POSEIDON_SINGLE_PARTIAL_ROUNDS;
@@ -48,7 +48,7 @@ pub async fn deploy_semacaulk(
 ) {
     let zero = compute_zero_leaf::<Fr>();
 
-    let (pk, lagrange_comms) = setup(log_2_table_size, ptau_filename);
+    let (pk, lagrange_comms) = setup(log_2_table_size, ptau_filename).unwrap();
     let srs_g1 = pk.srs_g1;
     let srs_g2 = pk.srs_g2;
 
@@ -155,10 +155,7 @@ pub async fn test_semacaulk_insert_and_broadcast() {
     }
 
     // Broadcast a signal using the identity behind leaf 1
-    let pk = ProvingKey::<Bn254> {
-        srs_g1,
-        srs_g2: srs_g2.clone(),
-    };
+    let pk = ProvingKey::<Bn254>::new(srs_g1, srs_g2.clone());
     let mut rng = test_rng();
 
     let index = 1;
@@ -58,7 +58,7 @@ pub async fn test_semacaulk_verifier() {
     identity_commitments[index] = identity_commitment;
     let c = DensePolynomial::from_coefficients_slice(&domain.ifft(&identity_commitments));
 
-    let (pk, _) = setup(log_2_table_size, "./11.ptau");
+    let (pk, _) = setup(log_2_table_size, "./11.ptau").unwrap();
 
     let precomputed = ProverPrecomputedData::index(&pk, &mimc7.cts, &[index], &c, table_size);
 
@@ -49,7 +49,7 @@ pub async fn deploy_semacaulk(
     // This file is generated by https://github.com/geometryresearch/export-ptau-points
     // If you change this file, use `cargo run setup <table_size> <hex_filename> <lagrange_comms_out>` to regenerate the lagrangeCommsX file. Also remember to update Constants.sol.
     let sw = Stopwatch::start_new();
-    let (pk, lagrange_comms) = setup(log_2_table_size, ptau_filename);
+    let (pk, lagrange_comms) = setup(log_2_table_size, ptau_filename).unwrap();
     let srs_g1 = pk.srs_g1;
     let srs_g2 = pk.srs_g2;
 
@@ -156,10 +156,10 @@ async fn main() {
     }
 
     // Broadcast a signal using the identity behind leaf 1
-    let pk = ProvingKey::<Bn254> {
-        srs_g1: srs_g1.clone(),
-        srs_g2: srs_g2.clone(),
-    };
+    let mut pk = ProvingKey::<Bn254>::new(srs_g1.clone(), srs_g2.clone());
+    let sw = Stopwatch::start_new();
+    pk.precompute_msm_table();
+    println!("Building the fixed-base MSM table took {}ms", sw.elapsed_ms());
     let mut rng = test_rng();
 
     let index = 0;
@@ -1,4 +1,58 @@
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Error {
     FinalPairingCheckFailed,
+    GroupIsFull,
+    IdentityNotInGroup,
+    InvalidIdentityString,
+    IdentityKeystoreError(String),
+    IdentityDerivationError(String),
+    MessageLimitExceeded,
+    AttributePredicateNotSatisfied,
+    InvalidInsertionProof,
+    InvalidPirQuery,
+    InvalidPirAnswer,
+    InvalidG2String,
+    G2PointNotOnCurve,
+    G2PointNotInSubgroup,
+    W1PairingCheckFailed,
+    PrecomputeCacheError(String),
+    SrsTooShort,
+    InvalidSrsGenerator,
+    SrsPointNotOnCurve,
+    SrsPointNotInSubgroup,
+    SrsG1PowersInconsistent,
+    SrsG2PowersInconsistent,
+    InvalidG1String,
+    G1PointNotOnCurve,
+    ArtifactIoError(String),
+    InvalidArtifactMagic,
+    UnsupportedArtifactVersion(u32),
+    InvalidArtifactTableSize,
+    ArtifactTruncated,
+    ArtifactChecksumMismatch,
+    InvalidArtifactPoint,
+    ArtifactIndexOutOfRange,
+    PtauIoError(String),
+    InvalidPtauMagic,
+    InvalidPtauHeader,
+    PpotIoError(String),
+    InvalidPpotFileSize,
+    InvalidPpotPoint,
+    PpotPowerTooSmall,
+    PpotHashMismatch,
+    ContributionLengthMismatch,
+    InvalidContributionProof,
+    ContributionNotFromPreviousSrs,
+    LagrangeCommsIoError(String),
+    LagrangeCommsWrongLength,
+    LagrangeCommsSumNotOne,
+    LagrangeCommsInconsistent,
+    InvalidPtauPoint,
+    PtauPowerTooSmall,
+    ScratchIoError(String),
+    UnsupportedTableSize(usize),
+    TupleColumnCountMismatch,
+    IndexNotInPrefix,
+    InvalidPrefixLength,
+    RotationWitnessMismatch,
 }
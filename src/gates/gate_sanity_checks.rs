@@ -199,3 +199,47 @@ pub fn gate_10_key_constant<F: PrimeField>(
         assert_eq!(q_key_evals[i] * (key_evals[i] - key_next_i), F::zero(),);
     }
 }
+
+/*
+ * Checks whether the evals satisfy the gate with the following equation, for each state column k:
+ *
+ * q_full * (sum_j(M[k][j] * (s_j + c_j)^5) - s_k_next) +
+ * q_partial * (M[k][0] * (s_0 + c_0)^5 + sum_{j > 0}(M[k][j] * (s_j + c_j)) - s_k_next) +
+ * q_pass * (s_k - s_k_next)
+ */
+#[allow(clippy::too_many_arguments)]
+pub fn poseidon_round<F: PrimeField>(
+    q_full_evals: &[F],
+    q_partial_evals: &[F],
+    q_pass_evals: &[F],
+    state_evals: &[Vec<F>],
+    c_evals: &[Vec<F>],
+    mds: &[Vec<F>],
+    dummy: F,
+    domain_size: usize,
+) {
+    let pow_5 = |x: F| x.pow([5, 0, 0, 0]);
+
+    for i in 0..domain_size {
+        for k in 0..state_evals.len() {
+            let s_next_i = if i == domain_size - 1 {
+                dummy
+            } else {
+                state_evals[k][i + 1]
+            };
+
+            let mut full = F::zero();
+            let mut partial = F::zero();
+            for j in 0..state_evals.len() {
+                let x = state_evals[j][i] + c_evals[j][i];
+                full += mds[k][j] * pow_5(x);
+                partial += mds[k][j] * if j == 0 { pow_5(x) } else { x };
+            }
+
+            let result = q_full_evals[i] * (full - s_next_i)
+                + q_partial_evals[i] * (partial - s_next_i)
+                + q_pass_evals[i] * (state_evals[k][i] - s_next_i);
+            assert_eq!(result, F::zero());
+        }
+    }
+}
@@ -93,5 +93,349 @@ impl<F: PrimeField> ExternalNullifierGate<F> {
     }
 }
 
+/*
+ * In RLN mode, the nullifier hash MiMC7(id_nul, ext_nul) is kept secret as the slope a1 of the
+ * Shamir share line, and is copied into the first row of the internal_nullifier column instead of
+ * being checked against a public input.
+ */
+pub struct RlnSlopeGate<F: PrimeField> {
+    _f: PhantomData<F>,
+}
+
+impl<F: PrimeField> RlnSlopeGate<F> {
+    pub fn compute_in_coset(
+        omega_i: usize,
+        nullifier_external: &[F],
+        key: &[F],
+        internal_nullifier: &[F],
+        l0: &[F],
+    ) -> F {
+        let nullifier_external_pow_n = positive_rotation_in_coset(
+            nullifier_external,
+            omega_i,
+            NUMBER_OF_MIMC_ROUNDS,
+            EXTENDED_DOMAIN_FACTOR,
+        );
+        l0[omega_i]
+            * (internal_nullifier[omega_i]
+                - nullifier_external_pow_n
+                - F::from(2u64) * key[omega_i]
+                - nullifier_external[omega_i])
+    }
+}
+
+pub struct RlnNullifierGate<F: PrimeField> {
+    _f: PhantomData<F>,
+}
+
+impl<F: PrimeField> RlnNullifierGate<F> {
+    // The RLN nullifier is the MiMC7 multihash of a1 with key 0
+    pub fn compute_in_coset(
+        omega_i: usize,
+        internal_nullifier: &[F],
+        l0: &[F],
+        rln_nullifier: F, // public input
+    ) -> F {
+        let internal_nullifier_pow_n = positive_rotation_in_coset(
+            internal_nullifier,
+            omega_i,
+            NUMBER_OF_MIMC_ROUNDS,
+            EXTENDED_DOMAIN_FACTOR,
+        );
+        l0[omega_i] * (rln_nullifier - internal_nullifier_pow_n - internal_nullifier[omega_i])
+    }
+}
+
+pub struct RlnShareGate<F: PrimeField> {
+    _f: PhantomData<F>,
+}
+
+impl<F: PrimeField> RlnShareGate<F> {
+    // y = a0 + a1 * x, where a0 is the identity nullifier and a1 is the internal nullifier input
+    pub fn compute_in_coset(
+        omega_i: usize,
+        nullifier: &[F],
+        internal_nullifier: &[F],
+        l0: &[F],
+        x: F, // public input
+        y: F, // public input
+    ) -> F {
+        l0[omega_i] * (y - nullifier[omega_i] - internal_nullifier[omega_i] * x)
+    }
+}
+
+pub struct RlnExternalNullifierGate<F: PrimeField> {
+    _f: PhantomData<F>,
+}
+
+impl<F: PrimeField> RlnExternalNullifierGate<F> {
+    // The external nullifier is the MiMC7 multihash of the epoch and the message ID, i.e. the
+    // output of the message ID rounds, whose key is the multihash of the epoch
+    pub fn compute_in_coset(
+        omega_i: usize,
+        nullifier_external: &[F],
+        message_key: &[F],
+        message_id: &[F],
+        l0: &[F],
+    ) -> F {
+        let message_id_pow_n = positive_rotation_in_coset(
+            message_id,
+            omega_i,
+            NUMBER_OF_MIMC_ROUNDS,
+            EXTENDED_DOMAIN_FACTOR,
+        );
+        l0[omega_i]
+            * (nullifier_external[omega_i]
+                - message_id_pow_n
+                - F::from(2u64) * message_key[omega_i]
+                - message_id[omega_i])
+    }
+}
+
+pub struct RlnMessageLimitGate<F: PrimeField> {
+    _f: PhantomData<F>,
+}
+
+impl<F: PrimeField> RlnMessageLimitGate<F> {
+    // message_limit - 1 - message_id is the sum of NUMBER_OF_MIMC_ROUNDS bits, so
+    // message_id < message_limit
+    pub fn compute_in_coset(
+        omega_i: usize,
+        range: &[F],
+        message_id: &[F],
+        l0: &[F],
+        message_limit: F, // public input
+    ) -> F {
+        let range_pow_n = positive_rotation_in_coset(
+            range,
+            omega_i,
+            NUMBER_OF_MIMC_ROUNDS,
+            EXTENDED_DOMAIN_FACTOR,
+        );
+        l0[omega_i] * (range_pow_n + message_id[omega_i] + F::one() - message_limit)
+    }
+}
+
+pub struct AttributeKeyCopyGate<F: PrimeField> {
+    _f: PhantomData<F>,
+}
+
+impl<F: PrimeField> AttributeKeyCopyGate<F> {
+    // The attribute is hashed with the MiMC7 multihash of the identity nullifier and trapdoor as
+    // the key
+    pub fn compute_in_coset(
+        omega_i: usize,
+        identity_commitment: &[F],
+        key: &[F],
+        attribute_key: &[F],
+        l0: &[F],
+    ) -> F {
+        let identity_commitment_pow_n = positive_rotation_in_coset(
+            identity_commitment,
+            omega_i,
+            NUMBER_OF_MIMC_ROUNDS,
+            EXTENDED_DOMAIN_FACTOR,
+        );
+        l0[omega_i]
+            * (attribute_key[omega_i]
+                - identity_commitment_pow_n
+                - F::from(2u64) * key[omega_i]
+                - identity_commitment[omega_i])
+    }
+}
+
+pub struct RangeBitGate<F: PrimeField> {
+    _f: PhantomData<F>,
+}
+
+impl<F: PrimeField> RangeBitGate<F> {
+    // Each step of the running sum adds one bit: range_next = 2 * range + b, where b is 0 or 1
+    pub fn compute_in_coset(omega_i: usize, range: &[F], q_mimc: &[F]) -> F {
+        let range_next = positive_rotation_in_coset(range, omega_i, 1, EXTENDED_DOMAIN_FACTOR);
+        let bit = range_next - F::from(2u64) * range[omega_i];
+        q_mimc[omega_i] * bit * (bit - F::one())
+    }
+}
+
+pub struct RangeStartGate<F: PrimeField> {
+    _f: PhantomData<F>,
+}
+
+impl<F: PrimeField> RangeStartGate<F> {
+    pub fn compute_in_coset(omega_i: usize, range: &[F], l0: &[F]) -> F {
+        l0[omega_i] * range[omega_i]
+    }
+}
+
+pub struct ComparisonGate<F: PrimeField> {
+    _f: PhantomData<F>,
+}
+
+impl<F: PrimeField> ComparisonGate<F> {
+    // attribute - threshold is the sum of NUMBER_OF_MIMC_ROUNDS bits, so attribute >= threshold
+    pub fn compute_in_coset(
+        omega_i: usize,
+        range: &[F],
+        attribute: &[F],
+        l0: &[F],
+        threshold: F, // public input
+    ) -> F {
+        let range_pow_n = positive_rotation_in_coset(
+            range,
+            omega_i,
+            NUMBER_OF_MIMC_ROUNDS,
+            EXTENDED_DOMAIN_FACTOR,
+        );
+        l0[omega_i] * (range_pow_n - attribute[omega_i] + threshold)
+    }
+}
+
+pub struct AttributeCopyGate<F: PrimeField> {
+    _f: PhantomData<F>,
+}
+
+impl<F: PrimeField> AttributeCopyGate<F> {
+    pub fn compute_in_coset(omega_i: usize, attribute: &[F], attribute_hash: &[F], l0: &[F]) -> F {
+        l0[omega_i] * (attribute_hash[omega_i] - attribute[omega_i])
+    }
+}
+
+pub struct AttributeHashGate<F: PrimeField> {
+    _f: PhantomData<F>,
+}
+
+impl<F: PrimeField> AttributeHashGate<F> {
+    // Only enforced if the reveal public input is 1
+    pub fn compute_in_coset(
+        omega_i: usize,
+        attribute_hash_col: &[F],
+        l0: &[F],
+        reveal: F,         // public input
+        attribute_hash: F, // public input
+    ) -> F {
+        let attribute_hash_pow_n = positive_rotation_in_coset(
+            attribute_hash_col,
+            omega_i,
+            NUMBER_OF_MIMC_ROUNDS,
+            EXTENDED_DOMAIN_FACTOR,
+        );
+        l0[omega_i] * reveal * (attribute_hash - attribute_hash_pow_n - attribute_hash_col[omega_i])
+    }
+}
+
+/*
+ * Constrains column k of the next row of a Poseidon state to equal row k of the MDS matrix applied
+ * to the current row, after adding the round constants and applying the S-box. In a full round
+ * (q_full) every state element goes through the S-box; in a partial round (q_partial) only the
+ * first one does. The rows selected by q_pass carry the state to the next row unchanged.
+ */
+pub struct PoseidonRoundGate<F: PrimeField> {
+    _f: PhantomData<F>,
+}
+
+impl<F: PrimeField> PoseidonRoundGate<F> {
+    #[allow(clippy::too_many_arguments)]
+    pub fn compute_in_coset(
+        omega_i: usize,
+        k: usize,
+        state: &[&[F]],
+        cts: &[&[F]],
+        mds: &[Vec<F>],
+        q_full: &[F],
+        q_partial: &[F],
+        q_pass: &[F],
+    ) -> F {
+        let pow_5 = |x: F| x.pow([5, 0, 0, 0]);
+
+        let s_next = positive_rotation_in_coset(state[k], omega_i, 1, EXTENDED_DOMAIN_FACTOR);
+
+        let mut full = F::zero();
+        let mut partial = F::zero();
+        for (j, (s, c)) in state.iter().zip(cts.iter()).enumerate() {
+            let x = s[omega_i] + c[omega_i];
+            let x_pow_5 = pow_5(x);
+            full += mds[k][j] * x_pow_5;
+            partial += mds[k][j] * if j == 0 { x_pow_5 } else { x };
+        }
+
+        q_full[omega_i] * (full - s_next)
+            + q_partial[omega_i] * (partial - s_next)
+            + q_pass[omega_i] * (state[k][omega_i] - s_next)
+    }
+}
+
+pub struct PoseidonCapacityGate<F: PrimeField> {
+    _f: PhantomData<F>,
+}
+
+impl<F: PrimeField> PoseidonCapacityGate<F> {
+    // The capacity element of the initial Poseidon state must be 0
+    pub fn compute_in_coset(omega_i: usize, capacity: &[F], l0: &[F]) -> F {
+        l0[omega_i] * capacity[omega_i]
+    }
+}
+
+pub struct PoseidonInputCopyGate<F: PrimeField> {
+    _f: PhantomData<F>,
+}
+
+impl<F: PrimeField> PoseidonInputCopyGate<F> {
+    // The identity nullifier is the first input to the identity secret and the second input to
+    // the nullifier hash
+    pub fn compute_in_coset(
+        omega_i: usize,
+        identity_secret_input: &[F],
+        nullifier_hash_input: &[F],
+        l0: &[F],
+    ) -> F {
+        l0[omega_i] * (identity_secret_input[omega_i] - nullifier_hash_input[omega_i])
+    }
+}
+
+pub struct PoseidonSecretCopyGate<F: PrimeField> {
+    _f: PhantomData<F>,
+}
+
+impl<F: PrimeField> PoseidonSecretCopyGate<F> {
+    // The output of the identity secret instance, which is carried to row NUMBER_OF_MIMC_ROUNDS,
+    // is the input to the identity commitment instance
+    pub fn compute_in_coset(
+        omega_i: usize,
+        identity_secret_capacity: &[F],
+        identity_commitment_input: &[F],
+        l0: &[F],
+    ) -> F {
+        let identity_secret = positive_rotation_in_coset(
+            identity_secret_capacity,
+            omega_i,
+            NUMBER_OF_MIMC_ROUNDS,
+            EXTENDED_DOMAIN_FACTOR,
+        );
+        l0[omega_i] * (identity_commitment_input[omega_i] - identity_secret)
+    }
+}
+
+pub struct PoseidonNullifierHashGate<F: PrimeField> {
+    _f: PhantomData<F>,
+}
+
+impl<F: PrimeField> PoseidonNullifierHashGate<F> {
+    // The output of the nullifier hash instance is carried to row NUMBER_OF_MIMC_ROUNDS
+    pub fn compute_in_coset(
+        omega_i: usize,
+        nullifier_hash_capacity: &[F],
+        l0: &[F],
+        nullifier_hash: F, // public input
+    ) -> F {
+        let output = positive_rotation_in_coset(
+            nullifier_hash_capacity,
+            omega_i,
+            NUMBER_OF_MIMC_ROUNDS,
+            EXTENDED_DOMAIN_FACTOR,
+        );
+        l0[omega_i] * (nullifier_hash - output)
+    }
+}
+
 #[cfg(test)]
 mod tests;
@@ -4,12 +4,25 @@ use crate::gates::gate_sanity_checks::{
     gate_6_nullifier_hash_final as gate_6_nullifier_hash_final_check,
     gate_7_key_col as gate_7_key_col_check,
     gate_8_nullifier_hash_col as gate_8_nullifier_hash_col_check, gate_9 as gate_9_check,
-    mimc as mimc_check,
+    mimc as mimc_check, poseidon_round as poseidon_round_check,
 };
 use crate::gates::utils::{fill_blinds, fill_dummy, fill_zeroes};
+use crate::gates::{
+    AttributeCopyGate, AttributeHashGate, AttributeKeyCopyGate, ComparisonGate,
+    ExternalNullifierGate, KeyCopyGate, KeyEqualityGate, Mimc7RoundGate, PoseidonCapacityGate,
+    PoseidonInputCopyGate, PoseidonNullifierHashGate, PoseidonRoundGate, PoseidonSecretCopyGate,
+    RangeBitGate, RangeStartGate, RlnExternalNullifierGate, RlnMessageLimitGate, RlnNullifierGate,
+    RlnShareGate, RlnSlopeGate,
+};
+use crate::hasher::IdentityHasher;
+use crate::layouter::Layouter;
 use crate::mimc7::{compute_round_digests, init_mimc7, Mimc7};
+use crate::poseidon::init_semaphore_poseidon;
 use ark_bn254::Fr as F;
 use ark_ff::{field_new, One, PrimeField, Zero};
+use ark_poly::{
+    univariate::DensePolynomial, EvaluationDomain, GeneralEvaluationDomain, UVPolynomial,
+};
 use ark_std::test_rng;
 use rand::rngs::StdRng;
 
@@ -589,3 +602,310 @@ fn key_constant() {
 
     gate_10_key_constant_check(&q_key_evals, &key_evals, test_vals.dummy, domain_size);
 }
+
+#[test]
+fn poseidon_round() {
+    /*
+       For each state column k:
+
+       q_full * (sum_j(M[k][j] * (s_j + c_j)^5) - s_k_next) +
+       q_partial * (M[k][0] * (s_0 + c_0)^5 + sum_{j > 0}(M[k][j] * (s_j + c_j)) - s_k_next) +
+       q_pass * (s_k - s_k_next)
+    */
+    let mut rng = test_rng();
+    let domain_size = 128;
+    let dummy = F::from(12345u64);
+    let poseidon = init_semaphore_poseidon::<F>();
+
+    let assignment =
+        Layouter::assign_poseidon(F::from(1), F::from(2), F::from(3), &poseidon, &mut rng);
+
+    for (state_evals, params) in [
+        (&assignment.identity_secret, &poseidon.poseidon),
+        (&assignment.identity_commitment, &poseidon.poseidon_single),
+        (&assignment.nullifier_hash, &poseidon.poseidon),
+    ] {
+        let fixed = Layouter::assign_poseidon_fixed(params);
+        poseidon_round_check(
+            &fixed.q_full,
+            &fixed.q_partial,
+            &fixed.q_pass,
+            state_evals,
+            &fixed.cts,
+            &fixed.mds,
+            dummy,
+            domain_size,
+        );
+    }
+}
+
+#[test]
+fn poseidon_round_in_coset() {
+    // The gate evaluated over the extended coset should be divisible by the vanishing polynomial
+    // of the subgroup
+    let mut rng = test_rng();
+    let domain_size = 128;
+    let poseidon = init_semaphore_poseidon::<F>();
+
+    let domain = GeneralEvaluationDomain::<F>::new(domain_size).unwrap();
+    let extended_coset_domain = GeneralEvaluationDomain::<F>::new(8 * domain_size).unwrap();
+    let to_coset_evals = |evals: &[F]| {
+        let poly = DensePolynomial::from_coefficients_slice(&domain.ifft(evals));
+        extended_coset_domain.coset_fft(&poly)
+    };
+
+    let assignment =
+        Layouter::assign_poseidon(F::from(1), F::from(2), F::from(3), &poseidon, &mut rng);
+
+    for (instance, params) in [
+        (&assignment.identity_secret, &poseidon.poseidon),
+        (&assignment.identity_commitment, &poseidon.poseidon_single),
+        (&assignment.nullifier_hash, &poseidon.poseidon),
+    ] {
+        let fixed = Layouter::assign_poseidon_fixed(params);
+        let q_full = to_coset_evals(&fixed.q_full);
+        let q_partial = to_coset_evals(&fixed.q_partial);
+        let q_pass = to_coset_evals(&fixed.q_pass);
+        let c: Vec<Vec<F>> = fixed.cts.iter().map(|e| to_coset_evals(e)).collect();
+        let c: Vec<&[F]> = c.iter().map(|e| e.as_slice()).collect();
+
+        let state: Vec<Vec<F>> = instance.iter().map(|e| to_coset_evals(e)).collect();
+        let state: Vec<&[F]> = state.iter().map(|e| e.as_slice()).collect();
+
+        for k in 0..params.width {
+            let numerator_evals: Vec<F> = (0..extended_coset_domain.size())
+                .map(|i| {
+                    PoseidonRoundGate::compute_in_coset(
+                        i, k, &state, &c, &fixed.mds, &q_full, &q_partial, &q_pass,
+                    )
+                })
+                .collect();
+            let numerator = DensePolynomial::from_coefficients_slice(
+                &extended_coset_domain.coset_ifft(&numerator_evals),
+            );
+            let (_, r) = numerator.divide_by_vanishing_poly(domain).unwrap();
+            assert!(r.is_zero());
+        }
+    }
+}
+
+#[test]
+fn poseidon_gates_in_coset() {
+    // The Poseidon capacity, input copy, secret copy and nullifier hash gates evaluated over the
+    // extended coset should be divisible by the vanishing polynomial of the subgroup, unless the
+    // public nullifier hash is wrong
+    let mut rng = test_rng();
+    let domain_size = 128;
+    let poseidon = init_semaphore_poseidon::<F>();
+
+    let domain = GeneralEvaluationDomain::<F>::new(domain_size).unwrap();
+    let extended_coset_domain = GeneralEvaluationDomain::<F>::new(8 * domain_size).unwrap();
+    let to_coset_evals = |evals: &[F]| {
+        let poly = DensePolynomial::from_coefficients_slice(&domain.ifft(evals));
+        extended_coset_domain.coset_fft(&poly)
+    };
+
+    let identity_nullifier = F::from(1);
+    let external_nullifier = F::from(3);
+    let nullifier_hash = poseidon.nullifier_hash(identity_nullifier, external_nullifier);
+
+    let assignment = Layouter::assign_poseidon(
+        identity_nullifier,
+        F::from(2),
+        external_nullifier,
+        &poseidon,
+        &mut rng,
+    );
+    let to_instance_coset_evals = |instance: &[Vec<F>]| -> Vec<Vec<F>> {
+        instance.iter().map(|e| to_coset_evals(e)).collect()
+    };
+    let s = to_instance_coset_evals(&assignment.identity_secret);
+    let t = to_instance_coset_evals(&assignment.identity_commitment);
+    let u = to_instance_coset_evals(&assignment.nullifier_hash);
+
+    let mut l0_evals = vec![F::zero(); domain_size];
+    l0_evals[0] = F::one();
+    let l0 = to_coset_evals(&l0_evals);
+
+    let remainder = |nullifier_hash: F| {
+        let numerator_evals: Vec<F> = (0..extended_coset_domain.size())
+            .map(|i| {
+                PoseidonCapacityGate::compute_in_coset(i, &s[0], &l0)
+                    + PoseidonCapacityGate::compute_in_coset(i, &t[0], &l0)
+                    + PoseidonCapacityGate::compute_in_coset(i, &u[0], &l0)
+                    + PoseidonInputCopyGate::compute_in_coset(i, &s[1], &u[2], &l0)
+                    + PoseidonSecretCopyGate::compute_in_coset(i, &s[0], &t[1], &l0)
+                    + PoseidonNullifierHashGate::compute_in_coset(i, &u[0], &l0, nullifier_hash)
+                    + ExternalNullifierGate::compute_in_coset(i, &u[1], &l0, external_nullifier)
+            })
+            .collect();
+        let numerator = DensePolynomial::from_coefficients_slice(
+            &extended_coset_domain.coset_ifft(&numerator_evals),
+        );
+        let (_, r) = numerator.divide_by_vanishing_poly(domain).unwrap();
+        r
+    };
+
+    assert!(remainder(nullifier_hash).is_zero());
+    assert!(!remainder(nullifier_hash + F::one()).is_zero());
+}
+
+#[test]
+fn rln_gates_in_coset() {
+    // The RLN gates evaluated over the extended coset should be divisible by the vanishing
+    // polynomial of the subgroup
+    let mut rng = test_rng();
+    let domain_size = 128;
+    let mimc7 = init_mimc7::<F>();
+
+    let domain = GeneralEvaluationDomain::<F>::new(domain_size).unwrap();
+    let extended_coset_domain = GeneralEvaluationDomain::<F>::new(8 * domain_size).unwrap();
+    let to_coset_evals = |evals: &[F]| {
+        let poly = DensePolynomial::from_coefficients_slice(&domain.ifft(evals));
+        extended_coset_domain.coset_fft(&poly)
+    };
+
+    let identity_nullifier = F::from(1);
+    let epoch = F::from(3);
+    let message_id = 1;
+    let message_limit = F::from(2);
+    let external_nullifier = mimc7.multi_hash(&[epoch, F::from(message_id)], F::zero());
+    let x = F::from(4);
+    let a1 = mimc7.multi_hash(&[identity_nullifier, external_nullifier], F::zero());
+    let y = identity_nullifier + a1 * x;
+    let rln_nullifier = mimc7.multi_hash(&[a1], F::zero());
+
+    let rln_assignment = Layouter::assign_rln(
+        identity_nullifier,
+        F::from(2),
+        epoch,
+        message_id,
+        2,
+        &mimc7.cts,
+        &mut rng,
+    )
+    .unwrap();
+    let w0 = to_coset_evals(&rln_assignment.assignment.nullifier);
+    let key = to_coset_evals(&rln_assignment.assignment.key);
+    let w2 = to_coset_evals(&rln_assignment.assignment.external_nullifier);
+    let w3 = to_coset_evals(&rln_assignment.internal_nullifier);
+    let e = to_coset_evals(&rln_assignment.epoch);
+    let k2 = to_coset_evals(&rln_assignment.message_key);
+    let m = to_coset_evals(&rln_assignment.message_id);
+    let r1 = to_coset_evals(&rln_assignment.message_id_range);
+    let r2 = to_coset_evals(&rln_assignment.message_limit_range);
+
+    let mut l0_evals = vec![F::zero(); domain_size];
+    l0_evals[0] = F::one();
+    let l0 = to_coset_evals(&l0_evals);
+
+    let mut q_mimc_evals = vec![F::one(); 91];
+    fill_zeroes(&mut q_mimc_evals, domain_size);
+    let q_mimc = to_coset_evals(&q_mimc_evals);
+    let mut c_evals = mimc7.cts.clone();
+    fill_zeroes(&mut c_evals, domain_size);
+    let c = to_coset_evals(&c_evals);
+    let zeroes = vec![F::zero(); extended_coset_domain.size()];
+
+    let numerator_evals: Vec<F> = (0..extended_coset_domain.size())
+        .map(|i| {
+            RlnSlopeGate::compute_in_coset(i, &w2, &key, &w3, &l0)
+                + RlnExternalNullifierGate::compute_in_coset(i, &w2, &k2, &m, &l0)
+                + RlnNullifierGate::compute_in_coset(i, &w3, &l0, rln_nullifier)
+                + RlnShareGate::compute_in_coset(i, &w0, &w3, &l0, x, y)
+                + Mimc7RoundGate::compute_in_coset(i, &e, &zeroes, &c, &q_mimc)
+                + ExternalNullifierGate::compute_in_coset(i, &e, &l0, epoch)
+                + KeyEqualityGate::compute_in_coset(i, &k2, &q_mimc)
+                + KeyCopyGate::compute_in_coset(i, &e, &k2, &l0)
+                + Mimc7RoundGate::compute_in_coset(i, &m, &k2, &c, &q_mimc)
+                + RangeBitGate::compute_in_coset(i, &r1, &q_mimc)
+                + RangeStartGate::compute_in_coset(i, &r1, &l0)
+                + ComparisonGate::compute_in_coset(i, &r1, &m, &l0, F::zero())
+                + RangeBitGate::compute_in_coset(i, &r2, &q_mimc)
+                + RangeStartGate::compute_in_coset(i, &r2, &l0)
+                + RlnMessageLimitGate::compute_in_coset(i, &r2, &m, &l0, message_limit)
+        })
+        .collect();
+    let numerator = DensePolynomial::from_coefficients_slice(
+        &extended_coset_domain.coset_ifft(&numerator_evals),
+    );
+    let (_, r) = numerator.divide_by_vanishing_poly(domain).unwrap();
+    assert!(r.is_zero());
+
+    // A message limit of 1 does not admit the message ID 1
+    let numerator_evals: Vec<F> = (0..extended_coset_domain.size())
+        .map(|i| RlnMessageLimitGate::compute_in_coset(i, &r2, &m, &l0, F::one()))
+        .collect();
+    let numerator = DensePolynomial::from_coefficients_slice(
+        &extended_coset_domain.coset_ifft(&numerator_evals),
+    );
+    let (_, r) = numerator.divide_by_vanishing_poly(domain).unwrap();
+    assert!(!r.is_zero());
+}
+
+#[test]
+fn attribute_gates_in_coset() {
+    // The attribute gates evaluated over the extended coset should be divisible by the vanishing
+    // polynomial of the subgroup
+    let mut rng = test_rng();
+    let domain_size = 128;
+    let mimc7 = init_mimc7::<F>();
+
+    let domain = GeneralEvaluationDomain::<F>::new(domain_size).unwrap();
+    let extended_coset_domain = GeneralEvaluationDomain::<F>::new(8 * domain_size).unwrap();
+    let to_coset_evals = |evals: &[F]| {
+        let poly = DensePolynomial::from_coefficients_slice(&domain.ifft(evals));
+        extended_coset_domain.coset_fft(&poly)
+    };
+
+    let attribute = F::from(3);
+    let threshold = F::from(2);
+    let attribute_hash = mimc7.multi_hash(&[attribute], F::zero());
+
+    let attribute_assignment = Layouter::assign_with_attribute(
+        F::from(1),
+        F::from(2),
+        attribute,
+        threshold,
+        F::from(4),
+        &mimc7.cts,
+        &mut rng,
+    )
+    .unwrap();
+    let w1 = to_coset_evals(&attribute_assignment.assignment.identity_commitment);
+    let key = to_coset_evals(&attribute_assignment.assignment.key);
+    let attribute_key = to_coset_evals(&attribute_assignment.attribute_key);
+    let attribute_col = to_coset_evals(&attribute_assignment.attribute);
+    let range = to_coset_evals(&attribute_assignment.range);
+    let attribute_hash_col = to_coset_evals(&attribute_assignment.attribute_hash);
+
+    let mut l0_evals = vec![F::zero(); domain_size];
+    l0_evals[0] = F::one();
+    let l0 = to_coset_evals(&l0_evals);
+
+    let mut q_mimc_evals = vec![F::one(); 91];
+    fill_zeroes(&mut q_mimc_evals, domain_size);
+    let q_mimc = to_coset_evals(&q_mimc_evals);
+
+    let numerator_evals: Vec<F> = (0..extended_coset_domain.size())
+        .map(|i| {
+            AttributeKeyCopyGate::compute_in_coset(i, &w1, &key, &attribute_key, &l0)
+                + RangeBitGate::compute_in_coset(i, &range, &q_mimc)
+                + RangeStartGate::compute_in_coset(i, &range, &l0)
+                + ComparisonGate::compute_in_coset(i, &range, &attribute_col, &l0, threshold)
+                + AttributeCopyGate::compute_in_coset(i, &attribute_col, &attribute_hash_col, &l0)
+                + AttributeHashGate::compute_in_coset(
+                    i,
+                    &attribute_hash_col,
+                    &l0,
+                    F::one(),
+                    attribute_hash,
+                )
+        })
+        .collect();
+    let numerator = DensePolynomial::from_coefficients_slice(
+        &extended_coset_domain.coset_ifft(&numerator_evals),
+    );
+    let (_, r) = numerator.divide_by_vanishing_poly(domain).unwrap();
+    assert!(r.is_zero());
+}
@@ -0,0 +1,108 @@
+use crate::mimc7::Mimc7;
+use crate::poseidon::SemaphorePoseidon;
+use ark_ff::PrimeField;
+
+/*
+ * The hash from which identity commitments and nullifier hashes are derived. The circuit which
+ * proves knowledge of the preimages depends on the implementation: MiMC7 commitments are proven
+ * with Prover::prove() over the assignment from Layouter::assign, and Semaphore v2 Poseidon
+ * commitments with Prover::prove_poseidon() over the assignment from Layouter::assign_poseidon.
+ */
+pub trait IdentityHasher<F: PrimeField> {
+    fn identity_commitment(&self, identity_nullifier: F, identity_trapdoor: F) -> F;
+
+    fn nullifier_hash(&self, identity_nullifier: F, external_nullifier: F) -> F;
+}
+
+impl<F: PrimeField> IdentityHasher<F> for Mimc7<F> {
+    fn identity_commitment(&self, identity_nullifier: F, identity_trapdoor: F) -> F {
+        self.multi_hash(&[identity_nullifier, identity_trapdoor], F::zero())
+    }
+
+    fn nullifier_hash(&self, identity_nullifier: F, external_nullifier: F) -> F {
+        self.multi_hash(&[identity_nullifier, external_nullifier], F::zero())
+    }
+}
+
+// The same formulas as the Semaphore v2 circuit, so that existing Semaphore identities can be
+// proven
+impl<F: PrimeField> IdentityHasher<F> for SemaphorePoseidon<F> {
+    fn identity_commitment(&self, identity_nullifier: F, identity_trapdoor: F) -> F {
+        let identity_secret = self.identity_secret(identity_nullifier, identity_trapdoor);
+        self.poseidon_single.hash(&[identity_secret])
+    }
+
+    fn nullifier_hash(&self, identity_nullifier: F, external_nullifier: F) -> F {
+        self.poseidon
+            .hash(&[external_nullifier, identity_nullifier])
+    }
+}
+
+#[cfg(test)]
+mod hasher_tests {
+    use super::IdentityHasher;
+    use crate::mimc7::init_mimc7;
+    use crate::poseidon::init_semaphore_poseidon;
+    use ark_bn254::Fr as F;
+    use ark_ff::{field_new, Zero};
+
+    #[test]
+    fn test_identity_hashers() {
+        let mimc7 = init_mimc7::<F>();
+        let poseidon = init_semaphore_poseidon::<F>();
+
+        let identity_nullifier = F::from(100u64);
+        let identity_trapdoor = F::from(200u64);
+        let external_nullifier = F::from(300u64);
+
+        assert_eq!(
+            mimc7.identity_commitment(identity_nullifier, identity_trapdoor),
+            mimc7.multi_hash(&[identity_nullifier, identity_trapdoor], F::zero())
+        );
+        assert_eq!(
+            poseidon.identity_commitment(identity_nullifier, identity_trapdoor),
+            poseidon.poseidon_single.hash(&[poseidon
+                .poseidon
+                .hash(&[identity_nullifier, identity_trapdoor])])
+        );
+        assert_eq!(
+            poseidon.nullifier_hash(identity_nullifier, external_nullifier),
+            poseidon
+                .poseidon
+                .hash(&[external_nullifier, identity_nullifier])
+        );
+        assert_ne!(
+            mimc7.identity_commitment(identity_nullifier, identity_trapdoor),
+            poseidon.identity_commitment(identity_nullifier, identity_trapdoor)
+        );
+    }
+
+    #[test]
+    fn test_semaphore_identity_commitment() {
+        let poseidon = init_semaphore_poseidon::<F>();
+
+        // The identity secret of an identity with nullifier 1 and trapdoor 2 is circomlibjs'
+        // poseidon([1, 2]), and its commitment is poseidon([secret]), as computed by Semaphore v2
+        let identity_secret = poseidon.identity_secret(F::from(1), F::from(2));
+        assert_eq!(
+            identity_secret,
+            field_new!(
+                F,
+                "7853200120776062878684798364095072458815029376092732009249414926327459813530"
+            )
+        );
+        assert_eq!(
+            poseidon.identity_commitment(F::from(1), F::from(2)),
+            field_new!(
+                F,
+                "1726140942480881257963748121685659126946424978635264596106980875531445116889"
+            )
+        );
+
+        // The nullifier hash hashes the external nullifier first
+        assert_eq!(
+            poseidon.nullifier_hash(F::from(2), F::from(1)),
+            identity_secret
+        );
+    }
+}
@@ -0,0 +1,152 @@
+/*
+   Hidden-value insertion. insertIdentity() emits the plaintext identity commitment, so the member
+   list is public. Instead, the inserter may submit the point P = (v - zero) * [L_i]_1 which the
+   contract adds to the accumulator, together with a Schnorr proof of knowledge of v - zero
+   relative to [L_i]_1. The value v stays off-chain, and is shared only with the precompute
+   providers which compute the W1 values, since the prover needs the full table.
+
+   The proof shows that P is a multiple of [L_i]_1, so P only changes slot i of the table, and that
+   the inserter knows the value which it puts there. It does not show that v is a well-formed
+   identity commitment; a member with a malformed value simply cannot prove membership.
+
+   The Schnorr challenge is derived with the keccak256 transcript from the index, [L_i]_1, P and
+   the commitment R, so a proof cannot be replayed at another index.
+*/
+use crate::accumulator::Accumulator;
+use crate::error::Error;
+use crate::transcript::Transcript;
+use ark_bn254::{Bn254, Fr, G1Affine};
+use ark_ec::{AffineCurve, ProjectiveCurve};
+use ark_ff::UniformRand;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Read, SerializationError, Write};
+use rand::RngCore;
+
+// A Schnorr proof of knowledge of s such that point = s * [L_i]_1
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InsertionProof {
+    pub r: G1Affine,
+    pub z: Fr,
+}
+
+// The data of a hidden-value insertion, which replaces the identity commitment in the transaction
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HiddenInsertion {
+    pub index: u64,
+    pub point: G1Affine,
+    pub proof: InsertionProof,
+}
+
+fn compute_challenge(index: u64, lagrange_comm: &G1Affine, point: &G1Affine, r: &G1Affine) -> Fr {
+    let mut transcript = Transcript::new_transcript();
+    transcript.update_with_f(Fr::from(index));
+    transcript.update_with_g1(lagrange_comm);
+    transcript.update_with_g1(point);
+    transcript.update_with_g1(r);
+    transcript.get_challenge()
+}
+
+impl HiddenInsertion {
+    /*
+     * Compute the point which inserts value into the given slot, and prove that it is well-formed.
+     * @param index: The index of the slot, which must be empty.
+     * @param lagrange_comm: The commitment to the Lagrange basis polynomial of the index.
+     * @param zero: The zero leaf.
+     * @param value: The hidden value, e.g. an identity commitment.
+     * @param rng: The random number generator for the Schnorr nonce.
+     */
+    pub fn new<R: RngCore>(
+        index: usize,
+        lagrange_comm: G1Affine,
+        zero: Fr,
+        value: Fr,
+        rng: &mut R,
+    ) -> Self {
+        let s = value - zero;
+        let point = lagrange_comm.mul(s).into_affine();
+
+        let k = Fr::rand(rng);
+        let r = lagrange_comm.mul(k).into_affine();
+        let e = compute_challenge(index as u64, &lagrange_comm, &point, &r);
+        let z = k + e * s;
+
+        Self {
+            index: index as u64,
+            point,
+            proof: InsertionProof { r, z },
+        }
+    }
+
+    /*
+     * Check that z * [L_i]_1 = R + e * P.
+     * @param lagrange_comm: The commitment to the Lagrange basis polynomial of self.index, which
+     *                       the contract checks against the Lagrange tree root.
+     */
+    pub fn verify(&self, lagrange_comm: &G1Affine) -> bool {
+        let e = compute_challenge(self.index, lagrange_comm, &self.point, &self.proof.r);
+
+        let lhs = lagrange_comm.mul(self.proof.z);
+        let rhs = self.proof.r.into_projective() + self.point.mul(e);
+
+        lhs == rhs
+    }
+
+    // Verify the insertion and add its point to the accumulator
+    pub fn apply(&self, accumulator: &mut Accumulator<Bn254>) -> Result<(), Error> {
+        let index = self.index as usize;
+        if index >= accumulator.lagrange_comms.len() {
+            return Err(Error::InvalidInsertionProof);
+        }
+        if !self.verify(&accumulator.lagrange_comms[index]) {
+            return Err(Error::InvalidInsertionProof);
+        }
+
+        accumulator.update_with_point(self.point);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod hidden_insertion_tests {
+    use super::HiddenInsertion;
+    use crate::accumulator::{commit_to_lagrange_bases, compute_zero_leaf, Accumulator};
+    use crate::error::Error;
+    use crate::kzg::unsafe_setup_g1;
+    use ark_bn254::{Bn254, Fr};
+    use ark_std::{rand::rngs::StdRng, test_rng};
+
+    #[test]
+    fn test_hidden_insertion() {
+        let table_size = 8;
+        let mut rng = test_rng();
+
+        let srs_g1 = unsafe_setup_g1::<Bn254, StdRng>(table_size, &mut rng);
+        let zero = compute_zero_leaf::<Fr>();
+        let lagrange_comms = commit_to_lagrange_bases::<Bn254>(table_size, &srs_g1);
+
+        let mut acc = Accumulator::<Bn254>::new(zero, &lagrange_comms);
+        let mut expected = Accumulator::<Bn254>::new(zero, &lagrange_comms);
+
+        // Applying the point is the same as inserting the value
+        let insertion = HiddenInsertion::new(2, lagrange_comms[2], zero, Fr::from(123), &mut rng);
+        assert!(insertion.verify(&lagrange_comms[2]));
+        insertion.apply(&mut acc).unwrap();
+        expected.update(2, Fr::from(123));
+        assert_eq!(acc.point, expected.point);
+
+        // The proof is bound to the index
+        assert!(!insertion.verify(&lagrange_comms[3]));
+        let moved = HiddenInsertion {
+            index: 3,
+            ..insertion
+        };
+        assert_eq!(moved.apply(&mut acc), Err(Error::InvalidInsertionProof));
+
+        // The proof is bound to the point
+        let tampered = HiddenInsertion {
+            point: (insertion.point + lagrange_comms[3]),
+            ..insertion
+        };
+        assert_eq!(tampered.apply(&mut acc), Err(Error::InvalidInsertionProof));
+        assert_eq!(acc.point, expected.point);
+    }
+}
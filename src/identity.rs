@@ -0,0 +1,332 @@
+use crate::error::Error;
+use crate::hasher::IdentityHasher;
+use crate::mimc7::init_mimc7;
+use crate::poseidon::init_semaphore_poseidon;
+use ark_bn254::Fr;
+use ark_ff::{BigInteger, BigInteger256, FromBytes, PrimeField, UniformRand, Zero};
+use ethers::core::utils::keccak256;
+use ethers::signers::Signer;
+use ethers::types::Signature;
+use rand::{CryptoRng, Rng};
+use std::path::Path;
+use tiny_keccak::{Hasher, Keccak};
+
+// The message which a wallet signs to derive its Semacaulk identity
+pub const IDENTITY_DERIVATION_MESSAGE: &str =
+    "Sign this message to generate your Semacaulk identity. Only sign it on a trusted website.";
+
+/*
+ * A Semacaulk identity: the identity nullifier and identity trapdoor, whose MiMC7 multihash is the
+ * identity commitment stored in the accumulator.
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Identity {
+    pub nullifier: Fr,
+    pub trapdoor: Fr,
+}
+
+impl Identity {
+    pub fn new(nullifier: Fr, trapdoor: Fr) -> Self {
+        Self {
+            nullifier,
+            trapdoor,
+        }
+    }
+
+    pub fn random<R: Rng + ?Sized>(rng: &mut R) -> Self {
+        Self::new(Fr::rand(rng), Fr::rand(rng))
+    }
+
+    /*
+     * Deterministically derive an identity from a secret seed. The nullifier and trapdoor are
+     * keccak256(seed || "identity_nullifier") and keccak256(seed || "identity_trapdoor"), reduced
+     * modulo the scalar field order.
+     * @param seed: The secret seed. Anyone who knows it can recompute the identity.
+     */
+    pub fn from_seed(seed: &[u8]) -> Self {
+        let derive = |domain: &[u8]| {
+            let mut hasher = Keccak::v256();
+            let mut hash = [0u8; 32];
+            hasher.update(seed);
+            hasher.update(domain);
+            hasher.finalize(&mut hash);
+            Fr::from_be_bytes_mod_order(&hash)
+        };
+
+        Self::new(derive(b"identity_nullifier"), derive(b"identity_trapdoor"))
+    }
+
+    /*
+     * Derive an identity from a wallet's signature over IDENTITY_DERIVATION_MESSAGE. ECDSA
+     * signatures from ethers wallets are deterministic (RFC 6979), so the same wallet always
+     * derives the same identity.
+     */
+    pub fn from_signature(signature: &Signature) -> Self {
+        Self::from_seed(&signature.to_vec())
+    }
+
+    pub async fn from_wallet<S: Signer>(signer: &S) -> Result<Self, Error> {
+        let signature = signer
+            .sign_message(IDENTITY_DERIVATION_MESSAGE)
+            .await
+            .map_err(|e| Error::IdentityDerivationError(e.to_string()))?;
+        Ok(Self::from_signature(&signature))
+    }
+
+    pub fn commitment(&self) -> Fr {
+        let mimc7 = init_mimc7::<Fr>();
+        mimc7.multi_hash(&[self.nullifier, self.trapdoor], Fr::zero())
+    }
+
+    /*
+     * The commitment of an identity which carries an attribute, such as a membership tier or a
+     * join epoch. This is the MiMC7 multihash of the nullifier, the trapdoor and the attribute, and
+     * is inserted into the accumulator in place of commitment().
+     */
+    pub fn commitment_with_attribute(&self, attribute: Fr) -> Fr {
+        let mimc7 = init_mimc7::<Fr>();
+        mimc7.multi_hash(&[self.nullifier, self.trapdoor, attribute], Fr::zero())
+    }
+
+    pub fn nullifier_hash(&self, external_nullifier: Fr) -> Fr {
+        let mimc7 = init_mimc7::<Fr>();
+        mimc7.multi_hash(&[self.nullifier, external_nullifier], Fr::zero())
+    }
+
+    /*
+     * The Semaphore v2 commitment of the identity, i.e. Poseidon(Poseidon(nullifier, trapdoor)),
+     * which Prover::prove_poseidon() proves membership of.
+     */
+    pub fn poseidon_commitment(&self) -> Fr {
+        init_semaphore_poseidon::<Fr>().identity_commitment(self.nullifier, self.trapdoor)
+    }
+
+    // The Semaphore v2 nullifier hash, i.e. Poseidon(external_nullifier, nullifier)
+    pub fn poseidon_nullifier_hash(&self, external_nullifier: Fr) -> Fr {
+        init_semaphore_poseidon::<Fr>().nullifier_hash(self.nullifier, external_nullifier)
+    }
+
+    /*
+     * Serialise the identity in the format of Semaphore's Identity.toString(): a JSON array of
+     * the trapdoor and the nullifier as 0x-prefixed hexadecimal strings without leading zeroes.
+     */
+    pub fn to_semaphore_string(&self) -> String {
+        format!(
+            "[\"{}\",\"{}\"]",
+            fr_to_short_hex(self.trapdoor),
+            fr_to_short_hex(self.nullifier)
+        )
+    }
+
+    /*
+     * Parse an identity string produced by to_semaphore_string() or by Semaphore's
+     * Identity.toString().
+     */
+    pub fn from_semaphore_string(s: &str) -> Result<Self, Error> {
+        let values: Vec<&str> = s
+            .trim()
+            .strip_prefix('[')
+            .and_then(|s| s.strip_suffix(']'))
+            .ok_or(Error::InvalidIdentityString)?
+            .split(',')
+            .map(|v| v.trim().trim_matches('"'))
+            .collect();
+        if values.len() != 2 {
+            return Err(Error::InvalidIdentityString);
+        }
+
+        let trapdoor = short_hex_to_fr(values[0]).ok_or(Error::InvalidIdentityString)?;
+        let nullifier = short_hex_to_fr(values[1]).ok_or(Error::InvalidIdentityString)?;
+
+        Ok(Self::new(nullifier, trapdoor))
+    }
+
+    /*
+     * Encrypt the identity with a password and write it to dir as a Web3 Secret Storage
+     * (scrypt and AES-128-CTR) keystore file. The secret is the nullifier followed by the trapdoor,
+     * each as 32 big-endian bytes.
+     * @param dir: The directory to write the keystore file to.
+     * @param password: The password.
+     * @param name: The file name. If None, a random UUID is used.
+     * @return The name of the keystore file.
+     */
+    pub fn export_encrypted<P: AsRef<Path>, R: Rng + CryptoRng>(
+        &self,
+        dir: P,
+        password: &str,
+        name: Option<&str>,
+        rng: &mut R,
+    ) -> Result<String, Error> {
+        let mut secret = self.nullifier.into_repr().to_bytes_be();
+        secret.extend(self.trapdoor.into_repr().to_bytes_be());
+
+        eth_keystore::encrypt_key(dir, rng, &secret, password, name)
+            .map_err(|e| Error::IdentityKeystoreError(e.to_string()))
+    }
+
+    pub fn import_encrypted<P: AsRef<Path>>(path: P, password: &str) -> Result<Self, Error> {
+        let secret = eth_keystore::decrypt_key(path, password)
+            .map_err(|e| Error::IdentityKeystoreError(e.to_string()))?;
+        if secret.len() != 64 {
+            return Err(Error::IdentityKeystoreError(String::from(
+                "the keystore does not hold a Semacaulk identity",
+            )));
+        }
+
+        let nullifier = bytes_to_fr(&secret[..32]);
+        let trapdoor = bytes_to_fr(&secret[32..]);
+        match (nullifier, trapdoor) {
+            (Some(nullifier), Some(trapdoor)) => Ok(Self::new(nullifier, trapdoor)),
+            _ => Err(Error::IdentityKeystoreError(String::from(
+                "the keystore does not hold a Semacaulk identity",
+            ))),
+        }
+    }
+}
+
+/*
+ * The hash of an attribute which a prover may reveal as a public input of an attribute proof.
+ */
+pub fn attribute_hash(attribute: Fr) -> Fr {
+    let mimc7 = init_mimc7::<Fr>();
+    mimc7.multi_hash(&[attribute], Fr::zero())
+}
+
+/*
+ * The external nullifier of rotation proofs, keccak256("Semacaulk rotation") mod p. As it is
+ * fixed, an identity yields a single rotation nullifier hash, so it can only be rotated once.
+ */
+pub fn rotation_external_nullifier<F: PrimeField>() -> F {
+    F::from_be_bytes_mod_order(&keccak256("Semacaulk rotation".as_bytes()))
+}
+
+fn fr_to_short_hex(val: Fr) -> String {
+    let h = hex::encode(val.into_repr().to_bytes_be());
+    let h = h.trim_start_matches('0');
+    if h.is_empty() {
+        String::from("0x0")
+    } else {
+        format!("0x{}", h)
+    }
+}
+
+// Parse a 0x-prefixed hexadecimal string of any length, rejecting values which are not less than
+// the field order
+fn short_hex_to_fr(s: &str) -> Option<Fr> {
+    let h = s.strip_prefix("0x")?;
+    if h.is_empty() || h.len() > 64 {
+        return None;
+    }
+
+    let padded = format!("{:0>64}", h);
+    bytes_to_fr(&hex::decode(padded).ok()?)
+}
+
+// Convert 32 big-endian bytes to a field element, rejecting values which are out of range
+fn bytes_to_fr(bytes: &[u8]) -> Option<Fr> {
+    let le: Vec<u8> = bytes.iter().copied().rev().collect();
+    Fr::from_repr(BigInteger256::read(le.as_slice()).ok()?)
+}
+
+#[cfg(test)]
+mod identity_tests {
+    use super::{Identity, IDENTITY_DERIVATION_MESSAGE};
+    use crate::bn_solidity_utils::f_to_hex;
+    use crate::error::Error;
+    use ark_bn254::Fr;
+    use ark_std::test_rng;
+    use ethers::signers::LocalWallet;
+
+    #[test]
+    fn test_semaphore_string() {
+        let identity = Identity::new(Fr::from(1u64), Fr::from(0x1234u64));
+        let s = identity.to_semaphore_string();
+        assert_eq!(s, "[\"0x1234\",\"0x1\"]");
+        assert_eq!(Identity::from_semaphore_string(&s).unwrap(), identity);
+
+        let rng = &mut test_rng();
+        let identity = Identity::random(rng);
+        let s = identity.to_semaphore_string();
+        assert_eq!(Identity::from_semaphore_string(&s).unwrap(), identity);
+
+        // The field order itself is out of range
+        let p = "[\"0x30644e72e131a029b85045b68181585d2833e84879b9709143e1f593f0000001\",\"0x1\"]";
+        assert_eq!(
+            Identity::from_semaphore_string(p).unwrap_err(),
+            Error::InvalidIdentityString
+        );
+        assert_eq!(
+            Identity::from_semaphore_string("[\"0x1\"]").unwrap_err(),
+            Error::InvalidIdentityString
+        );
+    }
+
+    #[test]
+    fn test_derivation() {
+        assert_eq!(Identity::from_seed(b"seed"), Identity::from_seed(b"seed"));
+        assert_ne!(Identity::from_seed(b"seed"), Identity::from_seed(b"seed2"));
+
+        let wallet: LocalWallet =
+            "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80"
+                .parse()
+                .unwrap();
+        let hash = ethers::utils::hash_message(IDENTITY_DERIVATION_MESSAGE);
+
+        // The same wallet always derives the same identity
+        assert_eq!(
+            Identity::from_signature(&wallet.sign_hash(hash)),
+            Identity::from_signature(&wallet.sign_hash(hash))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_from_wallet() {
+        // The first anvil account
+        let wallet: LocalWallet =
+            "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80"
+                .parse()
+                .unwrap();
+        let identity = Identity::from_wallet(&wallet).await.unwrap();
+
+        // from_wallet() signs IDENTITY_DERIVATION_MESSAGE as an Ethereum signed message
+        let hash = ethers::utils::hash_message(IDENTITY_DERIVATION_MESSAGE);
+        assert_eq!(identity, Identity::from_signature(&wallet.sign_hash(hash)));
+        assert_eq!(identity, Identity::from_wallet(&wallet).await.unwrap());
+
+        // Pin the derivation, so that a change to it, which would orphan every identity derived
+        // from a wallet, fails this test
+        assert_eq!(
+            f_to_hex(identity.nullifier),
+            "18DCF04E80E39BD4157842FF02E9A0811F11066A8D989E72D9F260F57EF2E5F6"
+        );
+        assert_eq!(
+            f_to_hex(identity.trapdoor),
+            "18795594EA194E8183A79F39AC7EB81187768B611DA26DA6E51372C80EC71DA6"
+        );
+
+        let other: LocalWallet = "59c6995e998f97a5a0044966f0945389dc9e86dae88c7a8412f4603b6b78690d"
+            .parse()
+            .unwrap();
+        assert_ne!(identity, Identity::from_wallet(&other).await.unwrap());
+    }
+
+    #[test]
+    fn test_encrypted_export() {
+        let rng = &mut rand::thread_rng();
+        let identity = Identity::random(rng);
+        let dir = std::env::temp_dir();
+
+        let name = identity
+            .export_encrypted(&dir, "password", None, rng)
+            .unwrap();
+        let path = dir.join(&name);
+
+        assert_eq!(
+            Identity::import_encrypted(&path, "password").unwrap(),
+            identity
+        );
+        assert!(Identity::import_encrypted(&path, "wrong password").is_err());
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
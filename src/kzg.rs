@@ -1,8 +1,10 @@
-use ark_ec::{msm::VariableBaseMSM, AffineCurve, PairingEngine};
-use ark_ff::{One, PrimeField};
+use ark_ec::{msm::VariableBaseMSM, AffineCurve, PairingEngine, ProjectiveCurve};
+use ark_ff::{BigInteger, FpParameters, One, PrimeField, Zero};
 use ark_poly::{univariate::DensePolynomial, Polynomial, UVPolynomial};
-use ark_std::UniformRand;
+use ark_std::{cfg_chunks, cfg_iter, UniformRand};
 use rand::RngCore;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 use std::{cmp::max, iter};
 
 // Unsafe setup only for G1 points
@@ -63,10 +65,31 @@ pub fn commit<G: AffineCurve>(srs: &[G], poly: &DensePolynomial<G::ScalarField>)
     VariableBaseMSM::multi_scalar_mul(srs, &coeff_scalars)
 }
 
+// Commit with a fixed-base table of a prefix of the SRS if the polynomial fits in it
+pub fn commit_with_table<G: AffineCurve>(
+    srs: &[G],
+    table: Option<&FixedBaseMsmTable<G>>,
+    poly: &DensePolynomial<G::ScalarField>,
+) -> G::Projective {
+    match table {
+        Some(table) if poly.coeffs.len() <= table.len() => table.commit(poly),
+        _ => commit(srs, poly),
+    }
+}
+
 pub fn open<G: AffineCurve>(
     srs: &[G],
     poly: &DensePolynomial<G::ScalarField>,
     challenge: G::ScalarField,
+) -> (G::ScalarField, G) {
+    open_with_table(srs, None, poly, challenge)
+}
+
+pub fn open_with_table<G: AffineCurve>(
+    srs: &[G],
+    table: Option<&FixedBaseMsmTable<G>>,
+    poly: &DensePolynomial<G::ScalarField>,
+    challenge: G::ScalarField,
 ) -> (G::ScalarField, G) {
     let q = poly / &DensePolynomial::from_coefficients_slice(&[-challenge, G::ScalarField::one()]);
     if srs.len() - 1 < q.degree() {
@@ -76,6 +99,203 @@ pub fn open<G: AffineCurve>(
             srs.len()
         );
     }
-    let proof = commit(srs, &q);
+    let proof = commit_with_table(srs, table, &q);
     (poly.evaluate(&challenge), proof.into())
 }
+
+/*
+ * A precomputed table for multi-scalar multiplications over fixed bases, such as a prefix of the
+ * SRS. Each scalar is split into signed digits of window_size bits, so that
+ * Σ_i s_i * P_i = Σ_i Σ_j d_ij * (2^(window_size * j) * P_i). The table holds every
+ * 2^(window_size * j) * P_i in affine form, so a multiplication is a single pass of bucket
+ * additions over all the digits, without the doublings and per-window bucket sums of
+ * VariableBaseMSM. The table has (254 / window_size + 1) points per base.
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FixedBaseMsmTable<G: AffineCurve> {
+    window_size: usize,
+    num_windows: usize,
+    // table[i * num_windows + j] = 2^(window_size * j) * bases[i]
+    table: Vec<G>,
+}
+
+impl<G: AffineCurve> FixedBaseMsmTable<G> {
+    // Build the table with the window size which minimises the number of additions
+    pub fn new(bases: &[G]) -> Self {
+        let num_bits = <G::ScalarField as PrimeField>::Params::MODULUS_BITS as usize;
+        let cost = |c: usize| bases.len() * (num_bits / c + 2) + (1 << c);
+        let window_size = (2..=16).min_by_key(|c| cost(*c)).unwrap();
+        Self::with_window_size(bases, window_size)
+    }
+
+    pub fn with_window_size(bases: &[G], window_size: usize) -> Self {
+        assert!((1..64).contains(&window_size));
+        let num_bits = <G::ScalarField as PrimeField>::Params::MODULUS_BITS as usize;
+        // One more window than the bits need, for the carry of the signed digits
+        let num_windows = (num_bits + window_size - 1) / window_size + 1;
+
+        let table: Vec<G::Projective> = cfg_iter!(bases)
+            .map(|base| {
+                let mut p = base.into_projective();
+                let mut row = Vec::with_capacity(num_windows);
+                for _ in 0..num_windows {
+                    row.push(p);
+                    for _ in 0..window_size {
+                        p.double_in_place();
+                    }
+                }
+                row
+            })
+            .collect::<Vec<_>>()
+            .concat();
+
+        Self {
+            window_size,
+            num_windows,
+            table: G::Projective::batch_normalization_into_affine(&table),
+        }
+    }
+
+    // The number of bases
+    pub fn len(&self) -> usize {
+        self.table.len() / self.num_windows
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.table.is_empty()
+    }
+
+    pub fn window_size(&self) -> usize {
+        self.window_size
+    }
+
+    // Σ_i scalars[i] * bases[i], for at most len() scalars
+    pub fn msm(&self, scalars: &[<G::ScalarField as PrimeField>::BigInt]) -> G::Projective {
+        assert!(scalars.len() <= self.len());
+
+        // With the parallel feature, each thread sums a chunk of the scalars with its own buckets
+        #[cfg(feature = "parallel")]
+        let num_chunks = rayon::current_num_threads();
+        #[cfg(not(feature = "parallel"))]
+        let num_chunks = 1;
+        let chunk_size = ((scalars.len() + num_chunks - 1) / num_chunks).max(1);
+
+        cfg_chunks!(scalars, chunk_size)
+            .enumerate()
+            .map(|(k, chunk)| self.msm_chunk(k * chunk_size, chunk))
+            .sum()
+    }
+
+    // Σ_i scalars[i] * bases[offset + i]
+    fn msm_chunk(
+        &self,
+        offset: usize,
+        scalars: &[<G::ScalarField as PrimeField>::BigInt],
+    ) -> G::Projective {
+        let c = self.window_size;
+        let half = 1i64 << (c - 1);
+
+        // Bucket k holds the points whose digit is ±(k + 1)
+        let mut buckets = vec![G::Projective::zero(); half as usize];
+        for (i, scalar) in scalars.iter().enumerate() {
+            if scalar.is_zero() {
+                continue;
+            }
+            let limbs = scalar.as_ref();
+            let mut carry = 0i64;
+            for j in 0..self.num_windows {
+                let mut digit = get_bits(limbs, j * c, c) as i64 + carry;
+                carry = 0;
+                if digit > half {
+                    digit -= 1 << c;
+                    carry = 1;
+                }
+                let p = &self.table[(offset + i) * self.num_windows + j];
+                match digit.cmp(&0) {
+                    std::cmp::Ordering::Greater => buckets[digit as usize - 1].add_assign_mixed(p),
+                    std::cmp::Ordering::Less => {
+                        buckets[(-digit) as usize - 1].add_assign_mixed(&-*p)
+                    }
+                    std::cmp::Ordering::Equal => {}
+                }
+            }
+        }
+
+        // Σ_k (k + 1) * buckets[k], with a running sum
+        let mut running_sum = G::Projective::zero();
+        let mut result = G::Projective::zero();
+        for bucket in buckets.iter().rev() {
+            running_sum += bucket;
+            result += &running_sum;
+        }
+        result
+    }
+
+    // The commitment of a polynomial to the bases, which commit() would compute
+    pub fn commit(&self, poly: &DensePolynomial<G::ScalarField>) -> G::Projective {
+        let coeff_scalars: Vec<_> = poly.coeffs.iter().map(|c| c.into_repr()).collect();
+        self.msm(&coeff_scalars)
+    }
+}
+
+// The len bits of a little-endian integer from the start-th bit, for len < 64
+fn get_bits(limbs: &[u64], start: usize, len: usize) -> u64 {
+    let limb = start / 64;
+    let shift = start % 64;
+    if limb >= limbs.len() {
+        return 0;
+    }
+    let mut bits = limbs[limb] >> shift;
+    if shift + len > 64 && limb + 1 < limbs.len() {
+        bits |= limbs[limb + 1] << (64 - shift);
+    }
+    bits & ((1 << len) - 1)
+}
+
+#[cfg(test)]
+mod kzg_tests {
+    use super::{commit, FixedBaseMsmTable};
+    use ark_bn254::{Fr, G1Affine, G1Projective};
+    use ark_ec::{AffineCurve, ProjectiveCurve};
+    use ark_ff::{Field, One, PrimeField, UniformRand};
+    use ark_poly::{univariate::DensePolynomial, UVPolynomial};
+    use ark_std::test_rng;
+
+    #[test]
+    fn test_fixed_base_msm_table() {
+        let mut rng = test_rng();
+        let bases: Vec<G1Affine> = (0..100)
+            .map(|_| G1Projective::rand(&mut rng).into_affine())
+            .collect();
+
+        for table in [
+            FixedBaseMsmTable::new(&bases),
+            FixedBaseMsmTable::with_window_size(&bases, 1),
+            FixedBaseMsmTable::with_window_size(&bases, 5),
+            FixedBaseMsmTable::with_window_size(&bases, 13),
+        ] {
+            assert_eq!(table.len(), bases.len());
+
+            let poly = DensePolynomial::from_coefficients_vec(
+                (0..bases.len()).map(|_| Fr::rand(&mut rng)).collect(),
+            );
+            assert_eq!(table.commit(&poly), commit(&bases, &poly));
+
+            // Fewer scalars than bases, and edge-case scalars
+            let minus_one = -Fr::one();
+            let scalars = [
+                Fr::from(0u64),
+                Fr::one(),
+                minus_one,
+                Fr::from(2u64).pow([253]),
+            ];
+            let expected = bases
+                .iter()
+                .zip(scalars.iter())
+                .map(|(p, s)| p.mul(s.into_repr()))
+                .sum::<G1Projective>();
+            let reprs: Vec<_> = scalars.iter().map(|s| s.into_repr()).collect();
+            assert_eq!(table.msm(&reprs), expected);
+        }
+    }
+}
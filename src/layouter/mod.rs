@@ -1,5 +1,7 @@
 use crate::constants::{NUMBER_OF_MIMC_ROUNDS, SUBGROUP_SIZE};
-use ark_ff::PrimeField;
+use crate::error::Error;
+use crate::poseidon::{Poseidon, SemaphorePoseidon};
+use ark_ff::{BigInteger, PrimeField};
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Read, SerializationError, Write};
 use rand::RngCore;
 use std::{iter, marker::PhantomData};
@@ -16,6 +18,81 @@ pub struct Assignment<F: PrimeField> {
     pub(crate) external_nullifier: Vec<F>,
 }
 
+/*
+   Full assignment of (blinded) wires of the Poseidon variant of the circuit, which proves a
+   Semaphore v2 identity commitment. Each of the three Poseidon instances has one column per state
+   element, and row i holds the state before round i:
+    - identity_secret: Poseidon(2) of id_nul and id_trap.
+    - identity_commitment: Poseidon(1) of the identity secret.
+    - nullifier_hash: Poseidon(2) of ext_nul and id_nul.
+   The output state of each instance is carried to row NUMBER_OF_MIMC_ROUNDS, so that it is opened
+   at omega ^ n * alpha like the MiMC7 outputs. The MiMC7 columns are unused, and are all zero.
+*/
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+pub struct PoseidonAssignment<F: PrimeField> {
+    pub(crate) assignment: Assignment<F>,
+    pub(crate) identity_secret: Vec<Vec<F>>,
+    pub(crate) identity_commitment: Vec<Vec<F>>,
+    pub(crate) nullifier_hash: Vec<Vec<F>>,
+}
+
+/*
+   The fixed columns of a Poseidon instance of the Poseidon variant of the circuit, over the
+   subgroup. q_full and q_partial select the full and partial rounds, q_pass selects the rows which
+   carry the output state to row NUMBER_OF_MIMC_ROUNDS, and cts holds one column of round
+   constants per state element. mds is the MDS matrix which the round gates apply to each row.
+*/
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone, Debug)]
+pub struct PoseidonFixedColumns<F: PrimeField> {
+    pub(crate) q_full: Vec<F>,
+    pub(crate) q_partial: Vec<F>,
+    pub(crate) q_pass: Vec<F>,
+    pub(crate) cts: Vec<Vec<F>>,
+    pub(crate) mds: Vec<Vec<F>>,
+}
+
+/*
+   Full assignment of (blinded) wires of the RLN variant of the circuit. The external nullifier
+   is MiMC7(epoch, message_id), where the message ID is private:
+    - internal_nullifier holds the MiMC7 rounds of a1 (the nullifier hash, which is kept secret in
+      RLN mode), whose multihash is the public RLN nullifier.
+    - epoch holds the MiMC7 rounds of the epoch with key 0.
+    - message_key holds the multihash of the epoch, which is the key used to hash the message ID.
+    - message_id holds the MiMC7 rounds of the message ID.
+    - message_id_range and message_limit_range hold running sums of the bits of message_id and
+      message_limit - 1 - message_id, most significant bit first, so that row
+      NUMBER_OF_MIMC_ROUNDS holds the value.
+*/
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+pub struct RlnAssignment<F: PrimeField> {
+    pub(crate) assignment: Assignment<F>,
+    pub(crate) internal_nullifier: Vec<F>,
+    pub(crate) epoch: Vec<F>,
+    pub(crate) message_key: Vec<F>,
+    pub(crate) message_id: Vec<F>,
+    pub(crate) message_id_range: Vec<F>,
+    pub(crate) message_limit_range: Vec<F>,
+}
+
+/*
+   Full assignment of (blinded) wires of the attribute variant of the circuit, where the identity
+   commitment is MiMC7(id_nul, id_trap, attribute):
+    - attribute_key holds the MiMC7 multihash of id_nul and id_trap, which is the key used to hash
+      the attribute.
+    - attribute holds the MiMC7 rounds of the attribute.
+    - range holds a running sum of the bits of attribute - threshold, most significant bit
+      first, so that row NUMBER_OF_MIMC_ROUNDS holds the difference.
+    - attribute_hash holds the MiMC7 rounds of the attribute with key 0.
+*/
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+pub struct AttributeAssignment<F: PrimeField> {
+    pub(crate) assignment: Assignment<F>,
+    pub(crate) attribute_key: Vec<F>,
+    pub(crate) attribute: Vec<F>,
+    pub(crate) range: Vec<F>,
+    pub(crate) attribute_hash: Vec<F>,
+}
+
 pub struct Layouter<F: PrimeField> {
     _f: PhantomData<F>,
 }
@@ -97,6 +174,337 @@ impl<F: PrimeField> Layouter<F> {
         }
     }
 
+    /*
+     * Construct the circuit assignment table for a membership-only proof. Only the nullifier, key
+     * and identity_commitment columns are used, so the external_nullifier column is assigned with
+     * an external nullifier of 0 and is not committed to.
+     * @param identity_nullifier: The identity nullifier.
+     * @param identity_trapdoor: The identity trapdoor.
+     * @param c: MiMC7 round constants.
+     * @param rng: The random number generator used for blinding.
+     */
+    pub fn assign_membership<R: RngCore>(
+        identity_nullifier: F,
+        identity_trapdoor: F,
+        c: &[F],
+        rng: &mut R,
+    ) -> Assignment<F> {
+        Self::assign(identity_nullifier, identity_trapdoor, F::zero(), c, rng)
+    }
+
+    /*
+     * Construct the circuit assignment table for RLN mode. Returns Error::MessageLimitExceeded if
+     * message_id is not below message_limit.
+     * @param identity_nullifier: The identity nullifier, which is the secret a0.
+     * @param identity_trapdoor: The identity trapdoor.
+     * @param epoch: The epoch.
+     * @param message_id: The private index of the message within the epoch.
+     * @param message_limit: The number of messages a member may send per epoch.
+     * @param c: MiMC7 round constants.
+     * @param rng: The random number generator used for blinding.
+     */
+    pub fn assign_rln<R: RngCore>(
+        identity_nullifier: F,
+        identity_trapdoor: F,
+        epoch: F,
+        message_id: u64,
+        message_limit: u64,
+        c: &[F],
+        rng: &mut R,
+    ) -> Result<RlnAssignment<F>, Error> {
+        let pow_7 = |x: F| x.pow([7, 0, 0, 0]);
+
+        if message_id >= message_limit {
+            return Err(Error::MessageLimitExceeded);
+        }
+        let message_id_range_col =
+            Self::assign_range(F::from(message_id), rng).ok_or(Error::MessageLimitExceeded)?;
+        let message_limit_range_col =
+            Self::assign_range(F::from(message_limit - 1 - message_id), rng)
+                .ok_or(Error::MessageLimitExceeded)?;
+
+        //---------------------------------------------------------------------
+        // Assign the epoch column
+        let mut epoch_col = Vec::<F>::with_capacity(SUBGROUP_SIZE);
+        epoch_col.push(epoch);
+        epoch_col.push(pow_7(epoch));
+        for i in 1..NUMBER_OF_MIMC_ROUNDS {
+            epoch_col.push(pow_7(epoch_col[i] + c[i]));
+        }
+
+        //---------------------------------------------------------------------
+        // Assign the message_key column
+        let message_key = epoch_col[NUMBER_OF_MIMC_ROUNDS] + epoch;
+        let mut message_key_col = iter::repeat(message_key)
+            .take(NUMBER_OF_MIMC_ROUNDS + 1)
+            .collect();
+        Self::blind(&mut epoch_col, rng);
+        Self::blind(&mut message_key_col, rng);
+
+        //---------------------------------------------------------------------
+        // Assign the message_id column
+        let mut message_id_col = Vec::<F>::with_capacity(SUBGROUP_SIZE);
+        message_id_col.push(F::from(message_id));
+        message_id_col.push(pow_7(F::from(message_id) + message_key));
+        for i in 1..NUMBER_OF_MIMC_ROUNDS {
+            message_id_col.push(pow_7(message_id_col[i] + message_key + c[i]));
+        }
+
+        // The external nullifier is the MiMC7 multihash of the epoch and the message ID
+        let external_nullifier = message_id_col[NUMBER_OF_MIMC_ROUNDS]
+            + F::from(message_id)
+            + F::from(2u64) * message_key;
+        Self::blind(&mut message_id_col, rng);
+
+        let assignment = Self::assign(
+            identity_nullifier,
+            identity_trapdoor,
+            external_nullifier,
+            c,
+            rng,
+        );
+
+        // a1 is the MiMC7 multihash of the identity nullifier and the external nullifier
+        let a1 = assignment.external_nullifier[NUMBER_OF_MIMC_ROUNDS]
+            + external_nullifier
+            + F::from(2u64) * assignment.key[0];
+
+        let mut internal_nullifier_col = Vec::<F>::with_capacity(SUBGROUP_SIZE);
+        internal_nullifier_col.push(a1);
+        internal_nullifier_col.push(pow_7(a1));
+        for i in 1..NUMBER_OF_MIMC_ROUNDS {
+            internal_nullifier_col.push(pow_7(internal_nullifier_col[i] + c[i]));
+        }
+        Self::blind(&mut internal_nullifier_col, rng);
+
+        Ok(RlnAssignment {
+            assignment,
+            internal_nullifier: internal_nullifier_col,
+            epoch: epoch_col,
+            message_key: message_key_col,
+            message_id: message_id_col,
+            message_id_range: message_id_range_col,
+            message_limit_range: message_limit_range_col,
+        })
+    }
+
+    /*
+     * Construct the circuit assignment table for an attribute-bearing identity commitment, which
+     * proves that attribute >= threshold. Returns Error::AttributePredicateNotSatisfied if
+     * attribute - threshold is not in [0, 2 ^ NUMBER_OF_MIMC_ROUNDS).
+     * @param identity_nullifier: The identity nullifier.
+     * @param identity_trapdoor: The identity trapdoor.
+     * @param attribute: The attribute.
+     * @param threshold: The public lower bound on the attribute. Use 0 to prove no predicate.
+     * @param external_nullifier: The external nullifier.
+     * @param c: MiMC7 round constants.
+     * @param rng: The random number generator used for blinding.
+     */
+    #[allow(clippy::too_many_arguments)]
+    pub fn assign_with_attribute<R: RngCore>(
+        identity_nullifier: F,
+        identity_trapdoor: F,
+        attribute: F,
+        threshold: F,
+        external_nullifier: F,
+        c: &[F],
+        rng: &mut R,
+    ) -> Result<AttributeAssignment<F>, Error> {
+        let pow_7 = |x: F| x.pow([7, 0, 0, 0]);
+
+        let range_col = Self::assign_range(attribute - threshold, rng)
+            .ok_or(Error::AttributePredicateNotSatisfied)?;
+
+        let assignment = Self::assign(
+            identity_nullifier,
+            identity_trapdoor,
+            external_nullifier,
+            c,
+            rng,
+        );
+
+        //---------------------------------------------------------------------
+        // Assign the attribute_key column
+        let attribute_key = assignment.identity_commitment[NUMBER_OF_MIMC_ROUNDS]
+            + identity_trapdoor
+            + F::from(2u64) * assignment.key[0];
+        let mut attribute_key_col = iter::repeat(attribute_key)
+            .take(NUMBER_OF_MIMC_ROUNDS + 1)
+            .collect();
+        Self::blind(&mut attribute_key_col, rng);
+
+        //---------------------------------------------------------------------
+        // Assign the attribute column
+        let mut attribute_col = Vec::<F>::with_capacity(SUBGROUP_SIZE);
+        attribute_col.push(attribute);
+        attribute_col.push(pow_7(attribute + attribute_key));
+        for i in 1..NUMBER_OF_MIMC_ROUNDS {
+            attribute_col.push(pow_7(attribute_col[i] + attribute_key + c[i]));
+        }
+        Self::blind(&mut attribute_col, rng);
+
+        //---------------------------------------------------------------------
+        // Assign the attribute_hash column
+        let mut attribute_hash_col = Vec::<F>::with_capacity(SUBGROUP_SIZE);
+        attribute_hash_col.push(attribute);
+        attribute_hash_col.push(pow_7(attribute));
+        for i in 1..NUMBER_OF_MIMC_ROUNDS {
+            attribute_hash_col.push(pow_7(attribute_hash_col[i] + c[i]));
+        }
+        Self::blind(&mut attribute_hash_col, rng);
+
+        Ok(AttributeAssignment {
+            assignment,
+            attribute_key: attribute_key_col,
+            attribute: attribute_col,
+            range: range_col,
+            attribute_hash: attribute_hash_col,
+        })
+    }
+
+    /*
+     * Assign a running sum of the bits of value, most significant bit first, so that row
+     * NUMBER_OF_MIMC_ROUNDS holds the value. Returns None if value is not in
+     * [0, 2 ^ NUMBER_OF_MIMC_ROUNDS).
+     */
+    fn assign_range<R: RngCore>(value: F, rng: &mut R) -> Option<Vec<F>> {
+        let bits = value.into_repr().to_bits_be();
+        let (high_bits, bits) = bits.split_at(bits.len() - NUMBER_OF_MIMC_ROUNDS);
+        if high_bits.iter().any(|b| *b) {
+            return None;
+        }
+
+        let mut range_col = Vec::<F>::with_capacity(SUBGROUP_SIZE);
+        range_col.push(F::zero());
+        for (i, bit) in bits.iter().enumerate() {
+            range_col.push(F::from(2u64) * range_col[i] + F::from(*bit as u64));
+        }
+        Self::blind(&mut range_col, rng);
+
+        Some(range_col)
+    }
+
+    /*
+     * Construct the circuit assignment table for Semaphore v2 Poseidon identity commitments.
+     * @param identity_nullifier: The identity nullifier.
+     * @param identity_trapdoor: The identity trapdoor.
+     * @param external_nullifier: The external nullifier.
+     * @param poseidon: The Poseidon(2) and Poseidon(1) parameters.
+     * @param rng: The random number generator used for blinding.
+     */
+    pub fn assign_poseidon<R: RngCore>(
+        identity_nullifier: F,
+        identity_trapdoor: F,
+        external_nullifier: F,
+        poseidon: &SemaphorePoseidon<F>,
+        rng: &mut R,
+    ) -> PoseidonAssignment<F> {
+        let identity_secret = Self::assign_poseidon_instance(
+            &[identity_nullifier, identity_trapdoor],
+            &poseidon.poseidon,
+            rng,
+        );
+        let identity_commitment = Self::assign_poseidon_instance(
+            &[identity_secret[0][NUMBER_OF_MIMC_ROUNDS]],
+            &poseidon.poseidon_single,
+            rng,
+        );
+        let nullifier_hash = Self::assign_poseidon_instance(
+            &[external_nullifier, identity_nullifier],
+            &poseidon.poseidon,
+            rng,
+        );
+
+        // The MiMC7 columns are not constrained in the Poseidon variant
+        let zeroes = vec![F::zero(); SUBGROUP_SIZE];
+        let assignment = Assignment {
+            nullifier: zeroes.clone(),
+            key: zeroes.clone(),
+            identity_commitment: zeroes.clone(),
+            external_nullifier: zeroes,
+        };
+
+        PoseidonAssignment {
+            assignment,
+            identity_secret,
+            identity_commitment,
+            nullifier_hash,
+        }
+    }
+
+    /*
+     * Assign one column per Poseidon state element, where the first row is the initial state
+     * [0, inputs..], each following row is the output of one round, and the output state is
+     * repeated up to row NUMBER_OF_MIMC_ROUNDS.
+     */
+    fn assign_poseidon_instance<R: RngCore>(
+        inputs: &[F],
+        poseidon: &Poseidon<F>,
+        rng: &mut R,
+    ) -> Vec<Vec<F>> {
+        assert_eq!(inputs.len(), poseidon.width - 1);
+        assert!(poseidon.n_rounds() <= NUMBER_OF_MIMC_ROUNDS);
+
+        let mut cols: Vec<Vec<F>> = (0..poseidon.width)
+            .map(|_| Vec::<F>::with_capacity(SUBGROUP_SIZE))
+            .collect();
+
+        let mut state: Vec<F> = iter::once(F::zero())
+            .chain(inputs.iter().cloned())
+            .collect();
+        for round in 0..=NUMBER_OF_MIMC_ROUNDS {
+            for (col, s) in cols.iter_mut().zip(state.iter()) {
+                col.push(*s);
+            }
+            if round < poseidon.n_rounds() {
+                state = poseidon.round_function(round, &state);
+            }
+        }
+
+        // Fill the remaining rows with random values
+        for col in cols.iter_mut() {
+            Self::blind(col, rng);
+        }
+
+        cols
+    }
+
+    /*
+     * Construct the fixed columns of a Poseidon instance of the Poseidon variant of the circuit.
+     * @param poseidon: The Poseidon parameters of the instance.
+     */
+    pub fn assign_poseidon_fixed(poseidon: &Poseidon<F>) -> PoseidonFixedColumns<F> {
+        let n_rounds = poseidon.n_rounds();
+        assert!(n_rounds <= NUMBER_OF_MIMC_ROUNDS);
+
+        let mut q_full = vec![F::zero(); SUBGROUP_SIZE];
+        let mut q_partial = vec![F::zero(); SUBGROUP_SIZE];
+        let mut q_pass = vec![F::zero(); SUBGROUP_SIZE];
+        let mut cts = vec![vec![F::zero(); SUBGROUP_SIZE]; poseidon.width];
+
+        for round in 0..n_rounds {
+            if poseidon.is_full_round(round) {
+                q_full[round] = F::one();
+            } else {
+                q_partial[round] = F::one();
+            }
+            for (j, c) in cts.iter_mut().enumerate() {
+                c[round] = poseidon.cts[round * poseidon.width + j];
+            }
+        }
+        for q in q_pass.iter_mut().take(NUMBER_OF_MIMC_ROUNDS).skip(n_rounds) {
+            *q = F::one();
+        }
+
+        PoseidonFixedColumns {
+            q_full,
+            q_partial,
+            q_pass,
+            cts,
+            mds: poseidon.mds.clone(),
+        }
+    }
+
     /*
      * Given a Vec of field elements (which must be NUMBER_OF_MIMC_ROUNDS + 1 in length), extend it
      * to SUBGROUP_SIZE elements where the remaining values are random field elements.
@@ -120,12 +528,15 @@ impl<F: PrimeField> Layouter<F> {
 #[cfg(test)]
 mod layouter_tests {
     use ark_bn254::Fr;
-    use ark_ff::Zero;
+    use ark_ff::{One, Zero};
     use ark_std::test_rng;
 
     use super::Layouter;
     use crate::constants::{NUMBER_OF_MIMC_ROUNDS, SUBGROUP_SIZE};
+    use crate::error::Error;
+    use crate::hasher::IdentityHasher;
     use crate::mimc7::init_mimc7;
+    use crate::poseidon::init_semaphore_poseidon;
 
     #[test]
     fn test_mimc_correctness() {
@@ -203,4 +614,227 @@ mod layouter_tests {
                 + Fr::from(2u64) * assignment.key[0]
         );
     }
+
+    #[test]
+    fn test_rln_correctness() {
+        let n_rounds = NUMBER_OF_MIMC_ROUNDS;
+        let mut rng = test_rng();
+
+        let mimc7 = init_mimc7::<Fr>();
+
+        let identity_nullifier = Fr::from(100u64);
+        let identity_trapdoor = Fr::from(200u64);
+        let epoch = Fr::from(300u64);
+        let external_nullifier = mimc7.multi_hash(&[epoch, Fr::from(1u64)], Fr::zero());
+
+        let a1 = mimc7.multi_hash(&[identity_nullifier, external_nullifier], Fr::zero());
+        let rln_nullifier = mimc7.multi_hash(&[a1], Fr::zero());
+
+        let rln_assignment = Layouter::assign_rln(
+            identity_nullifier,
+            identity_trapdoor,
+            epoch,
+            1,
+            3,
+            &mimc7.cts,
+            &mut rng,
+        )
+        .unwrap();
+
+        assert_eq!(rln_assignment.internal_nullifier.len(), SUBGROUP_SIZE);
+        assert_eq!(rln_assignment.internal_nullifier[0], a1);
+        assert_eq!(
+            rln_nullifier,
+            rln_assignment.internal_nullifier[n_rounds] + rln_assignment.internal_nullifier[0]
+        );
+        assert_eq!(
+            rln_assignment.assignment.external_nullifier[0],
+            external_nullifier
+        );
+        assert_eq!(rln_assignment.message_id_range[n_rounds], Fr::from(1u64));
+        assert_eq!(rln_assignment.message_limit_range[n_rounds], Fr::from(1u64));
+
+        assert_eq!(
+            Layouter::assign_rln(
+                identity_nullifier,
+                identity_trapdoor,
+                epoch,
+                3,
+                3,
+                &mimc7.cts,
+                &mut rng,
+            )
+            .err(),
+            Some(Error::MessageLimitExceeded)
+        );
+    }
+
+    #[test]
+    fn test_attribute_correctness() {
+        let n_rounds = NUMBER_OF_MIMC_ROUNDS;
+        let mut rng = test_rng();
+
+        let mimc7 = init_mimc7::<Fr>();
+
+        let identity_nullifier = Fr::from(100u64);
+        let identity_trapdoor = Fr::from(200u64);
+        let attribute = Fr::from(3u64);
+        let external_nullifier = Fr::from(300u64);
+
+        let identity_commitment = mimc7.multi_hash(
+            &[identity_nullifier, identity_trapdoor, attribute],
+            Fr::zero(),
+        );
+        let attribute_hash = mimc7.multi_hash(&[attribute], Fr::zero());
+
+        let assignment = Layouter::assign_with_attribute(
+            identity_nullifier,
+            identity_trapdoor,
+            attribute,
+            Fr::from(2u64),
+            external_nullifier,
+            &mimc7.cts,
+            &mut rng,
+        )
+        .unwrap();
+
+        assert_eq!(
+            identity_commitment,
+            assignment.attribute[n_rounds]
+                + attribute
+                + Fr::from(2u64) * assignment.attribute_key[0]
+        );
+        assert_eq!(
+            attribute_hash,
+            assignment.attribute_hash[n_rounds] + assignment.attribute_hash[0]
+        );
+
+        // The range column ends with attribute - threshold
+        assert_eq!(assignment.range[0], Fr::zero());
+        assert_eq!(assignment.range[n_rounds], Fr::from(1u64));
+
+        // The predicate must hold
+        assert_eq!(
+            Layouter::assign_with_attribute(
+                identity_nullifier,
+                identity_trapdoor,
+                attribute,
+                Fr::from(4u64),
+                external_nullifier,
+                &mimc7.cts,
+                &mut rng,
+            )
+            .err(),
+            Some(Error::AttributePredicateNotSatisfied)
+        );
+    }
+
+    #[test]
+    fn test_poseidon_correctness() {
+        let mut rng = test_rng();
+
+        let poseidon = init_semaphore_poseidon::<Fr>();
+
+        let identity_nullifier = Fr::from(100u64);
+        let identity_trapdoor = Fr::from(200u64);
+        let external_nullifier = Fr::from(300u64);
+
+        let identity_secret = poseidon.identity_secret(identity_nullifier, identity_trapdoor);
+        let identity_commitment =
+            poseidon.identity_commitment(identity_nullifier, identity_trapdoor);
+        let nullifier_hash = poseidon.nullifier_hash(identity_nullifier, external_nullifier);
+
+        let assignment = Layouter::assign_poseidon(
+            identity_nullifier,
+            identity_trapdoor,
+            external_nullifier,
+            &poseidon,
+            &mut rng,
+        );
+
+        let instances = [
+            (&assignment.identity_secret, &poseidon.poseidon),
+            (&assignment.identity_commitment, &poseidon.poseidon_single),
+            (&assignment.nullifier_hash, &poseidon.poseidon),
+        ];
+
+        // Check the lengths of the rows
+        for (cols, params) in instances.iter() {
+            assert_eq!(cols.len(), params.width);
+            for col in cols.iter() {
+                assert_eq!(col.len(), SUBGROUP_SIZE);
+            }
+        }
+
+        // Check the initial states
+        assert_eq!(assignment.identity_secret[0][0], Fr::zero());
+        assert_eq!(assignment.identity_secret[1][0], identity_nullifier);
+        assert_eq!(assignment.identity_secret[2][0], identity_trapdoor);
+        assert_eq!(assignment.identity_commitment[0][0], Fr::zero());
+        assert_eq!(assignment.identity_commitment[1][0], identity_secret);
+        assert_eq!(assignment.nullifier_hash[0][0], Fr::zero());
+        assert_eq!(assignment.nullifier_hash[1][0], external_nullifier);
+        assert_eq!(assignment.nullifier_hash[2][0], identity_nullifier);
+
+        for (cols, params) in instances.iter() {
+            // Check that each row is the output of the round function applied to the previous
+            // row
+            for i in 0..params.n_rounds() {
+                let state: Vec<Fr> = cols.iter().map(|col| col[i]).collect();
+                let next_state: Vec<Fr> = cols.iter().map(|col| col[i + 1]).collect();
+                assert_eq!(params.round_function(i, &state), next_state);
+            }
+
+            // The output state is carried to row NUMBER_OF_MIMC_ROUNDS
+            for col in cols.iter() {
+                for i in params.n_rounds()..NUMBER_OF_MIMC_ROUNDS {
+                    assert_eq!(col[i], col[i + 1]);
+                }
+            }
+        }
+
+        // Check the outputs
+        assert_eq!(
+            identity_secret,
+            assignment.identity_secret[0][NUMBER_OF_MIMC_ROUNDS]
+        );
+        assert_eq!(
+            identity_commitment,
+            assignment.identity_commitment[0][NUMBER_OF_MIMC_ROUNDS]
+        );
+        assert_eq!(
+            nullifier_hash,
+            assignment.nullifier_hash[0][NUMBER_OF_MIMC_ROUNDS]
+        );
+        assert!(assignment.assignment.key.iter().all(|x| x.is_zero()));
+    }
+
+    #[test]
+    fn test_poseidon_fixed_columns() {
+        let poseidon = init_semaphore_poseidon::<Fr>();
+
+        for params in [&poseidon.poseidon, &poseidon.poseidon_single] {
+            let n_rounds = params.n_rounds();
+            let fixed = Layouter::assign_poseidon_fixed(params);
+
+            // Exactly one selector is set on each of the first NUMBER_OF_MIMC_ROUNDS rows
+            for i in 0..SUBGROUP_SIZE {
+                let selected = fixed.q_full[i] + fixed.q_partial[i] + fixed.q_pass[i];
+                if i < NUMBER_OF_MIMC_ROUNDS {
+                    assert_eq!(selected, Fr::one());
+                } else {
+                    assert!(selected.is_zero());
+                }
+            }
+            assert_eq!(fixed.q_full[3], Fr::one());
+            assert_eq!(fixed.q_partial[4], Fr::one());
+            assert_eq!(fixed.q_full[n_rounds - 1], Fr::one());
+            assert_eq!(fixed.q_pass[n_rounds], Fr::one());
+
+            assert_eq!(fixed.cts.len(), params.width);
+            assert_eq!(fixed.cts[1][1], params.cts[params.width + 1]);
+            assert!(fixed.cts[0][n_rounds].is_zero());
+            assert_eq!(fixed.mds, params.mds);
+        }
+    }
 }
@@ -4,13 +4,23 @@ pub mod constants;
 pub mod contracts;
 pub mod error;
 pub mod gates;
+pub mod hasher;
+pub mod hidden_insertion;
+pub mod identity;
 pub mod keccak_tree;
 pub mod kzg;
 pub mod layouter;
 pub mod mimc7;
+pub mod out_of_core;
 pub mod multiopen;
+pub mod pir;
+pub mod poseidon;
+pub mod precompute_cache;
+pub mod precompute_server;
 pub mod prover;
+pub mod rln;
 pub mod rng;
+pub mod semaphore_compat;
 pub mod setup;
 pub mod tests;
 pub mod transcript;
@@ -120,6 +120,7 @@ mod multiopen_tests {
         // compute proof
         let multiopen_proof = Prover::prove(
             &srs_g1,
+            None,
             &w0,
             &w1,
             &w2,
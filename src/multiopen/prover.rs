@@ -4,7 +4,7 @@ use ark_ff::One;
 use ark_poly::{univariate::DensePolynomial, Polynomial, UVPolynomial};
 
 use crate::{
-    kzg::{commit, open},
+    kzg::{commit_with_table, open_with_table, FixedBaseMsmTable},
     transcript::Transcript,
 };
 use ark_bn254::{Bn254, Fr, G1Affine};
@@ -18,6 +18,7 @@ impl Prover {
     pub fn prove(
         //srs
         srs_g1: &[G1Affine],
+        msm_table: Option<&FixedBaseMsmTable<G1Affine>>,
         // semaphore related polys
         w0: &DensePolynomial<Fr>,
         w1: &DensePolynomial<Fr>,
@@ -71,7 +72,7 @@ impl Prover {
 
         let f = f1 + (&f2 * x2_powers[0]) + (&f3 * x2_powers[1]) + (&f4 * x2_powers[2]);
 
-        let f_cm: G1Affine = commit(srs_g1, &f).into();
+        let f_cm: G1Affine = commit_with_table(srs_g1, msm_table, &f).into();
         transcript.round_5(&f_cm);
 
         let x3 = transcript.get_challenge();
@@ -85,7 +86,7 @@ impl Prover {
             + (&q2 * x4_powers[1])
             + (&q3 * x4_powers[2])
             + (&q4 * x4_powers[3]);
-        let (_, final_poly_proof) = open(srs_g1, &final_poly, x3);
+        let (_, final_poly_proof) = open_with_table(srs_g1, msm_table, &final_poly, x3);
 
         MultiopenProof {
             q1_opening: q1.evaluate(&x3),
@@ -0,0 +1,644 @@
+/*
+   Setup and precomputation for capacities of 2^24 to 2^28, whose SRS, Lagrange commitments, and
+   W1 and W2 values do not fit in memory. Points are kept in memory-mapped scratch files in a
+   directory, and each step only holds chunk_size points in memory at a time:
+
+   - read_srs() copies the SRS from a .ptau or raw PPOT file a chunk at a time.
+   - fft() computes the FFT of a scratch file of n = n1 * n2 points with the four-step algorithm:
+     n2 FFTs of size n1 over the columns of the n1 x n2 matrix of the input, multiplied by the
+     twiddle factors ω^(j2 * k1), then n1 FFTs of size n2 over its rows. Both n1 and n2 are about
+     sqrt(n), so chunk_size must be at least 2^14 for n = 2^28.
+   - lagrange_comms() computes [L_i] = n^-1 * FFT([τ^j])_(-i mod n) as
+     utils::compute_lagrange_basis_commitments() does.
+   - w2() computes W2_i = n * ω^-i * [L_i]_2, as CaulkPlusPrecomputed::precompute_all_w2() does.
+   - w1() computes the W1 value of every index from the W2 values with FFTs, without C(X) (see
+     w1()).
+*/
+use crate::accumulator::compute_lagrange_leaf;
+use crate::error::Error;
+use crate::keccak_tree::Keccak256;
+use crate::setup::ppot::PpotFile;
+use crate::setup::ptau::{is_ptau, PtauHeader};
+use ark_bn254::{G1Affine, G2Affine};
+use ark_ec::{AffineCurve, PairingEngine, ProjectiveCurve};
+use ark_ff::{Field, One, PrimeField, Zero};
+use ark_poly::{EvaluationDomain, GeneralEvaluationDomain};
+use ark_std::{cfg_into_iter, cfg_iter};
+use memmap2::MmapMut;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use semaphore::merkle_tree::Hasher;
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+
+// A fixed-length array of points in a memory-mapped file, serialised uncompressed
+pub struct ScratchFile<C: AffineCurve> {
+    path: PathBuf,
+    mmap: MmapMut,
+    len: usize,
+    point_size: usize,
+    _curve: PhantomData<C>,
+}
+
+impl<C: AffineCurve> ScratchFile<C> {
+    // Create a file of len points at infinity, replacing any existing file
+    pub fn create<P: AsRef<Path>>(path: P, len: usize) -> Result<Self, Error> {
+        let point_size = C::zero().uncompressed_size();
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&path)
+            .map_err(scratch_error)?;
+        file.set_len((len * point_size) as u64)
+            .map_err(scratch_error)?;
+
+        // Safety: the file was created above and is only accessed through this map
+        let mmap = unsafe { MmapMut::map_mut(&file) }.map_err(scratch_error)?;
+        let mut scratch = Self {
+            path: path.as_ref().to_path_buf(),
+            mmap,
+            len,
+            point_size,
+            _curve: PhantomData,
+        };
+
+        // An all-zero file does not deserialise to the point at infinity, so write it explicitly
+        let zero = vec![C::zero(); len.min(1 << 16)];
+        for start in (0..len).step_by(zero.len().max(1)) {
+            let count = zero.len().min(len - start);
+            scratch.write(start, &zero[..count])?;
+        }
+        Ok(scratch)
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn get(&self, index: usize) -> Result<C, Error> {
+        Ok(self.read(index, 1)?[0])
+    }
+
+    // Read count points, starting from the start-th
+    pub fn read(&self, start: usize, count: usize) -> Result<Vec<C>, Error> {
+        assert!(start + count <= self.len);
+        self.mmap[start * self.point_size..(start + count) * self.point_size]
+            .chunks(self.point_size)
+            .map(|bytes| C::deserialize_unchecked(bytes).map_err(scratch_error))
+            .collect()
+    }
+
+    // Write points, starting from the start-th
+    pub fn write(&mut self, start: usize, points: &[C]) -> Result<(), Error> {
+        assert!(start + points.len() <= self.len);
+        let bytes = &mut self.mmap[start * self.point_size..];
+        for (p, chunk) in points.iter().zip(bytes.chunks_mut(self.point_size)) {
+            p.serialize_unchecked(chunk).map_err(scratch_error)?;
+        }
+        Ok(())
+    }
+
+    pub fn flush(&self) -> Result<(), Error> {
+        self.mmap.flush().map_err(scratch_error)
+    }
+
+    // Read every point into memory, for tables small enough to fit
+    pub fn to_vec(&self) -> Result<Vec<C>, Error> {
+        self.read(0, self.len)
+    }
+}
+
+pub struct OutOfCore {
+    dir: PathBuf,
+    chunk_size: usize,
+}
+
+impl OutOfCore {
+    /*
+     * @param dir: The directory of the scratch files, which is created if it does not exist.
+     * @param chunk_size: The largest number of points which a step holds in memory. It must be a
+     *                    power of two, and at least the square root of the largest table size.
+     */
+    pub fn new<P: AsRef<Path>>(dir: P, chunk_size: usize) -> Result<Self, Error> {
+        assert!(chunk_size.is_power_of_two());
+        fs::create_dir_all(&dir).map_err(scratch_error)?;
+        Ok(Self {
+            dir: dir.as_ref().to_path_buf(),
+            chunk_size,
+        })
+    }
+
+    // Create a scratch file in the scratch directory
+    pub fn create<C: AffineCurve>(&self, name: &str, len: usize) -> Result<ScratchFile<C>, Error> {
+        ScratchFile::create(self.dir.join(name), len)
+    }
+
+    /*
+     * Copy the first G1 and G2 powers of tau of a .ptau or raw PPOT file to the scratch files
+     * srs_g1.bin and srs_g2.bin, a chunk at a time.
+     */
+    pub fn read_srs(
+        &self,
+        path: &str,
+        num_g1_points: usize,
+        num_g2_points: usize,
+    ) -> Result<(ScratchFile<G1Affine>, ScratchFile<G2Affine>), Error> {
+        let mut srs_g1 = self.create::<G1Affine>("srs_g1.bin", num_g1_points)?;
+        let mut srs_g2 = self.create::<G2Affine>("srs_g2.bin", num_g2_points)?;
+
+        if is_ptau(path) {
+            let header = PtauHeader::read(path)?;
+            self.for_each_chunk(num_g1_points, |start, count| {
+                srs_g1.write(start, &header.read_g1_range(path, start, count)?)
+            })?;
+            self.for_each_chunk(num_g2_points, |start, count| {
+                srs_g2.write(start, &header.read_g2_range(path, start, count)?)
+            })?;
+        } else {
+            let file = PpotFile::open(path)?;
+            self.for_each_chunk(num_g1_points, |start, count| {
+                srs_g1.write(start, &file.read_g1_range(start, count)?)
+            })?;
+            self.for_each_chunk(num_g2_points, |start, count| {
+                srs_g2.write(start, &file.read_g2_range(start, count)?)
+            })?;
+        }
+
+        srs_g1.flush()?;
+        srs_g2.flush()?;
+        Ok((srs_g1, srs_g2))
+    }
+
+    /*
+     * The FFT, or inverse FFT, of the first n points of a scratch file, written to the scratch
+     * file of the given name.
+     */
+    pub fn fft<C: AffineCurve>(
+        &self,
+        input: &ScratchFile<C>,
+        n: usize,
+        name: &str,
+        inverse: bool,
+    ) -> Result<ScratchFile<C>, Error> {
+        assert!(n.is_power_of_two() && n <= input.len());
+        let domain = GeneralEvaluationDomain::<C::ScalarField>::new(n).unwrap();
+        let mut output = self.create::<C>(name, n)?;
+
+        if n <= self.chunk_size {
+            let mut points = to_projective(&input.read(0, n)?);
+            fft_in_place(&domain, &mut points, inverse);
+            output.write(0, &C::Projective::batch_normalization_into_affine(&points))?;
+            output.flush()?;
+            return Ok(output);
+        }
+
+        let log_n = n.trailing_zeros();
+        let n1 = 1usize << ((log_n + 1) / 2);
+        let n2 = n / n1;
+        assert!(self.chunk_size >= n1, "chunk_size must be at least sqrt(n)");
+        let domain1 = GeneralEvaluationDomain::<C::ScalarField>::new(n1).unwrap();
+        let domain2 = GeneralEvaluationDomain::<C::ScalarField>::new(n2).unwrap();
+        let omega = if inverse {
+            domain.element(1).inverse().unwrap()
+        } else {
+            domain.element(1)
+        };
+
+        // Step 1: column j2 of the input is x[n2 * j1 + j2] for j1 in 0..n1. Its FFT, multiplied
+        // by ω^(j2 * k1), is written to row j2 of the transposed file, i.e. from j2 * n1 on.
+        let transposed_name = format!("{name}.transposed");
+        let mut transposed = self.create::<C>(&transposed_name, n)?;
+        let batch = (self.chunk_size / n1).min(n2);
+        for j2_start in (0..n2).step_by(batch) {
+            let mut columns = vec![Vec::with_capacity(n1); batch];
+            for j1 in 0..n1 {
+                let row = input.read(n2 * j1 + j2_start, batch)?;
+                for (column, p) in columns.iter_mut().zip(row.iter()) {
+                    column.push(p.into_projective());
+                }
+            }
+
+            let mut out = Vec::with_capacity(batch * n1);
+            for (b, mut column) in columns.into_iter().enumerate() {
+                fft_in_place(&domain1, &mut column, inverse);
+                let w = omega.pow([(j2_start + b) as u64]);
+                let mut twiddle = C::ScalarField::one();
+                for p in column.iter_mut() {
+                    *p *= twiddle;
+                    twiddle *= w;
+                }
+                out.extend(column);
+            }
+            transposed.write(
+                j2_start * n1,
+                &C::Projective::batch_normalization_into_affine(&out),
+            )?;
+        }
+
+        // Step 2: column k1 of the transposed file is Y[j2][k1] for j2 in 0..n2. Its FFT is
+        // X[k1 + n1 * k2] for k2 in 0..n2.
+        let batch = (self.chunk_size / n2).min(n1);
+        for k1_start in (0..n1).step_by(batch) {
+            let mut rows = vec![Vec::with_capacity(n2); batch];
+            for j2 in 0..n2 {
+                let segment = transposed.read(j2 * n1 + k1_start, batch)?;
+                for (row, p) in rows.iter_mut().zip(segment.iter()) {
+                    row.push(p.into_projective());
+                }
+            }
+
+            let mut out = Vec::with_capacity(batch * n2);
+            for mut row in rows.into_iter() {
+                fft_in_place(&domain2, &mut row, inverse);
+                out.extend(row);
+            }
+            let out = C::Projective::batch_normalization_into_affine(&out);
+            for k2 in 0..n2 {
+                let segment: Vec<C> = (0..batch).map(|b| out[b * n2 + k2]).collect();
+                output.write(k1_start + n1 * k2, &segment)?;
+            }
+        }
+
+        drop(transposed);
+        fs::remove_file(self.dir.join(transposed_name)).map_err(scratch_error)?;
+        output.flush()?;
+        Ok(output)
+    }
+
+    /*
+     * The commitments to the Lagrange basis polynomials of a domain of size n, from the first n
+     * powers of tau of a scratch file, written to the scratch file of the given name.
+     */
+    pub fn lagrange_comms<C: AffineCurve>(
+        &self,
+        srs: &ScratchFile<C>,
+        n: usize,
+        name: &str,
+    ) -> Result<ScratchFile<C>, Error> {
+        let fft_name = format!("{name}.fft");
+        let p_evals = self.fft(srs, n, &fft_name, false)?;
+        let n_inv = C::ScalarField::from(n as u64).inverse().unwrap();
+
+        // [L_i] = n^-1 * p_evals[-i mod n]
+        let mut output = self.create::<C>(name, n)?;
+        self.for_each_chunk(n, |start, count| {
+            let points = reversed_range(&p_evals, start, count)?;
+            let scalars = vec![n_inv; count];
+            output.write(start, &scale(&points, &scalars))
+        })?;
+
+        drop(p_evals);
+        fs::remove_file(self.dir.join(fft_name)).map_err(scratch_error)?;
+        output.flush()?;
+        Ok(output)
+    }
+
+    /*
+     * The W2 value of every index of a domain of size n, written to w2.bin, using
+     * Z_H(X) / (X - ω^i) = n * ω^-i * L_i(X).
+     */
+    pub fn w2<E: PairingEngine>(
+        &self,
+        srs_g2: &ScratchFile<E::G2Affine>,
+        n: usize,
+    ) -> Result<ScratchFile<E::G2Affine>, Error> {
+        let domain = GeneralEvaluationDomain::<E::Fr>::new(n).unwrap();
+        let lagrange_comms = self.lagrange_comms(srs_g2, n, "lagrange_comms_g2.bin")?;
+        let n_f = domain.size_as_field_element();
+
+        let mut w2 = self.create::<E::G2Affine>("w2.bin", n)?;
+        self.for_each_chunk(n, |start, count| {
+            let points = lagrange_comms.read(start, count)?;
+            let scalars: Vec<E::Fr> = (start..start + count)
+                .map(|i| n_f * domain.element(i).inverse().unwrap())
+                .collect();
+            w2.write(start, &scale(&points, &scalars))
+        })?;
+
+        w2.flush()?;
+        Ok(w2)
+    }
+
+    /*
+     * The commitment of D_i(X) = (L_i(X) - 1) / (X - ω^i) for every index of a domain of size n,
+     * written to w1_diagonal.bin. Expanding L_i(X) = n^-1 * Σ_t (ω^-i * X)^t gives
+     *
+     * D_i(X) = n^-1 * Σ_t (n - 1 - t) * ω^(-i * (t + 1)) * X^t
+     *
+     * so [D_i]_2 = ω^-i * IFFT(g)_i, where g_t = (n - 1 - t) * [τ^t]_2.
+     */
+    pub fn w1_diagonal<E: PairingEngine>(
+        &self,
+        srs_g2: &ScratchFile<E::G2Affine>,
+        n: usize,
+    ) -> Result<ScratchFile<E::G2Affine>, Error> {
+        let domain = GeneralEvaluationDomain::<E::Fr>::new(n).unwrap();
+
+        let mut g = self.create::<E::G2Affine>("w1_diagonal.g.bin", n)?;
+        self.for_each_chunk(n, |start, count| {
+            let points = srs_g2.read(start, count)?;
+            let scalars: Vec<E::Fr> = (start..start + count)
+                .map(|t| E::Fr::from((n - 1 - t) as u64))
+                .collect();
+            g.write(start, &scale(&points, &scalars))
+        })?;
+
+        let g_ifft = self.fft(&g, n, "w1_diagonal.ifft.bin", true)?;
+        let mut diagonal = self.create::<E::G2Affine>("w1_diagonal.bin", n)?;
+        self.for_each_chunk(n, |start, count| {
+            let points = g_ifft.read(start, count)?;
+            let scalars: Vec<E::Fr> = (start..start + count)
+                .map(|i| domain.element(i).inverse().unwrap())
+                .collect();
+            diagonal.write(start, &scale(&points, &scalars))
+        })?;
+
+        drop(g);
+        drop(g_ifft);
+        fs::remove_file(self.dir.join("w1_diagonal.g.bin")).map_err(scratch_error)?;
+        fs::remove_file(self.dir.join("w1_diagonal.ifft.bin")).map_err(scratch_error)?;
+        diagonal.flush()?;
+        Ok(diagonal)
+    }
+
+    /*
+     * The W1 value of every index, written to w1.bin, for C(X) = base + Σ_k δ_k * L_k(X), where
+     * δ_k = values[k] - base, e.g. an accumulator whose empty slots hold the zero leaf. Since
+     * C(X) - C(ω^i) = Σ_k (δ_k - δ_i) * L_k(X), and for k != i,
+     * L_k(X) / (X - ω^i) = ω^k / (n * (ω^k - ω^i)) * (Z_H(X) / (X - ω^k) - Z_H(X) / (X - ω^i)):
+     *
+     * W1_i = Σ_{k != i} δ_k * ω^k / (n * (ω^k - ω^i)) * (W2_k - W2_i) + δ_i * [D_i]_2
+     *
+     * With a_k = δ_k * ω^k / n, g_m = 1 / (ω^m - 1) for m != 0 and g_0 = 0,
+     * 1 / (ω^k - ω^i) = ω^-i * g_(k - i), so the Toeplitz matrix of the sum is circulant, as in
+     * the Feist-Khovratovich method:
+     *
+     * W1_i = ω^-i * (T_i - S_i * W2_i) + δ_i * [D_i]_2
+     *
+     * where T_i = Σ_k g_(k - i) * a_k * W2_k and S_i = Σ_k g_(k - i) * a_k are circular
+     * correlations with g. Since Σ_m g_m * ω^(-m * j) = j - (n - 1) / 2, each correlation is an
+     * FFT, a multiplication by j - (n - 1) / 2, and an inverse FFT. The T_i take two G2 FFTs with
+     * fft(), and the S_i two FFTs of n scalars, which are held in memory as the values are. The
+     * W2 values are read a chunk at a time.
+     * @param w2: The W2 values, from w2().
+     * @param diagonal: The [D_i]_2 values, from w1_diagonal().
+     */
+    pub fn w1<E: PairingEngine>(
+        &self,
+        w2: &ScratchFile<E::G2Affine>,
+        diagonal: &ScratchFile<E::G2Affine>,
+        base: E::Fr,
+        values: &BTreeMap<usize, E::Fr>,
+    ) -> Result<ScratchFile<E::G2Affine>, Error> {
+        let n = w2.len();
+        let domain = GeneralEvaluationDomain::<E::Fr>::new(n).unwrap();
+        let n_f = domain.size_as_field_element();
+
+        let mut a = vec![E::Fr::zero(); n];
+        for (k, v) in values.iter() {
+            a[*k] = (*v - base) * domain.element(*k) / n_f;
+        }
+
+        // The FFT of g
+        let half_n_minus_1 = (n_f - E::Fr::one()) / E::Fr::from(2u64);
+        let g_fft = |j: usize| E::Fr::from(j as u64) - half_n_minus_1;
+
+        let mut p = self.create::<E::G2Affine>("w1.p.bin", n)?;
+        self.for_each_chunk(n, |start, count| {
+            let points = w2.read(start, count)?;
+            p.write(start, &scale(&points, &a[start..start + count]))
+        })?;
+        let mut p_fft = self.fft(&p, n, "w1.p_fft.bin", false)?;
+        self.for_each_chunk(n, |start, count| {
+            let points = p_fft.read(start, count)?;
+            let scalars: Vec<E::Fr> = (start..start + count).map(g_fft).collect();
+            p_fft.write(start, &scale(&points, &scalars))
+        })?;
+        let t = self.fft(&p_fft, n, "w1.t.bin", true)?;
+
+        domain.fft_in_place(&mut a);
+        for (j, a_j) in a.iter_mut().enumerate() {
+            *a_j *= g_fft(j);
+        }
+        domain.ifft_in_place(&mut a);
+        let s = a;
+
+        let mut w1 = self.create::<E::G2Affine>("w1.bin", n)?;
+        self.for_each_chunk(n, |start, count| {
+            let t_i = t.read(start, count)?;
+            let w2_i = w2.read(start, count)?;
+            let diagonal_i = diagonal.read(start, count)?;
+
+            let out: Vec<E::G2Projective> = cfg_into_iter!(0..count)
+                .map(|b| {
+                    let i = start + b;
+                    let omega_i_inv = domain.element(i).inverse().unwrap();
+                    let mut w1_i = t_i[b].mul(omega_i_inv.into_repr());
+                    w1_i -= w2_i[b].mul((omega_i_inv * s[i]).into_repr());
+                    if let Some(value) = values.get(&i) {
+                        let delta_i = *value - base;
+                        if !delta_i.is_zero() {
+                            w1_i += diagonal_i[b].mul(delta_i.into_repr());
+                        }
+                    }
+                    w1_i
+                })
+                .collect();
+            w1.write(
+                start,
+                &<E::G2Projective as ProjectiveCurve>::batch_normalization_into_affine(&out),
+            )
+        })?;
+
+        drop(p);
+        drop(p_fft);
+        drop(t);
+        for name in ["w1.p.bin", "w1.p_fft.bin", "w1.t.bin"] {
+            fs::remove_file(self.dir.join(name)).map_err(scratch_error)?;
+        }
+        w1.flush()?;
+        Ok(w1)
+    }
+
+    /*
+     * The root of the Lagrange tree of a scratch file of Lagrange commitments, which matches
+     * accumulator::compute_lagrange_tree(). Each chunk of leaves is reduced to the root of its
+     * subtree, so the whole tree is never in memory.
+     */
+    pub fn lagrange_root<E: PairingEngine>(
+        &self,
+        lagrange_comms: &ScratchFile<E::G1Affine>,
+    ) -> Result<[u8; 32], Error> {
+        let n = lagrange_comms.len();
+        assert!(n.is_power_of_two());
+
+        let mut subtree_roots = vec![];
+        self.for_each_chunk(n, |start, count| {
+            let comms = lagrange_comms.read(start, count)?;
+            let leaves: Vec<[u8; 32]> = cfg_iter!(comms).map(compute_lagrange_leaf::<E>).collect();
+            subtree_roots.push(merkle_root(leaves));
+            Ok(())
+        })?;
+        Ok(merkle_root(subtree_roots))
+    }
+
+    // Call f with the start and length of each chunk of 0..n
+    fn for_each_chunk<F: FnMut(usize, usize) -> Result<(), Error>>(
+        &self,
+        n: usize,
+        mut f: F,
+    ) -> Result<(), Error> {
+        for start in (0..n).step_by(self.chunk_size) {
+            f(start, self.chunk_size.min(n - start))?;
+        }
+        Ok(())
+    }
+}
+
+fn fft_in_place<F: PrimeField, T: ark_poly::domain::DomainCoeff<F>>(
+    domain: &GeneralEvaluationDomain<F>,
+    values: &mut Vec<T>,
+    inverse: bool,
+) {
+    if inverse {
+        domain.ifft_in_place(values);
+    } else {
+        domain.fft_in_place(values);
+    }
+}
+
+fn to_projective<C: AffineCurve>(points: &[C]) -> Vec<C::Projective> {
+    points.iter().map(|p| p.into_projective()).collect()
+}
+
+// Multiply each point by its scalar
+fn scale<C: AffineCurve>(points: &[C], scalars: &[C::ScalarField]) -> Vec<C> {
+    let scaled: Vec<C::Projective> = points
+        .iter()
+        .zip(scalars.iter())
+        .map(|(p, s)| p.mul(s.into_repr()))
+        .collect();
+    C::Projective::batch_normalization_into_affine(&scaled)
+}
+
+// points[-i mod n] for i in start..start + count
+fn reversed_range<C: AffineCurve>(
+    points: &ScratchFile<C>,
+    start: usize,
+    count: usize,
+) -> Result<Vec<C>, Error> {
+    let n = points.len();
+    let mut out = Vec::with_capacity(count);
+    let mut first = start;
+    if start == 0 {
+        out.push(points.get(0)?);
+        first = 1;
+    }
+    if first < start + count {
+        // -i mod n for i in first..start + count is the range n - (start + count) + 1..=n - first
+        let mut range = points.read(n - (start + count) + 1, start + count - first)?;
+        range.reverse();
+        out.extend(range);
+    }
+    Ok(out)
+}
+
+// The root of a complete binary tree over a power of two leaves
+fn merkle_root(mut level: Vec<[u8; 32]>) -> [u8; 32] {
+    assert!(level.len().is_power_of_two());
+    while level.len() > 1 {
+        level = level
+            .chunks(2)
+            .map(|pair| Keccak256::hash_node(&pair[0], &pair[1]))
+            .collect();
+    }
+    level[0]
+}
+
+fn scratch_error<T: ToString>(e: T) -> Error {
+    Error::ScratchIoError(e.to_string())
+}
+
+#[cfg(test)]
+mod out_of_core_tests {
+    use super::OutOfCore;
+    use crate::accumulator::{compute_lagrange_tree, compute_zero_leaf};
+    use crate::prover::precomputed::CaulkPlusPrecomputed;
+    use crate::setup::verify_srs;
+    use crate::utils::compute_lagrange_basis_commitments;
+    use ark_bn254::{Bn254, Fr};
+    use ark_ff::UniformRand;
+    use ark_poly::{
+        univariate::DensePolynomial, EvaluationDomain, GeneralEvaluationDomain, UVPolynomial,
+    };
+    use ark_std::test_rng;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_out_of_core() {
+        let mut rng = test_rng();
+        let log_2_table_size = 10;
+        let n: usize = 1 << log_2_table_size;
+
+        // A chunk size of 64 forces the four-step FFT, with 32 x 32 matrices
+        let dir = std::env::temp_dir().join("semacaulk_test_out_of_core");
+        let ooc = OutOfCore::new(&dir, 64).unwrap();
+
+        let (srs_g1, srs_g2) = ooc.read_srs("./11.ptau", n + 1, n).unwrap();
+        let srs_g1_vec = srs_g1.to_vec().unwrap();
+        let srs_g2_vec = srs_g2.to_vec().unwrap();
+        assert_eq!(verify_srs(&srs_g1_vec, &srs_g2_vec, &mut rng), Ok(()));
+
+        let lagrange_comms = ooc
+            .lagrange_comms(&srs_g1, n, "lagrange_comms.bin")
+            .unwrap();
+        let expected = compute_lagrange_basis_commitments(srs_g1_vec[..n].to_vec());
+        assert_eq!(lagrange_comms.to_vec().unwrap(), expected);
+        assert_eq!(
+            ooc.lagrange_root::<Bn254>(&lagrange_comms).unwrap(),
+            compute_lagrange_tree::<Bn254>(&expected).root()
+        );
+
+        let domain = GeneralEvaluationDomain::<Fr>::new(n).unwrap();
+        let mut expected = CaulkPlusPrecomputed::<Bn254>::empty();
+        expected.precompute_all_w2(&srs_g2_vec, &domain);
+        let w2 = ooc.w2::<Bn254>(&srs_g2, n).unwrap();
+        for (i, w2_i) in w2.to_vec().unwrap().iter().enumerate() {
+            assert_eq!(*w2_i, expected.get_w2_i(&i));
+        }
+
+        // An accumulator with three values, whose other slots hold the zero leaf
+        let zero = compute_zero_leaf::<Fr>();
+        let values: BTreeMap<usize, Fr> = [(0, Fr::rand(&mut rng)), (5, Fr::rand(&mut rng))]
+            .into_iter()
+            .chain([(n - 1, Fr::rand(&mut rng))])
+            .collect();
+        let mut evals = vec![zero; n];
+        for (i, v) in values.iter() {
+            evals[*i] = *v;
+        }
+        let c = DensePolynomial::from_coefficients_vec(domain.ifft(&evals));
+
+        let diagonal = ooc.w1_diagonal::<Bn254>(&srs_g2, n).unwrap();
+        let w1 = ooc.w1::<Bn254>(&w2, &diagonal, zero, &values).unwrap();
+        let indices = [0, 1, 5, 700, n - 1];
+        expected.precompute_w1(&srs_g2_vec, &indices, &c, &domain);
+        for i in indices {
+            assert_eq!(w1.get(i).unwrap(), expected.get_w1_i(&i));
+        }
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
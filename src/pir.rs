@@ -0,0 +1,250 @@
+/*
+   Two-server XOR private information retrieval (PIR) of precomputed W1 values.
+
+   Fetching {endpoint}/{index} from a precompute server tells it which member is about to prove.
+   Instead, two servers hold the same database of serialised W1 values, one record per index. The
+   client sends each server a bit vector: the first is uniformly random, and the second is the
+   first with the bit of the index flipped. Each server XORs the records whose bits are set and
+   returns the result. XORing the two answers gives the record at the index.
+
+   Each query on its own is a uniformly random bit vector, so neither server learns the index, as
+   long as the two servers do not collude. Each answer costs the server a pass over the whole
+   database, and each query is num_records / 8 bytes.
+*/
+use crate::error::Error;
+use crate::prover::precomputed::CaulkPlusPrecomputed;
+use ark_ec::PairingEngine;
+use ark_ff::Zero;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use rand::{CryptoRng, RngCore};
+
+// The number of bytes of a query
+fn query_len(num_records: usize) -> usize {
+    (num_records + 7) / 8
+}
+
+// A bit vector with one bit per record, packed into bytes in little-endian bit order
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PirQuery {
+    pub num_records: usize,
+    pub bits: Vec<u8>,
+}
+
+impl PirQuery {
+    fn bit(&self, index: usize) -> bool {
+        (self.bits[index / 8] >> (index % 8)) & 1 == 1
+    }
+
+    pub fn to_hex(&self) -> String {
+        hex::encode(&self.bits)
+    }
+
+    pub fn from_hex(s: &str, num_records: usize) -> Result<Self, Error> {
+        let bits = hex::decode(s.trim()).map_err(|_| Error::InvalidPirQuery)?;
+        if bits.len() != query_len(num_records) {
+            return Err(Error::InvalidPirQuery);
+        }
+        Ok(Self { num_records, bits })
+    }
+}
+
+/*
+ * Generate the queries for the two servers.
+ * @param index: The index of the record to retrieve.
+ * @param num_records: The number of records in the database.
+ * @param rng: A cryptographically secure random number generator.
+ */
+pub fn generate_queries<R: RngCore + CryptoRng>(
+    index: usize,
+    num_records: usize,
+    rng: &mut R,
+) -> (PirQuery, PirQuery) {
+    assert!(index < num_records);
+
+    let mut bits = vec![0u8; query_len(num_records)];
+    rng.fill_bytes(&mut bits);
+
+    // Clear the padding bits so that both queries are canonical
+    let padding = bits.len() * 8 - num_records;
+    if padding > 0 {
+        let last = bits.len() - 1;
+        bits[last] &= 0xff >> padding;
+    }
+
+    let query_a = PirQuery {
+        num_records,
+        bits: bits.clone(),
+    };
+
+    bits[index / 8] ^= 1 << (index % 8);
+    let query_b = PirQuery { num_records, bits };
+
+    (query_a, query_b)
+}
+
+// XOR the two servers' answers to recover the record
+pub fn decode_answers(answer_a: &[u8], answer_b: &[u8]) -> Result<Vec<u8>, Error> {
+    if answer_a.len() != answer_b.len() {
+        return Err(Error::InvalidPirAnswer);
+    }
+    Ok(answer_a
+        .iter()
+        .zip(answer_b.iter())
+        .map(|(a, b)| a ^ b)
+        .collect())
+}
+
+// Recover a W1 value from the two servers' answers
+pub fn decode_w1<E: PairingEngine>(answer_a: &[u8], answer_b: &[u8]) -> Result<E::G2Affine, Error> {
+    let record = decode_answers(answer_a, answer_b)?;
+    E::G2Affine::deserialize(record.as_slice()).map_err(|_| Error::InvalidPirAnswer)
+}
+
+/*
+ * The database which each server holds: fixed-size records, stored contiguously in index order.
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PirDatabase {
+    record_size: usize,
+    num_records: usize,
+    data: Vec<u8>,
+}
+
+impl PirDatabase {
+    pub fn new(records: &[Vec<u8>]) -> Self {
+        assert!(!records.is_empty());
+
+        let record_size = records[0].len();
+        let mut data = Vec::with_capacity(record_size * records.len());
+        for record in records.iter() {
+            assert_eq!(record.len(), record_size);
+            data.extend_from_slice(record);
+        }
+
+        Self {
+            record_size,
+            num_records: records.len(),
+            data,
+        }
+    }
+
+    /*
+     * Build the database of serialised W1 values, one per index of the table. Indices without a
+     * precomputed W1 value hold the point at infinity.
+     */
+    pub fn from_w1_mapping<E: PairingEngine>(
+        precomputed: &CaulkPlusPrecomputed<E>,
+        table_size: usize,
+    ) -> Self {
+        let records: Vec<Vec<u8>> = (0..table_size)
+            .map(|index| {
+                let w1 = precomputed
+                    .w1_mapping
+                    .get(&index)
+                    .copied()
+                    .unwrap_or_else(E::G2Affine::zero);
+                let mut record = vec![];
+                w1.serialize(&mut record).unwrap();
+                record
+            })
+            .collect();
+
+        Self::new(&records)
+    }
+
+    pub fn num_records(&self) -> usize {
+        self.num_records
+    }
+
+    pub fn record_size(&self) -> usize {
+        self.record_size
+    }
+
+    pub fn record(&self, index: usize) -> &[u8] {
+        &self.data[index * self.record_size..(index + 1) * self.record_size]
+    }
+
+    // Overwrite the record at the given index in place
+    pub fn set_record(&mut self, index: usize, record: &[u8]) {
+        assert_eq!(record.len(), self.record_size);
+
+        self.data[index * self.record_size..(index + 1) * self.record_size].copy_from_slice(record);
+    }
+
+    // The server's answer: the XOR of the records whose bits are set in the query
+    pub fn answer(&self, query: &PirQuery) -> Result<Vec<u8>, Error> {
+        if query.num_records != self.num_records || query.bits.len() != query_len(self.num_records)
+        {
+            return Err(Error::InvalidPirQuery);
+        }
+
+        let mut answer = vec![0u8; self.record_size];
+        for index in 0..self.num_records {
+            if query.bit(index) {
+                for (a, r) in answer.iter_mut().zip(self.record(index).iter()) {
+                    *a ^= r;
+                }
+            }
+        }
+
+        Ok(answer)
+    }
+}
+
+#[cfg(test)]
+mod pir_tests {
+    use super::{decode_answers, decode_w1, generate_queries, PirDatabase, PirQuery};
+    use crate::error::Error;
+    use crate::prover::precomputed::CaulkPlusPrecomputed;
+    use ark_bn254::{Bn254, Fr, G2Affine};
+    use ark_ec::{AffineCurve, ProjectiveCurve};
+    use ark_std::{rand::RngCore, test_rng};
+
+    #[test]
+    fn test_pir_retrieval() {
+        let mut rng = test_rng();
+        let records: Vec<Vec<u8>> = (0..13)
+            .map(|_| {
+                let mut record = vec![0u8; 20];
+                rng.fill_bytes(&mut record);
+                record
+            })
+            .collect();
+        let db = PirDatabase::new(&records);
+
+        for (index, record) in records.iter().enumerate() {
+            let (query_a, query_b) = generate_queries(index, records.len(), &mut rng);
+            assert_ne!(query_a, query_b);
+
+            // The queries survive the hex encoding
+            let query_a = PirQuery::from_hex(&query_a.to_hex(), records.len()).unwrap();
+            let query_b = PirQuery::from_hex(&query_b.to_hex(), records.len()).unwrap();
+
+            let answer_a = db.answer(&query_a).unwrap();
+            let answer_b = db.answer(&query_b).unwrap();
+            assert_eq!(&decode_answers(&answer_a, &answer_b).unwrap(), record);
+        }
+
+        let (query, _) = generate_queries(0, 8, &mut rng);
+        assert_eq!(db.answer(&query), Err(Error::InvalidPirQuery));
+    }
+
+    #[test]
+    fn test_pir_w1() {
+        let mut rng = test_rng();
+        let table_size = 16;
+
+        let mut precomputed = CaulkPlusPrecomputed::<Bn254>::empty();
+        let g2 = G2Affine::prime_subgroup_generator();
+        for index in [3usize, 7, 8] {
+            let w1 = g2.mul(Fr::from(index as u64 + 1)).into_affine();
+            precomputed.w1_mapping.insert(index, w1);
+        }
+        let db = PirDatabase::from_w1_mapping(&precomputed, table_size);
+
+        let (query_a, query_b) = generate_queries(7, table_size, &mut rng);
+        let w1 = decode_w1::<Bn254>(&db.answer(&query_a).unwrap(), &db.answer(&query_b).unwrap())
+            .unwrap();
+        assert_eq!(w1, precomputed.get_w1_i(&7));
+    }
+}
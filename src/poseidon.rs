@@ -0,0 +1,286 @@
+use crate::constants::{
+    POSEIDON_FULL_ROUNDS, POSEIDON_PARTIAL_ROUNDS, POSEIDON_SINGLE_PARTIAL_ROUNDS,
+    POSEIDON_SINGLE_WIDTH, POSEIDON_WIDTH,
+};
+use ark_ff::{BigInteger, PrimeField};
+
+/*
+ * The Poseidon permutation over the scalar field, with the parameters used by circomlib (x^5
+ * S-box, 8 full rounds, and 57 partial rounds for a state of width 3 or 56 partial rounds for a
+ * state of width 2). The round constants and the MDS matrix are derived with the Grain LFSR from
+ * the Poseidon reference implementation (generate_parameters_grain.sage), so no constants need to
+ * be hardcoded.
+ */
+pub struct Poseidon<F: PrimeField> {
+    pub width: usize,
+    pub n_rounds_f: usize,
+    pub n_rounds_p: usize,
+    // Round constants, POSEIDON_WIDTH per round
+    pub cts: Vec<F>,
+    pub mds: Vec<Vec<F>>,
+}
+
+// circomlib's Poseidon(2), which hashes two inputs
+pub fn init_poseidon<F: PrimeField>() -> Poseidon<F> {
+    Poseidon::<F>::new(
+        POSEIDON_WIDTH,
+        POSEIDON_FULL_ROUNDS,
+        POSEIDON_PARTIAL_ROUNDS,
+    )
+}
+
+// circomlib's Poseidon(1), which hashes a single input
+pub fn init_poseidon_single<F: PrimeField>() -> Poseidon<F> {
+    Poseidon::<F>::new(
+        POSEIDON_SINGLE_WIDTH,
+        POSEIDON_FULL_ROUNDS,
+        POSEIDON_SINGLE_PARTIAL_ROUNDS,
+    )
+}
+
+/*
+ * The Poseidon instances from which Semaphore v2 identities are derived:
+ *  - id_secret = Poseidon(2)(id_nul, id_trap)
+ *  - id_comm = Poseidon(1)(id_secret)
+ *  - nul_hash = Poseidon(2)(ext_nul, id_nul)
+ */
+pub struct SemaphorePoseidon<F: PrimeField> {
+    pub poseidon: Poseidon<F>,
+    pub poseidon_single: Poseidon<F>,
+}
+
+pub fn init_semaphore_poseidon<F: PrimeField>() -> SemaphorePoseidon<F> {
+    SemaphorePoseidon {
+        poseidon: init_poseidon(),
+        poseidon_single: init_poseidon_single(),
+    }
+}
+
+impl<F: PrimeField> SemaphorePoseidon<F> {
+    pub fn identity_secret(&self, identity_nullifier: F, identity_trapdoor: F) -> F {
+        self.poseidon.hash(&[identity_nullifier, identity_trapdoor])
+    }
+}
+
+impl<F: PrimeField> Poseidon<F> {
+    pub fn new(width: usize, n_rounds_f: usize, n_rounds_p: usize) -> Self {
+        let mut grain = GrainLfsr::new(F::size_in_bits(), width, n_rounds_f, n_rounds_p);
+        let cts = Self::initialize_constants(&mut grain, width * (n_rounds_f + n_rounds_p));
+        let mds = Self::initialize_mds(&mut grain, width);
+
+        Self {
+            width,
+            n_rounds_f,
+            n_rounds_p,
+            cts,
+            mds,
+        }
+    }
+
+    pub fn n_rounds(&self) -> usize {
+        self.n_rounds_f + self.n_rounds_p
+    }
+
+    /*
+     * Whether the given round applies the S-box to the whole state. The first and last
+     * n_rounds_f / 2 rounds are full rounds; the rest are partial rounds.
+     */
+    pub fn is_full_round(&self, round: usize) -> bool {
+        round < self.n_rounds_f / 2 || round >= self.n_rounds_f / 2 + self.n_rounds_p
+    }
+
+    /*
+     * Hash width - 1 field elements, as circomlib's Poseidon(width - 1) template does. The
+     * capacity element is initialised to 0.
+     */
+    pub fn hash(&self, inputs: &[F]) -> F {
+        assert_eq!(inputs.len(), self.width - 1);
+
+        let mut state = Vec::with_capacity(self.width);
+        state.push(F::zero());
+        state.extend_from_slice(inputs);
+
+        for round in 0..self.n_rounds() {
+            state = self.round_function(round, &state);
+        }
+
+        state[0]
+    }
+
+    /*
+     * Apply a single round (add round constants, S-box, MDS mix) to the state.
+     */
+    pub fn round_function(&self, round: usize, state: &[F]) -> Vec<F> {
+        let pow_5 = |x: F| x.pow([5, 0, 0, 0]);
+        let full = self.is_full_round(round);
+
+        let sboxed: Vec<F> = state
+            .iter()
+            .enumerate()
+            .map(|(j, s)| {
+                let x = *s + self.cts[round * self.width + j];
+                if full || j == 0 {
+                    pow_5(x)
+                } else {
+                    x
+                }
+            })
+            .collect();
+
+        self.mds
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .zip(sboxed.iter())
+                    .fold(F::zero(), |acc, (m, x)| acc + *m * x)
+            })
+            .collect()
+    }
+
+    fn initialize_constants(grain: &mut GrainLfsr, n: usize) -> Vec<F> {
+        (0..n).map(|_| grain.next_field_element::<F>()).collect()
+    }
+
+    // A Cauchy matrix M[i][j] = 1 / (x_i + y_j) where the x_i and y_j are distinct
+    fn initialize_mds(grain: &mut GrainLfsr, width: usize) -> Vec<Vec<F>> {
+        loop {
+            let vals: Vec<F> = (0..2 * width)
+                .map(|_| grain.next_field_element_without_rejection::<F>())
+                .collect();
+
+            let mut distinct = true;
+            for i in 0..vals.len() {
+                for j in (i + 1)..vals.len() {
+                    distinct &= vals[i] != vals[j];
+                }
+            }
+            if !distinct {
+                continue;
+            }
+
+            let (xs, ys) = vals.split_at(width);
+            let mds: Option<Vec<Vec<F>>> = xs
+                .iter()
+                .map(|x| ys.iter().map(|y| (*x + y).inverse()).collect())
+                .collect();
+
+            if let Some(mds) = mds {
+                return mds;
+            }
+        }
+    }
+}
+
+/*
+ * The 80-bit Grain LFSR used by the Poseidon reference implementation to generate parameters.
+ */
+struct GrainLfsr {
+    state: [bool; 80],
+    field_size: usize,
+}
+
+impl GrainLfsr {
+    fn new(field_size: usize, width: usize, n_rounds_f: usize, n_rounds_p: usize) -> Self {
+        let mut bits = Vec::with_capacity(80);
+        let mut append = |value: usize, n_bits: usize| {
+            for i in (0..n_bits).rev() {
+                bits.push((value >> i) & 1 == 1);
+            }
+        };
+
+        // Prime field (1), x^alpha S-box (0), field size, width, full rounds, partial rounds
+        append(1, 2);
+        append(0, 4);
+        append(field_size, 12);
+        append(width, 12);
+        append(n_rounds_f, 10);
+        append(n_rounds_p, 10);
+        append((1 << 30) - 1, 30);
+
+        let mut state = [false; 80];
+        state.copy_from_slice(&bits);
+
+        let mut grain = Self { state, field_size };
+        for _ in 0..160 {
+            grain.update();
+        }
+        grain
+    }
+
+    fn update(&mut self) -> bool {
+        let s = &self.state;
+        let new_bit = s[62] ^ s[51] ^ s[38] ^ s[23] ^ s[13] ^ s[0];
+        self.state.rotate_left(1);
+        self.state[79] = new_bit;
+        new_bit
+    }
+
+    // Output bits are filtered in pairs: the second bit is kept only if the first bit is 1
+    fn next_bit(&mut self) -> bool {
+        loop {
+            let keep = self.update();
+            let bit = self.update();
+            if keep {
+                return bit;
+            }
+        }
+    }
+
+    // Read field_size bits, most significant first
+    fn next_bits(&mut self) -> Vec<bool> {
+        (0..self.field_size).map(|_| self.next_bit()).collect()
+    }
+
+    // Sample a field element, rejecting integers which are not smaller than the modulus
+    fn next_field_element<F: PrimeField>(&mut self) -> F {
+        loop {
+            let repr = F::BigInt::from_bits_be(&self.next_bits());
+            if let Some(f) = F::from_repr(repr) {
+                return f;
+            }
+        }
+    }
+
+    // Sample a field element, reducing the integer modulo the field order
+    fn next_field_element_without_rejection<F: PrimeField>(&mut self) -> F {
+        let repr = F::BigInt::from_bits_be(&self.next_bits());
+        F::from_be_bytes_mod_order(&repr.to_bytes_be())
+    }
+}
+
+#[cfg(test)]
+mod poseidon_tests {
+    use super::{init_poseidon, init_poseidon_single};
+    use ark_bn254::Fr as F;
+    use ark_ff::field_new;
+
+    #[test]
+    fn test_hash() {
+        let poseidon = init_poseidon::<F>();
+
+        // From https://github.com/iden3/circomlibjs/blob/main/test/poseidon.js
+        let hash = poseidon.hash(&[F::from(1), F::from(2)]);
+        assert_eq!(
+            hash,
+            field_new!(
+                F,
+                "7853200120776062878684798364095072458815029376092732009249414926327459813530"
+            )
+        );
+    }
+
+    #[test]
+    fn test_hash_single() {
+        let poseidon = init_poseidon_single::<F>();
+
+        // From https://github.com/iden3/circomlibjs/blob/main/test/poseidon.js
+        let hash = poseidon.hash(&[F::from(1)]);
+        assert_eq!(
+            hash,
+            field_new!(
+                F,
+                "18586133768512220936620570745912940619677854269274689475585506675881198879027"
+            )
+        );
+    }
+}
@@ -0,0 +1,289 @@
+/*
+   A disk-backed cache of prover precomputed data, so that repeated proofs do not recompute it,
+   and can be generated offline once the cache is populated. The cache directory holds:
+
+   - fixed_<config>.bin: the fixed data of ProverPrecomputedData, once per circuit config, i.e.
+     per set of MiMC7 round constants and circuit domain sizes.
+   - <srs>/w2_<index>.bin: W2 of an index, which only depends on the SRS and the table size.
+   - <srs>/w1_<index>.bin: W1 of an index, with the accumulator and value which it was computed
+     for. A lookup with any other accumulator misses, so W1 values are invalidated as soon as
+     the accumulator moves.
+
+   <srs> identifies the SRS and the table size, so that caches of different setups do not mix.
+*/
+use crate::constants::{EXTENDED_DOMAIN_FACTOR, SUBGROUP_SIZE};
+use crate::error::Error;
+use crate::prover::precomputed::CaulkPlusPrecomputed;
+use crate::prover::{ProverPrecomputedData, ProvingKey};
+use ark_ec::PairingEngine;
+use ark_poly::{EvaluationDomain, GeneralEvaluationDomain};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Read, SerializationError, Write};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tiny_keccak::{Hasher, Keccak};
+
+// A cached W1 value, with the accumulator and the value at the index which it corresponds to
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct CachedW1<E: PairingEngine> {
+    pub accumulator: E::G1Affine,
+    pub value: E::Fr,
+    pub w1: E::G2Affine,
+}
+
+pub struct PrecomputeCache {
+    dir: PathBuf,
+}
+
+impl PrecomputeCache {
+    // Open the cache in the given directory, and create the directory if it does not exist
+    pub fn new<P: AsRef<Path>>(dir: P) -> Result<Self, Error> {
+        fs::create_dir_all(&dir).map_err(cache_error)?;
+        Ok(Self {
+            dir: dir.as_ref().to_path_buf(),
+        })
+    }
+
+    /*
+     * The fixed data of ProverPrecomputedData, i.e. without any W1 or W2 values. It is read from
+     * the cache if present, and otherwise computed and stored.
+     * @param mimc_round_constants: The MiMC7 round constants of the circuit.
+     */
+    pub fn fixed<E: PairingEngine>(
+        &self,
+        mimc_round_constants: &Vec<E::Fr>,
+    ) -> Result<ProverPrecomputedData<E>, Error> {
+        let path = self
+            .dir
+            .join(format!("fixed_{}.bin", config_id(mimc_round_constants)?));
+        if let Some(fixed) = read::<ProverPrecomputedData<E>>(&path)? {
+            return Ok(fixed);
+        }
+
+        let fixed = ProverPrecomputedData::<E>::precompute_fixed(mimc_round_constants);
+        write(&path, &fixed)?;
+        Ok(fixed)
+    }
+
+    /*
+     * W2 of the given index. It is read from the cache if present, and otherwise computed and
+     * stored.
+     */
+    pub fn w2<E: PairingEngine>(
+        &self,
+        pk: &ProvingKey<E>,
+        index: usize,
+        table_size: usize,
+    ) -> Result<E::G2Affine, Error> {
+        let path = self
+            .srs_dir(pk, table_size)?
+            .join(format!("w2_{index}.bin"));
+        if let Some(w2) = read::<E::G2Affine>(&path)? {
+            return Ok(w2);
+        }
+
+        let domain = GeneralEvaluationDomain::<E::Fr>::new(table_size).unwrap();
+        let mut precomputed = CaulkPlusPrecomputed::<E>::empty();
+        precomputed.precompute_w2(&pk.srs_g2, &[index], &domain);
+        let w2 = precomputed.get_w2_i(&index);
+        write(&path, &w2)?;
+        Ok(w2)
+    }
+
+    // The cached W1 of the given index if it corresponds to the given accumulator
+    pub fn w1<E: PairingEngine>(
+        &self,
+        pk: &ProvingKey<E>,
+        index: usize,
+        accumulator: E::G1Affine,
+        table_size: usize,
+    ) -> Result<Option<CachedW1<E>>, Error> {
+        Ok(self
+            .latest_w1(pk, index, table_size)?
+            .filter(|cached| cached.accumulator == accumulator))
+    }
+
+    /*
+     * The most recently cached W1 of the given index, whatever accumulator it corresponds to.
+     * Proofs which use it are only valid against cached.accumulator.
+     */
+    pub fn latest_w1<E: PairingEngine>(
+        &self,
+        pk: &ProvingKey<E>,
+        index: usize,
+        table_size: usize,
+    ) -> Result<Option<CachedW1<E>>, Error> {
+        let path = self
+            .srs_dir(pk, table_size)?
+            .join(format!("w1_{index}.bin"));
+        read::<CachedW1<E>>(&path)
+    }
+
+    // Store W1 of the given index, replacing any value cached for an older accumulator
+    pub fn store_w1<E: PairingEngine>(
+        &self,
+        pk: &ProvingKey<E>,
+        index: usize,
+        cached: &CachedW1<E>,
+        table_size: usize,
+    ) -> Result<(), Error> {
+        let path = self
+            .srs_dir(pk, table_size)?
+            .join(format!("w1_{index}.bin"));
+        write(&path, cached)
+    }
+
+    /*
+     * Load the fixed data and the W2 of the given index, and the W1 of the index if it is
+     * cached for the given accumulator. Returns the precomputed data and whether W1 was found.
+     */
+    pub fn load<E: PairingEngine>(
+        &self,
+        pk: &ProvingKey<E>,
+        mimc_round_constants: &Vec<E::Fr>,
+        index: usize,
+        accumulator: E::G1Affine,
+        table_size: usize,
+    ) -> Result<(ProverPrecomputedData<E>, bool), Error> {
+        let mut precomputed = self.fixed::<E>(mimc_round_constants)?;
+        let w2 = self.w2(pk, index, table_size)?;
+        precomputed
+            .caulk_plus_precomputed
+            .w2_mapping
+            .insert(index, w2);
+
+        let cached = self.w1(pk, index, accumulator, table_size)?;
+        let found = cached.is_some();
+        if let Some(cached) = cached {
+            precomputed.update_w1(index, cached.w1);
+        }
+        Ok((precomputed, found))
+    }
+
+    // The directory of values which depend on the SRS and the table size
+    fn srs_dir<E: PairingEngine>(
+        &self,
+        pk: &ProvingKey<E>,
+        table_size: usize,
+    ) -> Result<PathBuf, Error> {
+        let mut bytes = vec![];
+        (table_size as u64)
+            .serialize(&mut bytes)
+            .map_err(cache_error)?;
+        pk.srs_g1[1].serialize(&mut bytes).map_err(cache_error)?;
+        pk.srs_g2[1].serialize(&mut bytes).map_err(cache_error)?;
+
+        let dir = self.dir.join(hash_to_hex(&bytes));
+        fs::create_dir_all(&dir).map_err(cache_error)?;
+        Ok(dir)
+    }
+}
+
+// Identifies the circuit config which the fixed data depends on
+fn config_id<F: CanonicalSerialize>(mimc_round_constants: &Vec<F>) -> Result<String, Error> {
+    let mut bytes = vec![];
+    mimc_round_constants
+        .serialize(&mut bytes)
+        .map_err(cache_error)?;
+    (SUBGROUP_SIZE as u64)
+        .serialize(&mut bytes)
+        .map_err(cache_error)?;
+    (EXTENDED_DOMAIN_FACTOR as u64)
+        .serialize(&mut bytes)
+        .map_err(cache_error)?;
+    Ok(hash_to_hex(&bytes))
+}
+
+fn hash_to_hex(bytes: &[u8]) -> String {
+    let mut hasher = Keccak::v256();
+    hasher.update(bytes);
+    let mut hash = [0u8; 32];
+    hasher.finalize(&mut hash);
+    hex::encode(&hash[..16])
+}
+
+fn read<T: CanonicalDeserialize>(path: &Path) -> Result<Option<T>, Error> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let bytes = fs::read(path).map_err(cache_error)?;
+    T::deserialize(bytes.as_slice())
+        .map(Some)
+        .map_err(cache_error)
+}
+
+// Write to a temporary file and rename it, so that an interrupted write leaves no partial entry
+fn write<T: CanonicalSerialize>(path: &Path, value: &T) -> Result<(), Error> {
+    let mut bytes = vec![];
+    value.serialize(&mut bytes).map_err(cache_error)?;
+    let tmp_path = path.with_extension("tmp");
+    fs::write(&tmp_path, bytes).map_err(cache_error)?;
+    fs::rename(&tmp_path, path).map_err(cache_error)
+}
+
+fn cache_error<T: ToString>(e: T) -> Error {
+    Error::PrecomputeCacheError(e.to_string())
+}
+
+#[cfg(test)]
+mod precompute_cache_tests {
+    use super::{CachedW1, PrecomputeCache};
+    use crate::accumulator::{compute_zero_leaf, Accumulator};
+    use crate::mimc7::init_mimc7;
+    use crate::setup::setup;
+    use ark_bn254::{Bn254, Fr, G2Affine};
+    use ark_ec::AffineCurve;
+
+    #[test]
+    fn test_precompute_cache() {
+        let log_2_table_size = 10;
+        let table_size: usize = 1 << log_2_table_size;
+        let (pk, lagrange_comms) = setup(log_2_table_size, "./11.ptau").unwrap();
+        let mimc7 = init_mimc7::<Fr>();
+
+        let dir = std::env::temp_dir().join("semacaulk_test_precompute_cache");
+        let _ = std::fs::remove_dir_all(&dir);
+        let cache = PrecomputeCache::new(&dir).unwrap();
+
+        let index = 3;
+        let mut acc = Accumulator::<Bn254>::new(compute_zero_leaf::<Fr>(), &lagrange_comms);
+        acc.update(index, Fr::from(123u64));
+
+        // The first load computes and stores the fixed data and W2, but W1 is missing
+        let (precomputed, found) = cache
+            .load(&pk, &mimc7.cts, index, acc.point, table_size)
+            .unwrap();
+        assert!(!found);
+        let w2 = precomputed.caulk_plus_precomputed.get_w2_i(&index);
+
+        let cached = CachedW1::<Bn254> {
+            accumulator: acc.point,
+            value: Fr::from(123u64),
+            w1: G2Affine::prime_subgroup_generator(),
+        };
+        cache.store_w1(&pk, index, &cached, table_size).unwrap();
+
+        // A second load reads everything from disk
+        let (precomputed, found) = cache
+            .load(&pk, &mimc7.cts, index, acc.point, table_size)
+            .unwrap();
+        assert!(found);
+        assert_eq!(
+            precomputed.caulk_plus_precomputed.get_w1_i(&index),
+            cached.w1
+        );
+        assert_eq!(precomputed.caulk_plus_precomputed.get_w2_i(&index), w2);
+
+        // W1 misses once the accumulator moves, but remains the latest cached value
+        acc.update(index + 1, Fr::from(456u64));
+        let (_, found) = cache
+            .load(&pk, &mimc7.cts, index, acc.point, table_size)
+            .unwrap();
+        assert!(!found);
+        assert_eq!(
+            cache.latest_w1::<Bn254>(&pk, index, table_size).unwrap(),
+            Some(cached)
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,300 @@
+use ark_bn254::{Bn254, Fq, Fr, G1Affine};
+use clap::Parser;
+use ethers::contract::abigen;
+use ethers::providers::{Http, Middleware, Provider};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use semacaulk::bn_solidity_utils::{f_to_hex, u256_to_f};
+use semacaulk::pir::PirQuery;
+use semacaulk::precompute_server::PrecomputeState;
+use semacaulk::prover::ProvingKey;
+use semacaulk::setup::{g2_to_g2_str, setup};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+abigen!(SemacaulkContract, "./src/contracts/Semacaulk.json");
+
+type SharedState = Arc<RwLock<PrecomputeState>>;
+
+/*
+ * Follows the InsertIdentity events of a Semacaulk contract, keeps the W1 and W2 values of every
+ * index current, and serves them over HTTP:
+ *
+ * GET  /<index>        W1 of the index, in the format which try_g2_str_to_g2() parses
+ * GET  /w1/<index>     The same as /<index>
+ * GET  /w2/<index>     W2 of the index
+ * GET  /json/<index>   W1 and W2 of the index, with the accumulator which W1 corresponds to
+ * GET  /accumulator    The accumulator and the number of inserted identities
+ * POST /pir            The answer to a hex-encoded two-server PIR query for a W1 value
+ */
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Cli {
+    /// The RPC endpoint of the chain, such as a local anvil node
+    #[arg(short, long, default_value = "http://127.0.0.1:8545")]
+    rpc: String,
+
+    /// The address of the Semacaulk contract
+    #[arg(short, long)]
+    contract: String,
+
+    #[arg(long)]
+    ptau: String,
+
+    /// log_2 of the capacity of the contract's accumulator
+    #[arg(short, long, default_value_t = 11)]
+    log_2_capacity: usize,
+
+    /// The port to serve precomputed values on
+    #[arg(short, long, default_value_t = 8000)]
+    port: u16,
+
+    /// The number of milliseconds between polls for new events
+    #[arg(short, long, default_value_t = 1000)]
+    interval: u64,
+}
+
+#[tokio::main]
+async fn main() {
+    let cli = Cli::parse();
+
+    println!(
+        "Computing W2 values for 2^{} indices...",
+        cli.log_2_capacity
+    );
+    let (pk, lagrange_comms) = setup(cli.log_2_capacity, &cli.ptau).unwrap();
+    let state = Arc::new(RwLock::new(PrecomputeState::new(&pk, &lagrange_comms)));
+    let keys = Arc::new((pk, lagrange_comms));
+
+    let provider = Provider::<Http>::try_from(cli.rpc.as_str()).unwrap();
+    let address = str_to_ethers_address(&cli.contract);
+    let contract = SemacaulkContract::new(address, Arc::new(provider));
+
+    tokio::spawn(follow_events(
+        contract,
+        state.clone(),
+        keys,
+        Duration::from_millis(cli.interval),
+    ));
+
+    let addr = SocketAddr::from(([0, 0, 0, 0], cli.port));
+    let make_svc = make_service_fn(move |_| {
+        let state = state.clone();
+        async move { Ok::<_, Infallible>(service_fn(move |req| handle(req, state.clone()))) }
+    });
+
+    println!("Serving precomputed values at http://{addr}");
+    Server::bind(&addr).serve(make_svc).await.unwrap();
+}
+
+/*
+ * Poll for InsertIdentity events and apply them to the state. The proving key and Lagrange
+ * commitments are kept to rebuild the state from scratch if it ever stops matching the
+ * accumulator on chain.
+ */
+async fn follow_events(
+    contract: SemacaulkContract<Provider<Http>>,
+    state: SharedState,
+    keys: Arc<(ProvingKey<Bn254>, Vec<G1Affine>)>,
+    interval: Duration,
+) {
+    let mut from_block = 0u64;
+    loop {
+        if let Err(e) = apply_new_events(&contract, &state, &keys, &mut from_block).await {
+            eprintln!("Failed to apply events: {e}");
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
+/*
+ * Apply the events from from_block up to the latest block to a copy of the state, and replace the
+ * served state with it only if its accumulator matches the one on chain. from_block is then
+ * advanced past the latest block. On a mismatch, such as after a missed or reorged event, the
+ * served state is left as it was, and from_block is reset to 0 so that the next poll rebuilds the
+ * state with PrecomputeState::new().
+ */
+async fn apply_new_events(
+    contract: &SemacaulkContract<Provider<Http>>,
+    state: &SharedState,
+    keys: &Arc<(ProvingKey<Bn254>, Vec<G1Affine>)>,
+    from_block: &mut u64,
+) -> Result<(), String> {
+    let latest = contract
+        .client()
+        .get_block_number()
+        .await
+        .map_err(|e| e.to_string())?
+        .as_u64();
+    if latest < *from_block {
+        return Ok(());
+    }
+
+    let events = contract
+        .event::<InsertIdentityFilter>()
+        .from_block(*from_block)
+        .to_block(latest)
+        .query()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    // Without new events, the served state only needs to be checked against the chain
+    let next = if events.is_empty() && *from_block != 0 {
+        None
+    } else {
+        // A served state without insertions is already the empty one, so it is only rebuilt after
+        // a mismatch
+        let base = {
+            let served = state.read().unwrap();
+            if *from_block == 0 && served.size() > 0 {
+                None
+            } else {
+                Some(served.clone())
+            }
+        };
+
+        // Rebuilding the state recomputes every W2 value, and each update takes one G2 scalar
+        // multiplication per index, so run them on a blocking thread rather than on the runtime
+        // threads which serve requests
+        let keys = keys.clone();
+        let next = tokio::task::spawn_blocking(move || {
+            let mut next = base.unwrap_or_else(|| PrecomputeState::new(&keys.0, &keys.1));
+            for event in events.iter() {
+                let index = event.index.as_usize();
+                let identity_commitment = u256_to_f::<Fr>(event.identity_commitment);
+                println!("Inserting identity commitment at index {index}");
+                next.update(index, identity_commitment);
+            }
+            next
+        })
+        .await
+        .map_err(|e| e.to_string())?;
+        Some(next)
+    };
+
+    // Check that the new state corresponds to the accumulator on chain as of the latest block
+    let acc_on_chain = contract
+        .get_accumulator()
+        .block(latest)
+        .call()
+        .await
+        .map_err(|e| e.to_string())?;
+    let acc = match &next {
+        Some(next) => next.accumulator(),
+        None => state.read().unwrap().accumulator(),
+    };
+    if u256_to_f::<Fq>(acc_on_chain.x) != acc.x || u256_to_f::<Fq>(acc_on_chain.y) != acc.y {
+        *from_block = 0;
+        return Err(format!(
+            "the accumulator does not match the one on chain at block {latest}, so the state \
+             will be rebuilt from block 0"
+        ));
+    }
+
+    // Only hold the write lock for the swap, and drop the old state after releasing it
+    if let Some(next) = next {
+        let old = std::mem::replace(&mut *state.write().unwrap(), next);
+        drop(old);
+    }
+    *from_block = latest + 1;
+
+    Ok(())
+}
+
+async fn handle(req: Request<Body>, state: SharedState) -> Result<Response<Body>, Infallible> {
+    let path: Vec<String> = req
+        .uri()
+        .path()
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .map(String::from)
+        .collect();
+    let path: Vec<&str> = path.iter().map(|s| s.as_str()).collect();
+
+    let response = match (req.method(), path.as_slice()) {
+        (&Method::GET, ["accumulator"]) => {
+            let state = state.read().unwrap();
+            let acc = state.accumulator();
+            json_response(serde_json::json!({
+                "accumulator": {
+                    "x": f_to_hex(acc.x),
+                    "y": f_to_hex(acc.y),
+                },
+                "size": state.size(),
+            }))
+        }
+        (&Method::GET, ["json", index]) => match parse_index(index) {
+            Some(index) => match state.read().unwrap().to_json(index) {
+                Some(json) => json_response(json),
+                None => not_found(),
+            },
+            None => bad_request("Invalid index"),
+        },
+        (&Method::GET, [index]) | (&Method::GET, ["w1", index]) => match parse_index(index) {
+            Some(index) => match state.read().unwrap().w1(index) {
+                Some(w1) => Response::new(Body::from(g2_to_g2_str(&w1))),
+                None => not_found(),
+            },
+            None => bad_request("Invalid index"),
+        },
+        (&Method::GET, ["w2", index]) => match parse_index(index) {
+            Some(index) => match state.read().unwrap().w2(index) {
+                Some(w2) => Response::new(Body::from(g2_to_g2_str(&w2))),
+                None => not_found(),
+            },
+            None => bad_request("Invalid index"),
+        },
+        (&Method::POST, ["pir"]) => {
+            let body = match hyper::body::to_bytes(req.into_body()).await {
+                Ok(body) => body,
+                Err(_) => return Ok(bad_request("Invalid body")),
+            };
+            let state = state.read().unwrap();
+            let database = state.pir_database();
+            let answer = std::str::from_utf8(&body)
+                .ok()
+                .and_then(|s| PirQuery::from_hex(s.trim(), database.num_records()).ok())
+                .and_then(|query| database.answer(&query).ok());
+            match answer {
+                Some(answer) => Response::new(Body::from(hex::encode(answer))),
+                None => bad_request("Invalid PIR query"),
+            }
+        }
+        _ => not_found(),
+    };
+
+    Ok(response)
+}
+
+fn parse_index(index: &str) -> Option<usize> {
+    index.parse::<usize>().ok()
+}
+
+fn json_response(json: serde_json::Value) -> Response<Body> {
+    Response::builder()
+        .header("Content-Type", "application/json")
+        .body(Body::from(json.to_string()))
+        .unwrap()
+}
+
+fn bad_request(message: &'static str) -> Response<Body> {
+    Response::builder()
+        .status(StatusCode::BAD_REQUEST)
+        .body(Body::from(message))
+        .unwrap()
+}
+
+fn not_found() -> Response<Body> {
+    Response::builder()
+        .status(StatusCode::NOT_FOUND)
+        .body(Body::empty())
+        .unwrap()
+}
+
+fn str_to_ethers_address(addr: &str) -> ethers::types::H160 {
+    let addr = addr.strip_prefix("0x").unwrap_or(addr);
+    let address = hex::decode(addr).unwrap();
+    ethers::types::H160::from_slice(address.as_slice())
+}
@@ -0,0 +1,214 @@
+/*
+   The state of a precompute server, which follows the identity commitments inserted into a
+   Semacaulk contract and keeps the W1 and W2 values of every index current, so that clients do
+   not have to compute them (see docs/src/precomputation_and_updates.md).
+
+   W2 values do not depend on the accumulator, so they are computed once. W1 values are all the
+   point at infinity for the empty accumulator, whose polynomial is the constant zero leaf. Each
+   insertion at index j adds delta * L_j(X) to C(X), updates the other W1 values with one G2
+   scalar multiplication each (see CaulkPlusPrecomputed::update_w1_for_change()), recomputes W1_j
+   from C(X), and rewrites the PIR records of the W1 values in place.
+*/
+use crate::accumulator::{compute_zero_leaf, Accumulator};
+use crate::bn_solidity_utils::f_to_hex;
+use crate::pir::PirDatabase;
+use crate::prover::precomputed::CaulkPlusPrecomputed;
+use crate::prover::ProvingKey;
+use crate::setup::g2_to_g2_str;
+use ark_bn254::{Bn254, Fr, G1Affine, G2Affine};
+use ark_ff::{Field, Zero};
+use ark_poly::{
+    univariate::DensePolynomial, EvaluationDomain, GeneralEvaluationDomain, UVPolynomial,
+};
+use ark_serialize::CanonicalSerialize;
+
+#[derive(Clone)]
+pub struct PrecomputeState {
+    srs_g2: Vec<G2Affine>,
+    domain: GeneralEvaluationDomain<Fr>,
+    accumulator: Accumulator<Bn254>,
+    values: Vec<Fr>,
+    c: DensePolynomial<Fr>,
+    precomputed: CaulkPlusPrecomputed<Bn254>,
+    pir_database: PirDatabase,
+    size: usize,
+}
+
+impl PrecomputeState {
+    /*
+     * The state of an empty accumulator.
+     * @param pk: The proving key.
+     * @param lagrange_comms: The commitments to the Lagrange basis polynomials, whose length is
+     *                        the table size.
+     */
+    pub fn new(pk: &ProvingKey<Bn254>, lagrange_comms: &[G1Affine]) -> Self {
+        let table_size = lagrange_comms.len();
+        let zero = compute_zero_leaf::<Fr>();
+        let domain = GeneralEvaluationDomain::<Fr>::new(table_size).unwrap();
+
+        let mut precomputed = CaulkPlusPrecomputed::<Bn254>::empty();
+        precomputed.precompute_all_w2(&pk.srs_g2, &domain);
+        for index in 0..table_size {
+            precomputed.w1_mapping.insert(index, G2Affine::zero());
+        }
+
+        let pir_database = PirDatabase::from_w1_mapping(&precomputed, table_size);
+
+        Self {
+            srs_g2: pk.srs_g2[..table_size].to_vec(),
+            domain,
+            accumulator: Accumulator::<Bn254>::new(zero, lagrange_comms),
+            values: vec![zero; table_size],
+            c: DensePolynomial::from_coefficients_slice(&[zero]),
+            precomputed,
+            pir_database,
+            size: 0,
+        }
+    }
+
+    // Set the value at the given index, as an InsertIdentity event does
+    pub fn update(&mut self, index: usize, value: Fr) {
+        assert!(index < self.values.len());
+
+        let old = self.values[index];
+        self.size = self.size.max(index + 1);
+        if old == value {
+            return;
+        }
+
+        // The coefficients of L_j(X) are omega ^ (-j * k) / n, so delta * L_j(X) is added to
+        // C(X) without an IFFT
+        let delta = value - old;
+        let omega_j_inv = self.domain.element(index).inverse().unwrap();
+        let mut coeffs = std::mem::take(&mut self.c.coeffs);
+        coeffs.resize(self.values.len(), Fr::zero());
+        let mut term = delta / self.domain.size_as_field_element();
+        for coeff in coeffs.iter_mut() {
+            *coeff += term;
+            term *= omega_j_inv;
+        }
+        self.c = DensePolynomial::from_coefficients_vec(coeffs);
+
+        self.values[index] = value;
+        self.accumulator.rotate(index, old, value);
+
+        self.precomputed
+            .update_w1_for_change(index, delta, &self.domain);
+        self.precomputed
+            .precompute_w1(&self.srs_g2, &[index], &self.c, &self.domain);
+
+        // Every W1 value changes with an insertion, so every record is rewritten, but in place
+        let mut record = vec![];
+        for (i, w1) in self.precomputed.w1_mapping.iter() {
+            record.clear();
+            w1.serialize(&mut record).unwrap();
+            self.pir_database.set_record(*i, &record);
+        }
+    }
+
+    // The number of indices which have been inserted into
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn accumulator(&self) -> G1Affine {
+        self.accumulator.point
+    }
+
+    pub fn w1(&self, index: usize) -> Option<G2Affine> {
+        self.precomputed.w1_mapping.get(&index).copied()
+    }
+
+    pub fn w2(&self, index: usize) -> Option<G2Affine> {
+        self.precomputed.w2_mapping.get(&index).copied()
+    }
+
+    // The database of serialised W1 values which the server answers PIR queries over
+    pub fn pir_database(&self) -> &PirDatabase {
+        &self.pir_database
+    }
+
+    /*
+     * The precomputed values of an index, with the accumulator point and value which they
+     * correspond to, as a JSON object. G2 points are in the format which try_g2_str_to_g2() parses.
+     */
+    pub fn to_json(&self, index: usize) -> Option<serde_json::Value> {
+        let w1 = self.w1(index)?;
+        let w2 = self.w2(index)?;
+
+        Some(serde_json::json!({
+            "index": index,
+            "value": f_to_hex(self.values[index]),
+            "w1": g2_to_g2_str(&w1),
+            "w2": g2_to_g2_str(&w2),
+            "accumulator": {
+                "x": f_to_hex(self.accumulator.point.x),
+                "y": f_to_hex(self.accumulator.point.y),
+            },
+            "size": self.size,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod precompute_server_tests {
+    use super::PrecomputeState;
+    use crate::pir::PirDatabase;
+    use crate::prover::ProverPrecomputedData;
+    use crate::semaphore_compat::Group;
+    use crate::setup::setup;
+    use ark_bn254::Fr;
+    use ark_poly::{univariate::DensePolynomial, EvaluationDomain, UVPolynomial};
+
+    #[test]
+    fn test_precompute_state() {
+        let log_2_table_size = 10;
+        let table_size: usize = 1 << log_2_table_size;
+        let (pk, lagrange_comms) = setup(log_2_table_size, "./11.ptau").unwrap();
+
+        let mut state = PrecomputeState::new(&pk, &lagrange_comms);
+        let mut group = Group::new(&lagrange_comms);
+        for value in [11u64, 22, 33] {
+            let index = group.add_member(Fr::from(value)).unwrap();
+            state.update(index, Fr::from(value));
+        }
+
+        assert_eq!(state.size(), 3);
+        assert_eq!(state.accumulator(), group.accumulator());
+
+        // The W1 values of inserted and empty slots are current
+        for index in [0, 1, 2, 700] {
+            let value = group
+                .members()
+                .get(index)
+                .copied()
+                .unwrap_or(state.values[index]);
+            assert_eq!(
+                ProverPrecomputedData::verify_w1(
+                    &pk,
+                    state.accumulator(),
+                    index,
+                    value,
+                    state.w1(index).unwrap(),
+                    table_size,
+                ),
+                Ok(())
+            );
+        }
+
+        // C(X) and the PIR records match those computed from scratch
+        let c = DensePolynomial::from_coefficients_vec(state.domain.ifft(&state.values));
+        assert_eq!(state.c, c);
+        assert_eq!(
+            state.pir_database().record(700),
+            PirDatabase::from_w1_mapping(&state.precomputed, table_size).record(700)
+        );
+        assert_eq!(
+            state.pir_database().record(1),
+            PirDatabase::from_w1_mapping(&state.precomputed, table_size).record(1)
+        );
+
+        let json = state.to_json(1).unwrap();
+        assert_eq!(json["size"], 3);
+    }
+}
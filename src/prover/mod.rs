@@ -1,12 +1,19 @@
+use std::collections::BTreeMap;
 use std::iter;
 
 use crate::{
+    accumulator::{compute_prefix_accumulator, compute_rotation_delta, compute_zero_leaf},
     constants::{DUMMY_VALUE, EXTENDED_DOMAIN_FACTOR, NUMBER_OF_MIMC_ROUNDS, SUBGROUP_SIZE},
+    error::Error,
+    identity::rotation_external_nullifier,
+    kzg::{commit_with_table, FixedBaseMsmTable},
+    layouter::{Layouter, PoseidonFixedColumns},
     multiopen::MultiopenProof,
+    poseidon::{init_poseidon, init_poseidon_single, Poseidon},
     utils::compute_vanishing_poly_over_coset,
 };
-use ark_ec::PairingEngine;
-use ark_ff::{One, Zero};
+use ark_ec::{AffineCurve, PairingEngine, ProjectiveCurve};
+use ark_ff::{FpParameters, One, PrimeField, Zero};
 use ark_poly::{
     univariate::DensePolynomial, EvaluationDomain, GeneralEvaluationDomain, UVPolynomial,
 };
@@ -26,6 +33,96 @@ pub struct Proof<E: PairingEngine> {
     pub(crate) commitments: Commitments<E>,
 }
 
+/*
+ * A proof in RLN mode: a regular proof, plus the commitments to the internal_nullifier, epoch,
+ * message_key, message_id, message_id_range and message_limit_range columns, and their openings
+ * at alpha, omega * alpha and omega ^ n * alpha.
+ */
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone, Debug)]
+pub struct RlnProof<E: PairingEngine> {
+    pub proof: Proof<E>,
+    pub(crate) extra_commitments: Vec<E::G1Affine>,
+    pub(crate) extra_openings: Vec<E::Fr>,
+}
+
+/*
+ * A proof for an attribute-bearing identity commitment: a regular proof, plus the commitments to
+ * the attribute_key, attribute, range and attribute_hash columns, and their openings at alpha,
+ * omega * alpha and omega ^ n * alpha.
+ */
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone, Debug)]
+pub struct AttributeProof<E: PairingEngine> {
+    pub proof: Proof<E>,
+    pub(crate) extra_commitments: Vec<E::G1Affine>,
+    pub(crate) extra_openings: Vec<E::Fr>,
+}
+
+/*
+ * A proof for a Poseidon identity commitment: a regular proof whose MiMC7 columns are all zero,
+ * plus the commitments to the state columns of the identity commitment and nullifier hash
+ * instances, and their openings at alpha, omega * alpha and omega ^ n * alpha.
+ */
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone, Debug)]
+pub struct PoseidonProof<E: PairingEngine> {
+    pub proof: Proof<E>,
+    pub(crate) extra_commitments: Vec<E::G1Affine>,
+    pub(crate) extra_openings: Vec<E::Fr>,
+}
+
+/*
+ * A membership-only proof, which proves knowledge of the identity behind a commitment in the
+ * accumulator, bound to a signal hash. It has no w2 column, and no nullifier hash or external
+ * nullifier.
+ */
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone, Debug)]
+pub struct MembershipProof<E: PairingEngine> {
+    pub(crate) multiopen_proof: MultiopenProof<E>,
+    pub openings: MembershipOpenings<E>,
+    pub(crate) commitments: MembershipCommitments<E>,
+}
+
+/*
+ * A linked membership proof, which shows that the same identity is in two groups, with a single
+ * nullifier hash. proof holds the circuit and the Caulk+ lookup against the first accumulator,
+ * and second_lookup holds the Caulk+ lookup of the same identity commitment against the second
+ * accumulator.
+ */
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone, Debug)]
+pub struct LinkedProof<E: PairingEngine> {
+    pub proof: Proof<E>,
+    pub(crate) second_lookup: LinkedLookupProof<E>,
+}
+
+/*
+ * A rotation proof: a regular proof against the accumulator which only holds the old identity
+ * commitment, and the KZG opening of the group accumulator at omega ^ index, which shows that
+ * the old identity commitment is at the public index.
+ */
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone, Debug)]
+pub struct RotationProof<E: PairingEngine> {
+    pub proof: Proof<E>,
+    pub(crate) slot_opening: E::G1Affine,
+}
+
+/*
+ * The commitments and openings of a second Caulk+ lookup, and the multiopen proof of its
+ * openings together with those of the circuit polynomials.
+ */
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone, Debug)]
+pub struct LinkedLookupProof<E: PairingEngine> {
+    pub(crate) multiopen_proof: MultiopenProof<E>,
+    pub(crate) zi: E::G1Affine,
+    pub(crate) ci: E::G1Affine,
+    pub(crate) u_prime: E::G1Affine,
+    pub(crate) h: E::G1Affine,
+    pub(crate) w: E::G2Affine,
+    pub(crate) p1: E::G1Affine,
+    pub(crate) p2: E::G1Affine,
+    pub(crate) u_prime_opening: E::Fr,
+    pub(crate) p1_opening: E::Fr,
+    pub(crate) p2_opening: E::Fr,
+}
+
 #[derive(CanonicalSerialize, CanonicalDeserialize, Clone, Debug)]
 pub struct Openings<E: PairingEngine> {
     pub q_mimc: E::Fr,
@@ -65,6 +162,41 @@ pub struct Commitments<E: PairingEngine> {
     pub(crate) w: E::G2Affine,
 }
 
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone, Debug)]
+pub struct MembershipOpenings<E: PairingEngine> {
+    pub q_mimc: E::Fr,
+    pub(crate) mimc_cts: E::Fr,
+    pub(crate) quotient: E::Fr,
+    pub(crate) u_prime: E::Fr,
+    pub(crate) p1: E::Fr,
+    pub(crate) p2: E::Fr,
+    pub(crate) w0_0: E::Fr,
+    pub(crate) w0_1: E::Fr,
+    pub(crate) w0_2: E::Fr,
+    pub(crate) w1_0: E::Fr,
+    pub(crate) w1_1: E::Fr,
+    pub(crate) w1_2: E::Fr,
+    pub(crate) key_0: E::Fr,
+    pub(crate) key_1: E::Fr,
+}
+
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone, Debug)]
+pub struct MembershipCommitments<E: PairingEngine> {
+    pub(crate) w0: E::G1Affine,
+    pub(crate) w1: E::G1Affine,
+    pub(crate) key: E::G1Affine,
+    pub(crate) mimc_cts: E::G1Affine,
+    pub(crate) quotient: E::G1Affine,
+    pub(crate) u_prime: E::G1Affine,
+    pub(crate) zi: E::G1Affine,
+    pub(crate) ci: E::G1Affine,
+    pub(crate) p1: E::G1Affine,
+    pub(crate) p2: E::G1Affine,
+    pub(crate) q_mimc: E::G1Affine,
+    pub(crate) h: E::G1Affine,
+    pub(crate) w: E::G2Affine,
+}
+
 #[derive(CanonicalSerialize, CanonicalDeserialize, Clone, Debug)]
 pub struct ProverPrecomputedData<E: PairingEngine> {
     pub(crate) mimc_cts: DensePolynomial<E::Fr>, // mimc round constants poly
@@ -73,6 +205,9 @@ pub struct ProverPrecomputedData<E: PairingEngine> {
     pub(crate) q_mimc: DensePolynomial<E::Fr>,
     pub(crate) q_mimc_coset_evals: Vec<E::Fr>,
     pub(crate) l0_coset_evals: Vec<E::Fr>,
+    // The fixed columns of the Poseidon circuit over the coset, for Poseidon(2) and Poseidon(1)
+    pub(crate) poseidon_coset_evals: PoseidonFixedColumns<E::Fr>,
+    pub(crate) poseidon_single_coset_evals: PoseidonFixedColumns<E::Fr>,
     pub caulk_plus_precomputed: CaulkPlusPrecomputed<E>,
 }
 
@@ -142,6 +277,15 @@ impl<E: PairingEngine> ProverPrecomputedData<E> {
         let l0 = DensePolynomial::from_coefficients_slice(&domain.ifft(&l0_evals));
         let l0_coset_evals = extended_coset_domain.coset_fft(&l0);
 
+        // Compute the coset evals of the fixed columns of the Poseidon circuit
+        let poseidon_coset_evals =
+            Self::poseidon_fixed_coset_evals(&init_poseidon(), domain, extended_coset_domain);
+        let poseidon_single_coset_evals = Self::poseidon_fixed_coset_evals(
+            &init_poseidon_single(),
+            domain,
+            extended_coset_domain,
+        );
+
         // Precompute w1 & w2 for the Caulk+ part of the proof
         //let domain_t = GeneralEvaluationDomain::new(table_size).unwrap();
         let caulk_plus_precomputed = CaulkPlusPrecomputed::<E>::empty();
@@ -153,10 +297,33 @@ impl<E: PairingEngine> ProverPrecomputedData<E> {
             q_mimc,
             q_mimc_coset_evals,
             l0_coset_evals,
+            poseidon_coset_evals,
+            poseidon_single_coset_evals,
             caulk_plus_precomputed,
         }
     }
 
+    // The fixed columns of a Poseidon instance of the Poseidon circuit, over the coset
+    fn poseidon_fixed_coset_evals(
+        poseidon: &Poseidon<E::Fr>,
+        domain: GeneralEvaluationDomain<E::Fr>,
+        extended_coset_domain: GeneralEvaluationDomain<E::Fr>,
+    ) -> PoseidonFixedColumns<E::Fr> {
+        let fixed = Layouter::assign_poseidon_fixed(poseidon);
+        let coset_evals = |evals: &Vec<E::Fr>| {
+            let poly = DensePolynomial::from_coefficients_slice(&domain.ifft(evals));
+            extended_coset_domain.coset_fft(&poly)
+        };
+
+        PoseidonFixedColumns {
+            q_full: coset_evals(&fixed.q_full),
+            q_partial: coset_evals(&fixed.q_partial),
+            q_pass: coset_evals(&fixed.q_pass),
+            cts: fixed.cts.iter().map(coset_evals).collect(),
+            mds: fixed.mds,
+        }
+    }
+
     /// Update one W1 commitment
     pub fn update_w1(&mut self, index: usize, new_w1: E::G2Affine) {
         self.caulk_plus_precomputed.w1_mapping.insert(index, new_w1);
@@ -175,6 +342,80 @@ impl<E: PairingEngine> ProverPrecomputedData<E> {
             .precompute_w1(&pk.srs_g2, indices, c, &domain_t);
     }
 
+    /// Update one W1 commitment of a parallel accumulator column
+    pub fn update_column_w1(&mut self, column: usize, index: usize, new_w1: E::G2Affine) {
+        assert!(column > 0, "column 0 is the identity commitment column");
+        let mappings = &mut self.caulk_plus_precomputed.column_w1_mappings;
+        if mappings.len() < column {
+            mappings.resize(column, BTreeMap::default());
+        }
+        mappings[column - 1].insert(index, new_w1);
+    }
+
+    /// Precompute the W1 commitments of a parallel accumulator column, for tuple lookups
+    pub fn precompute_column_w1(
+        &mut self,
+        pk: &ProvingKey<E>,
+        column: usize,
+        indices: &[usize],
+        c: &DensePolynomial<E::Fr>,
+        table_size: usize,
+    ) {
+        let domain_t = GeneralEvaluationDomain::new(table_size).unwrap();
+        self.caulk_plus_precomputed
+            .precompute_column_w1(&pk.srs_g2, column, indices, c, &domain_t);
+    }
+
+    /*
+     * Check an untrusted [W1^(i)]_2, such as one fetched from a precompute server, against the
+     * current accumulator C, i.e. that e(C - [c_i]_1, [1]_2) = e([X - ω^i]_1, [W1^(i)]_2).
+     * @param accumulator: The current accumulator.
+     * @param index: The index i.
+     * @param value: The value c_i at the index, e.g. the identity commitment.
+     * @param w1: The W1 value to check.
+     */
+    pub fn verify_w1(
+        pk: &ProvingKey<E>,
+        accumulator: E::G1Affine,
+        index: usize,
+        value: E::Fr,
+        w1: E::G2Affine,
+        table_size: usize,
+    ) -> Result<(), Error> {
+        if !w1.mul(<E::Fr as PrimeField>::Params::MODULUS).is_zero() {
+            return Err(Error::G2PointNotInSubgroup);
+        }
+
+        let domain_t = GeneralEvaluationDomain::<E::Fr>::new(table_size).unwrap();
+        let g1 = pk.srs_g1[0];
+        let g2 = pk.srs_g2[0];
+
+        let lhs_g1 = (accumulator.into_projective() - g1.mul(value)).into_affine();
+        let rhs_g1 =
+            (pk.srs_g1[1].into_projective() - g1.mul(domain_t.element(index))).into_affine();
+
+        if E::pairing(lhs_g1, g2) != E::pairing(rhs_g1, w1) {
+            return Err(Error::W1PairingCheckFailed);
+        }
+
+        Ok(())
+    }
+
+    // Check an untrusted W1 value with verify_w1(), and insert it if it is valid
+    pub fn update_w1_checked(
+        &mut self,
+        pk: &ProvingKey<E>,
+        accumulator: E::G1Affine,
+        index: usize,
+        value: E::Fr,
+        w1: E::G2Affine,
+        table_size: usize,
+    ) -> Result<(), Error> {
+        Self::verify_w1(pk, accumulator, index, value, w1, table_size)?;
+        self.update_w1(index, w1);
+        Ok(())
+    }
+
     /// Precompute the W2 commitments
     pub fn precompute_w2(&mut self, pk: &ProvingKey<E>, indices: &[usize], table_size: usize) {
         let domain_t = GeneralEvaluationDomain::new(table_size).unwrap();
@@ -183,9 +424,117 @@ impl<E: PairingEngine> ProverPrecomputedData<E> {
     }
 }
 
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct ProvingKey<E: PairingEngine> {
     pub srs_g1: Vec<E::G1Affine>,
     pub srs_g2: Vec<E::G2Affine>,
+    // If set by precompute_msm_table(), G1 commitments use this table of a prefix of srs_g1
+    pub g1_msm_table: Option<FixedBaseMsmTable<E::G1Affine>>,
+}
+
+// The table is not serialised, as it is larger than the SRS prefix and quick to rebuild
+impl<E: PairingEngine> CanonicalSerialize for ProvingKey<E> {
+    fn serialize<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        self.srs_g1.serialize(&mut writer)?;
+        self.srs_g2.serialize(&mut writer)
+    }
+
+    fn serialized_size(&self) -> usize {
+        self.srs_g1.serialized_size() + self.srs_g2.serialized_size()
+    }
+
+    fn serialize_uncompressed<W: Write>(&self, mut writer: W) -> Result<(), SerializationError> {
+        self.srs_g1.serialize_uncompressed(&mut writer)?;
+        self.srs_g2.serialize_uncompressed(&mut writer)
+    }
+
+    fn uncompressed_size(&self) -> usize {
+        self.srs_g1.uncompressed_size() + self.srs_g2.uncompressed_size()
+    }
+}
+
+impl<E: PairingEngine> CanonicalDeserialize for ProvingKey<E> {
+    fn deserialize<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        let srs_g1 = Vec::deserialize(&mut reader)?;
+        let srs_g2 = Vec::deserialize(&mut reader)?;
+        Ok(Self::new(srs_g1, srs_g2))
+    }
+
+    fn deserialize_uncompressed<R: Read>(mut reader: R) -> Result<Self, SerializationError> {
+        let srs_g1 = Vec::deserialize_uncompressed(&mut reader)?;
+        let srs_g2 = Vec::deserialize_uncompressed(&mut reader)?;
+        Ok(Self::new(srs_g1, srs_g2))
+    }
+}
+
+/*
+ * The number of G1 and G2 powers of tau which ProvingKey::trim() keeps. The default is what the
+ * prover needs once the W1 and W2 values are supplied, e.g. by a precompute server: the quotient
+ * polynomial has a degree below EXTENDED_DOMAIN_FACTOR * SUBGROUP_SIZE, and is the largest
+ * polynomial committed to in G1, while the only G2 commitment is to the blinder of C_I(X), which
+ * has 3 coefficients.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TrimConfig {
+    pub num_g1_points: usize,
+    pub num_g2_points: usize,
+}
+
+impl Default for TrimConfig {
+    fn default() -> Self {
+        Self {
+            num_g1_points: EXTENDED_DOMAIN_FACTOR * SUBGROUP_SIZE,
+            num_g2_points: 3,
+        }
+    }
+}
+
+impl<E: PairingEngine> ProvingKey<E> {
+    pub fn new(srs_g1: Vec<E::G1Affine>, srs_g2: Vec<E::G2Affine>) -> Self {
+        Self {
+            srs_g1,
+            srs_g2,
+            g1_msm_table: None,
+        }
+    }
+
+    /*
+     * Build a fixed-base table of the first EXTENDED_DOMAIN_FACTOR * SUBGROUP_SIZE G1 powers of
+     * tau, which every G1 commitment of the prover fits in, so that the prover's commitments
+     * skip the doublings and bucket sums of VariableBaseMSM.
+     */
+    pub fn precompute_msm_table(&mut self) {
+        let num_points = self
+            .srs_g1
+            .len()
+            .min(EXTENDED_DOMAIN_FACTOR * SUBGROUP_SIZE);
+        self.g1_msm_table = Some(FixedBaseMsmTable::new(&self.srs_g1[..num_points]));
+    }
+
+    // Commit to a polynomial in G1, with the fixed-base table if there is one
+    pub fn commit_g1(&self, poly: &DensePolynomial<E::Fr>) -> E::G1Projective {
+        commit_with_table(&self.srs_g1, self.g1_msm_table.as_ref(), poly)
+    }
+
+    /*
+     * Keep only the first powers of tau, so that a prover which fetches its W1 and W2 values
+     * remotely can load a key of kilobytes rather than the whole SRS. A trimmed key cannot
+     * precompute W1 or W2 values, or commit to an accumulator.
+     */
+    pub fn trim(&self, config: TrimConfig) -> Result<Self, Error> {
+        if self.srs_g1.len() < config.num_g1_points || self.srs_g2.len() < config.num_g2_points {
+            return Err(Error::SrsTooShort);
+        }
+        Ok(Self {
+            srs_g1: self.srs_g1[..config.num_g1_points].to_vec(),
+            srs_g2: self.srs_g2[..config.num_g2_points].to_vec(),
+            g1_msm_table: self
+                .g1_msm_table
+                .as_ref()
+                .filter(|table| table.len() <= config.num_g1_points)
+                .cloned(),
+        })
+    }
 }
 
 #[derive(CanonicalSerialize, CanonicalDeserialize, Clone, Debug)]
@@ -195,3 +544,237 @@ pub struct PublicData<E: PairingEngine> {
     pub signal_hash: E::Fr,
     pub nullifier_hash: E::Fr,
 }
+
+/*
+ * The public inputs of a membership-only proof. The signal hash binds the proof to a message,
+ * such as a challenge issued by an off-chain API.
+ */
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone, Debug)]
+pub struct MembershipPublicData<E: PairingEngine> {
+    pub accumulator: E::G1Affine,
+    pub signal_hash: E::Fr,
+}
+
+impl<E: PairingEngine> MembershipPublicData<E> {
+    // The nullifier gates are not enforced in membership-only mode, so the external nullifier
+    // and nullifier hash are unused
+    pub(crate) fn to_public_data(&self) -> PublicData<E> {
+        PublicData {
+            accumulator: self.accumulator,
+            external_nullifier: E::Fr::zero(),
+            signal_hash: self.signal_hash,
+            nullifier_hash: E::Fr::zero(),
+        }
+    }
+}
+
+/*
+ * The public inputs in RLN mode. The signal hash is the x-coordinate of the Shamir share, and
+ * the RLN nullifier takes the place of the nullifier hash. The external nullifier is
+ * rln::rln_external_nullifier() of the epoch and a private message ID, which the circuit checks
+ * is below message_limit, so messages of the same epoch are unlinkable until a message ID is
+ * reused.
+ */
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone, Debug)]
+pub struct RlnPublicData<E: PairingEngine> {
+    pub accumulator: E::G1Affine,
+    pub epoch: E::Fr,
+    pub message_limit: u64,
+    pub signal_hash: E::Fr,
+    pub share_y: E::Fr,
+    pub rln_nullifier: E::Fr,
+}
+
+impl<E: PairingEngine> RlnPublicData<E> {
+    // The public inputs which are shared with the regular circuit. The epoch takes the place of
+    // the external nullifier.
+    pub(crate) fn to_public_data(&self) -> PublicData<E> {
+        PublicData {
+            accumulator: self.accumulator,
+            external_nullifier: self.epoch,
+            signal_hash: self.signal_hash,
+            nullifier_hash: self.rln_nullifier,
+        }
+    }
+}
+
+/*
+ * The public inputs for an attribute-bearing identity commitment. The proof shows that the
+ * attribute is at least threshold, and if reveal_attribute_hash is set, that attribute_hash is
+ * the MiMC7 hash of the attribute.
+ */
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone, Debug)]
+pub struct AttributePublicData<E: PairingEngine> {
+    pub accumulator: E::G1Affine,
+    pub external_nullifier: E::Fr,
+    pub signal_hash: E::Fr,
+    pub nullifier_hash: E::Fr,
+    pub threshold: E::Fr,
+    pub reveal_attribute_hash: bool,
+    pub attribute_hash: E::Fr,
+}
+
+impl<E: PairingEngine> AttributePublicData<E> {
+    pub(crate) fn to_public_data(&self) -> PublicData<E> {
+        PublicData {
+            accumulator: self.accumulator,
+            external_nullifier: self.external_nullifier,
+            signal_hash: self.signal_hash,
+            nullifier_hash: self.nullifier_hash,
+        }
+    }
+}
+
+/*
+ * The public inputs of a tuple lookup, which proves that the identity commitment and the public
+ * values sit at the same hidden index of parallel accumulators. accumulators[0] is the identity
+ * commitment accumulator, and values[j] is the value at the member's index of
+ * accumulators[j + 1] (e.g. the member's weight).
+ */
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone, Debug)]
+pub struct TuplePublicData<E: PairingEngine> {
+    pub accumulators: Vec<E::G1Affine>,
+    pub values: Vec<E::Fr>,
+    pub external_nullifier: E::Fr,
+    pub signal_hash: E::Fr,
+    pub nullifier_hash: E::Fr,
+}
+
+impl<E: PairingEngine> TuplePublicData<E> {
+    pub(crate) fn to_public_data(&self) -> PublicData<E> {
+        PublicData {
+            accumulator: self.accumulators[0],
+            external_nullifier: self.external_nullifier,
+            signal_hash: self.signal_hash,
+            nullifier_hash: self.nullifier_hash,
+        }
+    }
+}
+
+/*
+ * The public inputs of a prefix proof, which shows that the member's index is less than k. The
+ * lookup is performed against the prefix accumulator of the first k slots of the group (see
+ * accumulator::compute_prefix_accumulator()), which the prover and the verifier compute from the
+ * group's members.
+ */
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone, Debug)]
+pub struct PrefixPublicData<E: PairingEngine> {
+    pub accumulator: E::G1Affine,
+    pub k: usize,
+    pub external_nullifier: E::Fr,
+    pub signal_hash: E::Fr,
+    pub nullifier_hash: E::Fr,
+}
+
+impl<E: PairingEngine> PrefixPublicData<E> {
+    /*
+     * The prefix accumulator of the first k of the given members. Returns
+     * Error::InvalidPrefixLength if k is greater than the capacity of the group.
+     * @param lagrange_comms: The commitments to the Lagrange basis polynomials.
+     * @param members: The identity commitments of the group, in index order.
+     */
+    pub fn prefix_accumulator(
+        &self,
+        lagrange_comms: &[E::G1Affine],
+        members: &[E::Fr],
+    ) -> Result<E::G1Affine, Error> {
+        if self.k > lagrange_comms.len() || members.len() > lagrange_comms.len() {
+            return Err(Error::InvalidPrefixLength);
+        }
+
+        Ok(compute_prefix_accumulator::<E>(
+            compute_zero_leaf::<E::Fr>(),
+            lagrange_comms,
+            members,
+            self.k,
+        ))
+    }
+
+    // The lookup is performed against the prefix accumulator rather than the group accumulator
+    pub(crate) fn to_public_data(&self, prefix_accumulator: E::G1Affine) -> PublicData<E> {
+        PublicData {
+            accumulator: prefix_accumulator,
+            external_nullifier: self.external_nullifier,
+            signal_hash: self.signal_hash,
+            nullifier_hash: self.nullifier_hash,
+        }
+    }
+}
+
+/*
+ * The public inputs of a linked membership proof: the accumulators of the two groups, and a
+ * single nullifier hash.
+ */
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone, Debug)]
+pub struct LinkedPublicData<E: PairingEngine> {
+    pub accumulator: E::G1Affine,
+    pub second_accumulator: E::G1Affine,
+    pub external_nullifier: E::Fr,
+    pub signal_hash: E::Fr,
+    pub nullifier_hash: E::Fr,
+}
+
+impl<E: PairingEngine> LinkedPublicData<E> {
+    pub(crate) fn to_public_data(&self) -> PublicData<E> {
+        PublicData {
+            accumulator: self.accumulator,
+            external_nullifier: self.external_nullifier,
+            signal_hash: self.signal_hash,
+            nullifier_hash: self.nullifier_hash,
+        }
+    }
+}
+
+/*
+ * The public inputs of a rotation proof, which authorises the replacement of
+ * old_identity_commitment at index with new_identity_commitment. The external nullifier is
+ * identity::rotation_external_nullifier() and the signal hash is new_identity_commitment. The
+ * index and the old identity commitment are public, so a rotation is linkable to the member's
+ * slot.
+ */
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone, Debug)]
+pub struct RotationPublicData<E: PairingEngine> {
+    pub accumulator: E::G1Affine,
+    pub index: usize,
+    pub old_identity_commitment: E::Fr,
+    pub new_identity_commitment: E::Fr,
+    pub nullifier_hash: E::Fr,
+}
+
+impl<E: PairingEngine> RotationPublicData<E> {
+    /*
+     * The circuit and the Caulk+ lookup of a rotation proof are against the accumulator which
+     * only holds old_identity_commitment, at index 0.
+     */
+    pub(crate) fn to_public_data(&self, lagrange_comms: &[E::G1Affine]) -> PublicData<E> {
+        PublicData {
+            accumulator: compute_prefix_accumulator::<E>(
+                compute_zero_leaf::<E::Fr>(),
+                lagrange_comms,
+                &[self.old_identity_commitment],
+                1,
+            ),
+            external_nullifier: rotation_external_nullifier::<E::Fr>(),
+            signal_hash: self.new_identity_commitment,
+            nullifier_hash: self.nullifier_hash,
+        }
+    }
+
+    /*
+     * The delta which replaces old_identity_commitment with new_identity_commitment in the
+     * accumulator (see accumulator::compute_rotation_delta()). Returns
+     * Error::IdentityNotInGroup if the index is out of range.
+     * @param lagrange_comms: The commitments to the Lagrange basis polynomials.
+     */
+    pub fn delta(&self, lagrange_comms: &[E::G1Affine]) -> Result<E::G1Affine, Error> {
+        let lagrange_comm = lagrange_comms
+            .get(self.index)
+            .ok_or(Error::IdentityNotInGroup)?;
+
+        Ok(compute_rotation_delta::<E>(
+            *lagrange_comm,
+            self.old_identity_commitment,
+            self.new_identity_commitment,
+        ))
+    }
+}
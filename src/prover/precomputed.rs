@@ -1,25 +1,34 @@
 use std::collections::BTreeMap;
 
-use ark_ec::PairingEngine;
-use ark_ff::One;
+use ark_ec::{AffineCurve, PairingEngine, ProjectiveCurve};
+use ark_ff::{Field, One, PrimeField};
 use ark_poly::{
     univariate::DensePolynomial, EvaluationDomain, GeneralEvaluationDomain, Polynomial,
     UVPolynomial,
 };
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Read, SerializationError, Write};
+use ark_std::cfg_iter;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 
 use crate::kzg::commit;
+use crate::utils::compute_lagrange_basis_commitments;
 
 /*
    Precomputed data will be stored in <key, value> map for key = index, value = [w_{1,2}^i]_2
 
    We can precompute all data, but it's very possible that just some indices will be needed,
    so we optimize precomputed data needed to store
+
+   For tuple lookups, column_w1_mappings holds the W1 values of each column which is stored in a
+   parallel accumulator (e.g. member weights). Column 0 of the tuple is the identity commitment
+   column, whose W1 values are in w1_mapping, so column_w1_mappings[j] is for column j + 1.
 */
 #[derive(CanonicalSerialize, CanonicalDeserialize, Clone, Debug)]
 pub struct CaulkPlusPrecomputed<E: PairingEngine> {
     pub(crate) w1_mapping: BTreeMap<usize, E::G2Affine>,
     pub(crate) w2_mapping: BTreeMap<usize, E::G2Affine>,
+    pub(crate) column_w1_mappings: Vec<BTreeMap<usize, E::G2Affine>>,
 }
 
 impl<E: PairingEngine> CaulkPlusPrecomputed<E> {
@@ -27,6 +36,7 @@ impl<E: PairingEngine> CaulkPlusPrecomputed<E> {
         Self {
             w1_mapping: BTreeMap::default(),
             w2_mapping: BTreeMap::default(),
+            column_w1_mappings: vec![],
         }
     }
 
@@ -44,6 +54,20 @@ impl<E: PairingEngine> CaulkPlusPrecomputed<E> {
         }
     }
 
+    pub fn get_column_w1_i(&self, column: usize, index: &usize) -> E::G2Affine {
+        match self
+            .column_w1_mappings
+            .get(column - 1)
+            .and_then(|mapping| mapping.get(index))
+        {
+            Some(element) => *element,
+            None => panic!(
+                "Element on index: {} of column: {} is not precomputed",
+                index, column
+            ),
+        }
+    }
+
     pub fn precompute_w1(
         &mut self,
         srs: &[E::G2Affine],
@@ -52,19 +76,47 @@ impl<E: PairingEngine> CaulkPlusPrecomputed<E> {
         domain: &GeneralEvaluationDomain<E::Fr>,
     ) {
         // As defined in the [Caulk+ paper, section 3](https://eprint.iacr.org/2022/957.pdf).
-        for index in indices {
-            let w_i = domain.element(*index);
-            let mut num = c.clone();
-            num[0] -= c.evaluate(&w_i);
-
-            // denom = (X - w_i)
-            let denom = DensePolynomial::from_coefficients_slice(&[-w_i, E::Fr::one()]);
-
-            // w1_i = (C - c_i) / (X - w_i)
-            let w1_i = &num / &denom;
-            let w1_i = commit(srs, &w1_i);
-            self.w1_mapping.insert(*index, w1_i.into());
+        let w1: Vec<E::G2Affine> = cfg_iter!(indices)
+            .map(|index| {
+                let w_i = domain.element(*index);
+                let mut num = c.clone();
+                num[0] -= c.evaluate(&w_i);
+
+                // denom = (X - w_i)
+                let denom = DensePolynomial::from_coefficients_slice(&[-w_i, E::Fr::one()]);
+
+                // w1_i = (C - c_i) / (X - w_i)
+                let w1_i = &num / &denom;
+                commit(srs, &w1_i).into()
+            })
+            .collect();
+        self.w1_mapping.extend(indices.iter().copied().zip(w1));
+    }
+
+    /*
+     * Precompute the W1 values of a parallel accumulator column.
+     * @param column: The column of the tuple, starting from 1.
+     * @param c: The polynomial whose commitment is the accumulator of the column.
+     */
+    pub fn precompute_column_w1(
+        &mut self,
+        srs: &[E::G2Affine],
+        column: usize,
+        indices: &[usize],
+        c: &DensePolynomial<E::Fr>,
+        domain: &GeneralEvaluationDomain<E::Fr>,
+    ) {
+        assert!(column > 0, "column 0 is the identity commitment column");
+
+        // The W1 values of a column are computed in the same way as those of the identity
+        // commitment column, so reuse precompute_w1() on a scratch instance
+        let mut column_precomputed = Self::empty();
+        column_precomputed.precompute_w1(srs, indices, c, domain);
+
+        if self.column_w1_mappings.len() < column {
+            self.column_w1_mappings.resize(column, BTreeMap::default());
         }
+        self.column_w1_mappings[column - 1].append(&mut column_precomputed.w1_mapping);
     }
 
     pub fn precompute_w2(
@@ -75,14 +127,86 @@ impl<E: PairingEngine> CaulkPlusPrecomputed<E> {
     ) {
         // As defined in the [Caulk+ paper, section 3](https://eprint.iacr.org/2022/957.pdf).
         let zh: DensePolynomial<_> = domain.vanishing_polynomial().into();
-        for index in indices {
-            let w2_i = &zh
-                / &DensePolynomial::from_coefficients_slice(&[
-                    -domain.element(*index),
-                    E::Fr::one(),
-                ]);
-            let w2_i = commit(srs, &w2_i);
-            self.w2_mapping.insert(*index, w2_i.into());
+        let w2: Vec<E::G2Affine> = cfg_iter!(indices)
+            .map(|index| {
+                let w2_i = &zh
+                    / &DensePolynomial::from_coefficients_slice(&[
+                        -domain.element(*index),
+                        E::Fr::one(),
+                    ]);
+                commit(srs, &w2_i).into()
+            })
+            .collect();
+        self.w2_mapping.extend(indices.iter().copied().zip(w2));
+    }
+
+    /*
+     * Precompute the W2 values of every index in O(n log n) rather than O(n ^ 2), using
+     * Z_H(X) / (X - ω^i) = n * ω^-i * L_i(X), where [L_i]_2 are the commitments to the Lagrange
+     * basis polynomials in G2.
+     */
+    pub fn precompute_all_w2(
+        &mut self,
+        srs: &[E::G2Affine],
+        domain: &GeneralEvaluationDomain<E::Fr>,
+    ) {
+        let n = domain.size();
+        let lagrange_comms = compute_lagrange_basis_commitments(srs[..n].to_vec());
+        let n_f = domain.size_as_field_element();
+
+        let w2: Vec<E::G2Projective> = cfg_iter!(lagrange_comms)
+            .enumerate()
+            .map(|(index, l_i)| {
+                let scalar = n_f * domain.element(index).inverse().unwrap();
+                l_i.mul(scalar.into_repr())
+            })
+            .collect();
+        let w2 = E::G2Projective::batch_normalization_into_affine(&w2);
+        self.w2_mapping.extend(w2.into_iter().enumerate());
+    }
+
+    /*
+     * Update the W1 values after the value at index j changed by delta, i.e. C'(X) =
+     * C(X) + delta * L_j(X), using the technique of [TADBFK20, section 3.4.2]
+     * (https://eprint.iacr.org/2020/527.pdf). For i != j:
+     *
+     * W1'_i = W1_i + delta * L_j(X) / (X - ω^i)
+     *       = W1_i + delta * ω^j / (n * (ω^i - ω^j)) * (W2_i - W2_j)
+     *
+     * This costs one G2 scalar multiplication per W1 value, and requires the W2 values of every
+     * index with a W1 value, and of j. W1_j itself also changes by
+     * delta * (L_j(X) - 1) / (X - ω^j), so it is not updated here; recompute it with
+     * precompute_w1() instead.
+     */
+    pub fn update_w1_for_change(
+        &mut self,
+        index: usize,
+        delta: E::Fr,
+        domain: &GeneralEvaluationDomain<E::Fr>,
+    ) {
+        let omega_j = domain.element(index);
+        let w2_j = self.get_w2_i(&index).into_projective();
+        let scale = delta * omega_j / domain.size_as_field_element();
+
+        let indices: Vec<usize> = self
+            .w1_mapping
+            .keys()
+            .copied()
+            .filter(|i| *i != index)
+            .collect();
+        let mut denoms: Vec<E::Fr> = indices
+            .iter()
+            .map(|i| domain.element(*i) - omega_j)
+            .collect();
+        ark_ff::batch_inversion(&mut denoms);
+
+        for (i, denom_inv) in indices.iter().zip(denoms.iter()) {
+            let w2_i = self.get_w2_i(i).into_projective();
+            let diff = (w2_i - w2_j)
+                .into_affine()
+                .mul((scale * denom_inv).into_repr());
+            let w1_i = self.w1_mapping.get_mut(i).unwrap();
+            *w1_i = (w1_i.into_projective() + diff).into_affine();
         }
     }
 }
@@ -246,6 +370,49 @@ mod precomputed_test {
         assert_eq!(lhs, rhs);
     }
 
+    #[test]
+    fn test_precompute_all_w2() {
+        let mut rng = test_rng();
+        let max_power = 8;
+        let domain = GeneralEvaluationDomain::<F>::new(max_power).unwrap();
+        let (_, srs_g2) = unsafe_setup::<Bn254, StdRng>(max_power, max_power, &mut rng);
+
+        let indices: Vec<usize> = (0..max_power).collect();
+        let mut expected = CaulkPlusPrecomputed::<Bn254>::empty();
+        expected.precompute_w2(&srs_g2, &indices, &domain);
+
+        let mut precomputed = CaulkPlusPrecomputed::<Bn254>::empty();
+        precomputed.precompute_all_w2(&srs_g2, &domain);
+        assert_eq!(precomputed.w2_mapping, expected.w2_mapping);
+    }
+
+    #[test]
+    fn test_update_w1_for_change() {
+        let mut rng = test_rng();
+        let max_power = 8;
+        let domain = GeneralEvaluationDomain::<F>::new(max_power).unwrap();
+        let (_, srs_g2) = unsafe_setup::<Bn254, StdRng>(max_power, max_power, &mut rng);
+
+        let indices: Vec<usize> = (0..max_power).collect();
+        let mut c_evals: Vec<F> = (0..max_power).map(|_| F::rand(&mut rng)).collect();
+        let c = DensePolynomial::from_coefficients_slice(&domain.ifft(&c_evals));
+
+        let mut precomputed = CaulkPlusPrecomputed::<Bn254>::empty();
+        precomputed.precompute_all_w2(&srs_g2, &domain);
+        precomputed.precompute_w1(&srs_g2, &indices, &c, &domain);
+
+        // Change the value at index 5, and recompute W1_5 directly
+        let delta = F::rand(&mut rng);
+        c_evals[5] += delta;
+        let c = DensePolynomial::from_coefficients_slice(&domain.ifft(&c_evals));
+        precomputed.update_w1_for_change(5, delta, &domain);
+        precomputed.precompute_w1(&srs_g2, &[5], &c, &domain);
+
+        let mut expected = CaulkPlusPrecomputed::<Bn254>::empty();
+        expected.precompute_w1(&srs_g2, &indices, &c, &domain);
+        assert_eq!(precomputed.w1_mapping, expected.w1_mapping);
+    }
+
     #[test]
     fn test_combined() {
         let mut rng = test_rng();
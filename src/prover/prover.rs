@@ -1,30 +1,47 @@
 use std::iter;
 use std::{cmp::max, vec};
 
-use ark_bn254::{Bn254, Fr};
+use ark_bn254::{Bn254, Fr, G1Affine};
 use ark_ec::{AffineCurve, PairingEngine, ProjectiveCurve};
 use ark_ff::{Field, One, PrimeField, Zero};
 use ark_poly::{
     univariate::DensePolynomial, EvaluationDomain, GeneralEvaluationDomain, Polynomial,
     UVPolynomial,
 };
-use ark_std::{cfg_into_iter, UniformRand};
+use ark_std::{cfg_into_iter, cfg_iter, UniformRand};
 use rand::RngCore;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 
 use crate::{
-    constants::{EXTENDED_DOMAIN_FACTOR, NUMBER_OF_MIMC_ROUNDS, SUBGROUP_SIZE},
+    accumulator::{combine_values, compute_prefix_polynomial, compute_zero_leaf},
+    constants::{
+        EXTENDED_DOMAIN_FACTOR, NUMBER_OF_MIMC_ROUNDS, POSEIDON_SINGLE_WIDTH, POSEIDON_WIDTH,
+        SUBGROUP_SIZE,
+    },
+    error::Error,
     gates::{
+        AttributeCopyGate, AttributeHashGate, AttributeKeyCopyGate, ComparisonGate,
         ExternalNullifierGate, KeyCopyGate, KeyEqualityGate, Mimc7RoundGate, NullifierHashGate,
+        PoseidonCapacityGate, PoseidonInputCopyGate, PoseidonNullifierHashGate, PoseidonRoundGate,
+        PoseidonSecretCopyGate, RangeBitGate, RangeStartGate, RlnExternalNullifierGate,
+        RlnMessageLimitGate, RlnNullifierGate, RlnShareGate, RlnSlopeGate,
     },
-    kzg::commit,
-    layouter::Assignment,
+    kzg::{self, commit},
+    layouter::{Assignment, AttributeAssignment, PoseidonAssignment, RlnAssignment},
     multiopen::{prover::Prover as MultiopenProver, MultiopenProof},
     transcript::Transcript,
     utils::construct_lagrange_basis_polys,
     utils::shift_dense_poly,
 };
 
-use super::{Commitments, Openings, Proof, ProverPrecomputedData, ProvingKey, PublicData};
+use super::{
+    AttributeProof, AttributePublicData, Commitments, LinkedLookupProof, LinkedProof,
+    LinkedPublicData, MembershipCommitments, MembershipOpenings, MembershipProof,
+    MembershipPublicData, Openings, PoseidonProof, PrefixPublicData, Proof, ProverPrecomputedData,
+    ProvingKey, PublicData, RlnProof, RlnPublicData, RotationProof, RotationPublicData,
+    TuplePublicData,
+};
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Read, SerializationError, Write};
 
 #[derive(CanonicalSerialize, CanonicalDeserialize, Clone, Debug)]
@@ -35,6 +52,80 @@ pub struct WitnessInput<F: PrimeField> {
     pub index: usize,
 }
 
+/*
+ * The extra columns and public inputs of the circuit variants which extend the regular circuit,
+ * or in the case of Poseidon, replace its gates. The extra columns are committed to after w0, key,
+ * w1 and w2, and are opened at the same points as w2.
+ */
+pub(crate) enum Extension<'a, F: PrimeField> {
+    // The internal nullifier, epoch, message ID and range columns, the y-coordinate of the share
+    // and the message limit
+    Rln {
+        assignment: &'a RlnAssignment<F>,
+        share_y: F,
+        message_limit: F,
+    },
+    Attribute {
+        assignment: &'a AttributeAssignment<F>,
+        threshold: F,
+        reveal_attribute_hash: bool,
+        attribute_hash: F,
+    },
+    // The state columns of the three Poseidon instances, which replace the MiMC7 gates
+    Poseidon {
+        assignment: &'a PoseidonAssignment<F>,
+    },
+}
+
+impl<'a, F: PrimeField> Extension<'a, F> {
+    fn columns(&self) -> Vec<&'a [F]> {
+        match self {
+            Extension::Rln { assignment, .. } => vec![
+                &assignment.internal_nullifier,
+                &assignment.epoch,
+                &assignment.message_key,
+                &assignment.message_id,
+                &assignment.message_id_range,
+                &assignment.message_limit_range,
+            ],
+            Extension::Attribute { assignment, .. } => vec![
+                &assignment.attribute_key,
+                &assignment.attribute,
+                &assignment.range,
+                &assignment.attribute_hash,
+            ],
+            Extension::Poseidon { assignment } => assignment
+                .identity_secret
+                .iter()
+                .chain(assignment.identity_commitment.iter())
+                .chain(assignment.nullifier_hash.iter())
+                .map(|col| col.as_slice())
+                .collect(),
+        }
+    }
+
+    fn public_inputs(&self) -> Vec<F> {
+        match self {
+            Extension::Rln {
+                share_y,
+                message_limit,
+                ..
+            } => vec![*share_y, *message_limit],
+            Extension::Attribute {
+                threshold,
+                reveal_attribute_hash,
+                attribute_hash,
+                ..
+            } => vec![
+                *threshold,
+                F::from(*reveal_attribute_hash as u64),
+                *attribute_hash,
+            ],
+            Extension::Poseidon { .. } => vec![],
+        }
+    }
+}
+
 pub struct State<'a, E: PairingEngine> {
     // init data in the state
     pub(crate) proving_key: &'a ProvingKey<E>,
@@ -44,6 +135,27 @@ pub struct State<'a, E: PairingEngine> {
     pub(crate) public_input: &'a PublicData<E>,
     // pub(crate) shifted_a: DensePolynomial<E::Fr>,
 
+    // The extra columns and public inputs of the RLN, attribute and Poseidon variants
+    pub(crate) extension: Option<Extension<'a, E::Fr>>,
+
+    // The parallel accumulators and public values of a tuple lookup, and the challenge which
+    // combines them
+    pub(crate) tuple: Option<&'a TuplePublicData<E>>,
+    pub(crate) beta: Option<E::Fr>,
+
+    // The prefix accumulator and k of a prefix proof
+    pub(crate) prefix: Option<(E::G1Affine, usize)>,
+
+    // The accumulators of a linked proof, the state of the Caulk+ lookup against the second
+    // accumulator, and the resulting proof
+    pub(crate) linked: Option<&'a LinkedPublicData<E>>,
+    pub(crate) second_lookup: Option<Box<State<'a, E>>>,
+    pub(crate) second_lookup_proof: Option<LinkedLookupProof<E>>,
+
+    // In membership-only mode, w2 is the zero polynomial and only gates 0, 1, 3 and 4 are
+    // enforced
+    pub(crate) membership_only: bool,
+
     // domains
     pub(crate) domain_h: GeneralEvaluationDomain<E::Fr>,
     pub(crate) domain_v: GeneralEvaluationDomain<E::Fr>,
@@ -61,6 +173,7 @@ pub struct State<'a, E: PairingEngine> {
     pub(crate) key: Option<DensePolynomial<E::Fr>>,
     pub(crate) w1: Option<DensePolynomial<E::Fr>>,
     pub(crate) w2: Option<DensePolynomial<E::Fr>>,
+    pub(crate) extra: Vec<DensePolynomial<E::Fr>>,
 
     pub(crate) quotient: Option<DensePolynomial<E::Fr>>,
 
@@ -98,6 +211,14 @@ impl Prover {
             public_input,
             precomputed,
             // shifted_a,
+            extension: None,
+            tuple: None,
+            beta: None,
+            prefix: None,
+            linked: None,
+            second_lookup: None,
+            second_lookup_proof: None,
+            membership_only: false,
             domain_h,
             domain_v,
             domain_t,
@@ -106,6 +227,7 @@ impl Prover {
             key: None,
             w1: None,
             w2: None,
+            extra: vec![],
 
             quotient: None,
 
@@ -144,33 +266,532 @@ impl Prover {
             precomputed,
             table_size,
         );
+
+        Self::prove_state(&mut state, zk_rng).0
+    }
+
+    /*
+     * Generate a proof in RLN mode, which proves that y = a0 + a1 * x for the public share (x, y),
+     * where a0 is the identity nullifier, a1 is MiMC7(a0, external_nullifier) and x is the signal
+     * hash, and that the public RLN nullifier is MiMC7(a1). The external nullifier is
+     * MiMC7(epoch, message_id), where the message ID is private and below the public message
+     * limit.
+     */
+    pub fn prove_rln<R: RngCore>(
+        pk: &ProvingKey<Bn254>,
+        witness: &WitnessInput<Fr>,
+        assignment: &RlnAssignment<Fr>,
+        public_input: &RlnPublicData<Bn254>,
+        precomputed: &ProverPrecomputedData<Bn254>,
+        zk_rng: &mut R,
+        table_size: usize,
+    ) -> RlnProof<Bn254> {
+        let base_public_input = public_input.to_public_data();
+        let mut state = Self::init(
+            pk,
+            witness,
+            &assignment.assignment,
+            &base_public_input,
+            precomputed,
+            table_size,
+        );
+        state.extension = Some(Extension::Rln {
+            assignment,
+            share_y: public_input.share_y,
+            message_limit: Fr::from(public_input.message_limit),
+        });
+
+        let (proof, extra_commitments, extra_openings) = Self::prove_state(&mut state, zk_rng);
+
+        RlnProof {
+            proof,
+            extra_commitments,
+            extra_openings: extra_openings.concat(),
+        }
+    }
+
+    /*
+     * Generate a proof for an attribute-bearing identity commitment MiMC7(id_nul, id_trap,
+     * attribute), which shows that attribute >= threshold, and optionally reveals the MiMC7 hash
+     * of the attribute. The identity commitment in the witness and in the accumulator must be
+     * the attribute-bearing one.
+     */
+    pub fn prove_with_attribute<R: RngCore>(
+        pk: &ProvingKey<Bn254>,
+        witness: &WitnessInput<Fr>,
+        assignment: &AttributeAssignment<Fr>,
+        public_input: &AttributePublicData<Bn254>,
+        precomputed: &ProverPrecomputedData<Bn254>,
+        zk_rng: &mut R,
+        table_size: usize,
+    ) -> AttributeProof<Bn254> {
+        let base_public_input = public_input.to_public_data();
+        let mut state = Self::init(
+            pk,
+            witness,
+            &assignment.assignment,
+            &base_public_input,
+            precomputed,
+            table_size,
+        );
+        state.extension = Some(Extension::Attribute {
+            assignment,
+            threshold: public_input.threshold,
+            reveal_attribute_hash: public_input.reveal_attribute_hash,
+            attribute_hash: public_input.attribute_hash,
+        });
+
+        let (proof, extra_commitments, extra_openings) = Self::prove_state(&mut state, zk_rng);
+
+        AttributeProof {
+            proof,
+            extra_commitments,
+            extra_openings: extra_openings.concat(),
+        }
+    }
+
+    /*
+     * Generate a proof for a Semaphore v2 identity commitment Poseidon(Poseidon(id_nul, id_trap)),
+     * with the nullifier hash Poseidon(ext_nul, id_nul). The identity commitment in the witness
+     * and in the accumulator must be the Poseidon one.
+     */
+    pub fn prove_poseidon<R: RngCore>(
+        pk: &ProvingKey<Bn254>,
+        witness: &WitnessInput<Fr>,
+        assignment: &PoseidonAssignment<Fr>,
+        public_input: &PublicData<Bn254>,
+        precomputed: &ProverPrecomputedData<Bn254>,
+        zk_rng: &mut R,
+        table_size: usize,
+    ) -> PoseidonProof<Bn254> {
+        let mut state = Self::init(
+            pk,
+            witness,
+            &assignment.assignment,
+            public_input,
+            precomputed,
+            table_size,
+        );
+        state.extension = Some(Extension::Poseidon { assignment });
+
+        let (proof, extra_commitments, extra_openings) = Self::prove_state(&mut state, zk_rng);
+
+        PoseidonProof {
+            proof,
+            extra_commitments,
+            extra_openings: extra_openings.concat(),
+        }
+    }
+
+    /*
+     * Generate a proof that the identity commitment and the public values in public_input sit at
+     * the same hidden index of the parallel accumulators in public_input. The Caulk+ lookup is
+     * performed against the random linear combination of the accumulators, so precomputed must
+     * also hold the W1 values of the other columns (see
+     * ProverPrecomputedData::precompute_column_w1()). Returns Error::TupleColumnCountMismatch
+     * if there is not exactly one more accumulator than public values.
+     */
+    pub fn prove_tuple<R: RngCore>(
+        pk: &ProvingKey<Bn254>,
+        witness: &WitnessInput<Fr>,
+        assignment: &Assignment<Fr>,
+        public_input: &TuplePublicData<Bn254>,
+        precomputed: &ProverPrecomputedData<Bn254>,
+        zk_rng: &mut R,
+        table_size: usize,
+    ) -> Result<Proof<Bn254>, Error> {
+        if public_input.accumulators.len() != public_input.values.len() + 1 {
+            return Err(Error::TupleColumnCountMismatch);
+        }
+
+        let base_public_input = public_input.to_public_data();
+        let mut state = Self::init(
+            pk,
+            witness,
+            assignment,
+            &base_public_input,
+            precomputed,
+            table_size,
+        );
+        state.tuple = Some(public_input);
+
+        Ok(Self::prove_state(&mut state, zk_rng).0)
+    }
+
+    /*
+     * Generate a membership-only proof, which keeps only the identity commitment MiMC7 chain and
+     * the Caulk+ lookup, and binds the proof to the signal hash. Use
+     * Layouter::assign_membership() to construct the assignment.
+     */
+    pub fn prove_membership<R: RngCore>(
+        pk: &ProvingKey<Bn254>,
+        witness: &WitnessInput<Fr>,
+        assignment: &Assignment<Fr>,
+        public_input: &MembershipPublicData<Bn254>,
+        precomputed: &ProverPrecomputedData<Bn254>,
+        zk_rng: &mut R,
+        table_size: usize,
+    ) -> MembershipProof<Bn254> {
+        let base_public_input = public_input.to_public_data();
+        let mut state = Self::init(
+            pk,
+            witness,
+            assignment,
+            &base_public_input,
+            precomputed,
+            table_size,
+        );
+        state.membership_only = true;
+
+        let mut transcript = Transcript::new_transcript();
+
+        let (w0, key, w1, _, _) = Self::assignment_round(&mut state);
+
+        transcript.update_with_f(public_input.signal_hash);
+        transcript.round_1_membership([&w0, &key, &w1]);
+
+        let v = transcript.get_challenge();
+
+        let quotient = Self::quotient_round(&mut state, v);
+
+        let (zi, ci, u_prime) = Self::caulk_plus_first_round(&mut state, zk_rng);
+
+        transcript.round_2([&quotient, &zi, &ci, &u_prime]);
+
+        let hi_1 = transcript.get_challenge();
+        let hi_2 = transcript.get_challenge();
+
+        let (w, h) = Self::caulk_plus_second_round(&mut state, hi_1, hi_2);
+
+        transcript.round_3(&w, &h);
+
+        let alpha = transcript.get_challenge();
+
+        let (
+            multiopen_proof,
+            w0_openings_0,
+            w0_openings_1,
+            w0_openings_2,
+            w1_openings_0,
+            w1_openings_1,
+            w1_openings_2,
+            _,
+            _,
+            _,
+            key_openings_0,
+            key_openings_1,
+            q_mimc_opening,
+            mimc_cts_opening,
+            quotient_opening,
+            u_prime_opening,
+            p1_opening,
+            p2_opening,
+            p1,
+            p2,
+            _,
+        ) = Self::opening_round(&state, hi_1, alpha, &mut transcript);
+
+        let q_mimc = state
+            .proving_key
+            .commit_g1(&state.precomputed.q_mimc)
+            .into_affine();
+        let mimc_cts = state
+            .proving_key
+            .commit_g1(&state.precomputed.mimc_cts)
+            .into_affine();
+        let p1 = state.proving_key.commit_g1(&p1).into_affine();
+        let p2 = state.proving_key.commit_g1(&p2).into_affine();
+
+        let commitments = MembershipCommitments {
+            w0,
+            w1,
+            key,
+            mimc_cts,
+            quotient,
+            u_prime,
+            zi,
+            ci,
+            p1,
+            p2,
+            q_mimc,
+            h,
+            w,
+        };
+
+        let openings = MembershipOpenings {
+            q_mimc: q_mimc_opening,
+            mimc_cts: mimc_cts_opening,
+            quotient: quotient_opening,
+            u_prime: u_prime_opening,
+            p1: p1_opening,
+            p2: p2_opening,
+            w0_0: w0_openings_0,
+            w0_1: w0_openings_1,
+            w0_2: w0_openings_2,
+            w1_0: w1_openings_0,
+            w1_1: w1_openings_1,
+            w1_2: w1_openings_2,
+            key_0: key_openings_0,
+            key_1: key_openings_1,
+        };
+
+        MembershipProof {
+            multiopen_proof,
+            openings,
+            commitments,
+        }
+    }
+
+    /*
+     * Generate a proof that the member's index is less than public_input.k, without revealing
+     * the index. The lookup is performed against the prefix accumulator, so precomputed must hold
+     * the W1 values of the prefix polynomial (see accumulator::compute_prefix_polynomial()) rather
+     * than those of the full accumulator. Returns Error::IndexNotInPrefix if the member's index is
+     * not less than k.
+     * @param lagrange_comms: The commitments to the Lagrange basis polynomials.
+     * @param members: The identity commitments of the group, in index order.
+     */
+    #[allow(clippy::too_many_arguments)]
+    pub fn prove_prefix<R: RngCore>(
+        pk: &ProvingKey<Bn254>,
+        lagrange_comms: &[G1Affine],
+        members: &[Fr],
+        witness: &WitnessInput<Fr>,
+        assignment: &Assignment<Fr>,
+        public_input: &PrefixPublicData<Bn254>,
+        precomputed: &ProverPrecomputedData<Bn254>,
+        zk_rng: &mut R,
+        table_size: usize,
+    ) -> Result<Proof<Bn254>, Error> {
+        if witness.index >= public_input.k {
+            return Err(Error::IndexNotInPrefix);
+        }
+
+        let prefix_accumulator = public_input.prefix_accumulator(lagrange_comms, members)?;
+        let base_public_input = public_input.to_public_data(prefix_accumulator);
+        let mut state = Self::init(
+            pk,
+            witness,
+            assignment,
+            &base_public_input,
+            precomputed,
+            table_size,
+        );
+        state.prefix = Some((prefix_accumulator, public_input.k));
+
+        Ok(Self::prove_state(&mut state, zk_rng).0)
+    }
+
+    /*
+     * Generate a proof that the same identity is in two groups, without revealing either index,
+     * with a single nullifier hash. The identity commitment is looked up in both accumulators.
+     * @param witness: The witness, whose index is the index in the first group.
+     * @param second_index: The index in the second group.
+     * @param precomputed: The precomputed data of the first group.
+     * @param second_precomputed: The precomputed data of the second group.
+     */
+    #[allow(clippy::too_many_arguments)]
+    pub fn prove_linked<R: RngCore>(
+        pk: &ProvingKey<Bn254>,
+        witness: &WitnessInput<Fr>,
+        second_index: usize,
+        assignment: &Assignment<Fr>,
+        public_input: &LinkedPublicData<Bn254>,
+        precomputed: &ProverPrecomputedData<Bn254>,
+        second_precomputed: &ProverPrecomputedData<Bn254>,
+        zk_rng: &mut R,
+        table_size: usize,
+    ) -> LinkedProof<Bn254> {
+        let base_public_input = public_input.to_public_data();
+        let second_witness = WitnessInput {
+            index: second_index,
+            ..witness.clone()
+        };
+
+        let mut state = Self::init(
+            pk,
+            witness,
+            assignment,
+            &base_public_input,
+            precomputed,
+            table_size,
+        );
+        state.linked = Some(public_input);
+        state.second_lookup = Some(Box::new(Self::init(
+            pk,
+            &second_witness,
+            assignment,
+            &base_public_input,
+            second_precomputed,
+            table_size,
+        )));
+
+        let (proof, _, _) = Self::prove_state(&mut state, zk_rng);
+
+        LinkedProof {
+            proof,
+            second_lookup: state.second_lookup_proof.take().unwrap(),
+        }
+    }
+
+    /*
+     * Generate a proof which authorises the replacement of the prover's identity commitment with
+     * public_input.new_identity_commitment. It proves knowledge of the identity behind
+     * public_input.old_identity_commitment with a lookup in the accumulator which only holds it,
+     * at index 0, and opens the group polynomial at omega ^ public_input.index to show that the
+     * old identity commitment is in that slot. The index is public, so the rotation is linkable.
+     * Returns Error::RotationWitnessMismatch if the witness does not open the old identity
+     * commitment, and Error::IdentityNotInGroup if it is not at the index.
+     * @param lagrange_comms: The commitments to the Lagrange basis polynomials.
+     * @param polynomial: The polynomial of the group, whose commitment is the accumulator.
+     * @param assignment: The assignment for identity::rotation_external_nullifier().
+     * @param precomputed: The fixed precomputed data (see ProverPrecomputedData::precompute_fixed()).
+     */
+    #[allow(clippy::too_many_arguments)]
+    pub fn prove_rotation<R: RngCore>(
+        pk: &ProvingKey<Bn254>,
+        lagrange_comms: &[G1Affine],
+        polynomial: &DensePolynomial<Fr>,
+        witness: &WitnessInput<Fr>,
+        assignment: &Assignment<Fr>,
+        public_input: &RotationPublicData<Bn254>,
+        precomputed: &ProverPrecomputedData<Bn254>,
+        zk_rng: &mut R,
+        table_size: usize,
+    ) -> Result<RotationProof<Bn254>, Error> {
+        if witness.identity_commitment != public_input.old_identity_commitment {
+            return Err(Error::RotationWitnessMismatch);
+        }
+        if public_input.index >= table_size {
+            return Err(Error::IdentityNotInGroup);
+        }
+
+        let domain_t = GeneralEvaluationDomain::<Fr>::new(table_size).unwrap();
+        let (value, slot_opening) =
+            kzg::open(&pk.srs_g1, polynomial, domain_t.element(public_input.index));
+        if value != public_input.old_identity_commitment {
+            return Err(Error::IdentityNotInGroup);
+        }
+
+        let old_polynomial = compute_prefix_polynomial(
+            compute_zero_leaf::<Fr>(),
+            &[public_input.old_identity_commitment],
+            1,
+            table_size,
+        );
+        let mut old_precomputed = precomputed.clone();
+        old_precomputed.precompute_w1(pk, &[0], &old_polynomial, table_size);
+        old_precomputed.precompute_w2(pk, &[0], table_size);
+
+        let old_witness = WitnessInput {
+            index: 0,
+            ..witness.clone()
+        };
+        let proof = Self::prove(
+            pk,
+            &old_witness,
+            assignment,
+            &public_input.to_public_data(lagrange_comms),
+            &old_precomputed,
+            zk_rng,
+            table_size,
+        );
+
+        Ok(RotationProof {
+            proof,
+            slot_opening,
+        })
+    }
+
+    // Run all the prover rounds, and also return the commitments to and openings of the extra
+    // columns
+    #[allow(clippy::type_complexity)]
+    fn prove_state<R: RngCore>(
+        state: &mut State<Bn254>,
+        zk_rng: &mut R,
+    ) -> (Proof<Bn254>, Vec<G1Affine>, Vec<[Fr; 3]>) {
+        let public_input = state.public_input;
         let mut transcript = Transcript::new_transcript();
 
-        let (w0, key, w1, w2) = Self::assignment_round(&mut state);
+        let (w0, key, w1, w2, extra_commitments) = Self::assignment_round(state);
 
         transcript.round_0_public_inputs([
             public_input.external_nullifier,
             public_input.nullifier_hash,
             public_input.signal_hash,
         ]);
+        if let Some(extension) = &state.extension {
+            for x in extension.public_inputs() {
+                transcript.update_with_f(x);
+            }
+        }
+
+        if let Some((prefix_accumulator, k)) = state.prefix {
+            transcript.update_with_g1(&prefix_accumulator);
+            transcript.update_with_f(Fr::from(k as u64));
+        }
+        if let Some(tuple) = state.tuple {
+            for accumulator in tuple.accumulators.iter() {
+                transcript.update_with_g1(accumulator);
+            }
+            for value in tuple.values.iter() {
+                transcript.update_with_f(*value);
+            }
+        }
+        if let Some(linked) = state.linked {
+            transcript.update_with_g1(&linked.accumulator);
+            transcript.update_with_g1(&linked.second_accumulator);
+        }
 
         transcript.round_1([&w0, &key, &w1, &w2]);
+        for commitment in extra_commitments.iter() {
+            transcript.update_with_g1(commitment);
+        }
 
         let v = transcript.get_challenge();
 
-        let quotient = Self::quotient_round(&mut state, v);
+        // The looked-up value of a tuple lookup is identity_commitment + beta * values[0] +
+        // beta ^ 2 * values[1] + ...
+        if let Some(tuple) = state.tuple {
+            let beta = transcript.get_challenge();
+            let offset = combine_values(&tuple.values, beta) * beta;
+            let a = state.a.as_mut().unwrap();
+            a.coeffs[0] += offset;
+            state.beta = Some(beta);
+        }
 
-        let (zi, ci, u_prime) = Self::caulk_plus_first_round(&mut state, zk_rng);
+        let quotient = Self::quotient_round(state, v);
+
+        let (zi, ci, u_prime) = Self::caulk_plus_first_round(state, zk_rng);
 
         transcript.round_2([&quotient, &zi, &ci, &u_prime]);
 
+        // The second lookup of a linked proof looks up the same polynomial a
+        let mut second_lookup_commitments = None;
+        if let Some(second) = state.second_lookup.as_deref_mut() {
+            second.a = state.a.clone();
+            let (second_zi, second_ci, second_u_prime) =
+                Self::caulk_plus_first_round(second, zk_rng);
+            transcript.update_with_g1(&second_zi);
+            transcript.update_with_g1(&second_ci);
+            transcript.update_with_g1(&second_u_prime);
+            second_lookup_commitments = Some((second_zi, second_ci, second_u_prime));
+        }
+
         let hi_1 = transcript.get_challenge();
         let hi_2 = transcript.get_challenge();
 
-        let (w, h) = Self::caulk_plus_second_round(&mut state, hi_1, hi_2);
+        let (w, h) = Self::caulk_plus_second_round(state, hi_1, hi_2);
 
         transcript.round_3(&w, &h);
 
+        let mut second_lookup_quotients = None;
+        if let Some(second) = state.second_lookup.as_deref_mut() {
+            let (second_w, second_h) = Self::caulk_plus_second_round(second, hi_1, hi_2);
+            transcript.round_3(&second_w, &second_h);
+            second_lookup_quotients = Some((second_w, second_h));
+        }
+
         let alpha = transcript.get_challenge();
 
         let (
@@ -194,7 +815,8 @@ impl Prover {
             p2_opening,
             p1,
             p2,
-        ) = Self::opening_round(&state, hi_1, alpha, &mut transcript);
+            extra_openings,
+        ) = Self::opening_round(state, hi_1, alpha, &mut transcript);
 
         //// Sanity check multiopen_proof
         //if cfg!(debug_assertions) {
@@ -279,10 +901,40 @@ impl Prover {
         //assert!(is_multiopen_proof_valid);
         //}
 
-        let q_mimc = commit(&state.proving_key.srs_g1, &state.precomputed.q_mimc).into_affine();
-        let mimc_cts = commit(&state.proving_key.srs_g1, &state.precomputed.mimc_cts).into_affine();
-        let p1 = commit(&state.proving_key.srs_g1, &p1).into_affine();
-        let p2 = commit(&state.proving_key.srs_g1, &p2).into_affine();
+        if let (Some(second), Some((zi, ci, u_prime)), Some((w, h))) = (
+            state.second_lookup.as_deref(),
+            second_lookup_commitments,
+            second_lookup_quotients,
+        ) {
+            let (multiopen_proof, u_prime_opening, p1_opening, p2_opening, p1, p2) =
+                Self::second_lookup_opening_round(state, second, hi_1, alpha, &mut transcript);
+
+            let second_lookup_proof = LinkedLookupProof {
+                multiopen_proof,
+                zi,
+                ci,
+                u_prime,
+                h,
+                w,
+                p1: state.proving_key.commit_g1(&p1).into_affine(),
+                p2: state.proving_key.commit_g1(&p2).into_affine(),
+                u_prime_opening,
+                p1_opening,
+                p2_opening,
+            };
+            state.second_lookup_proof = Some(second_lookup_proof);
+        }
+
+        let q_mimc = state
+            .proving_key
+            .commit_g1(&state.precomputed.q_mimc)
+            .into_affine();
+        let mimc_cts = state
+            .proving_key
+            .commit_g1(&state.precomputed.mimc_cts)
+            .into_affine();
+        let p1 = state.proving_key.commit_g1(&p1).into_affine();
+        let p2 = state.proving_key.commit_g1(&p2).into_affine();
 
         let commitments = Commitments {
             w0,
@@ -321,41 +973,90 @@ impl Prover {
             key_1: key_openings_1,
         };
 
-        Proof {
+        let proof = Proof {
             multiopen_proof,
             openings,
             commitments,
-        }
+        };
+
+        (proof, extra_commitments, extra_openings)
     }
 
+    #[allow(clippy::type_complexity)]
     fn assignment_round<E: PairingEngine>(
         state: &mut State<E>,
-    ) -> (E::G1Affine, E::G1Affine, E::G1Affine, E::G1Affine) {
+    ) -> (
+        E::G1Affine,
+        E::G1Affine,
+        E::G1Affine,
+        E::G1Affine,
+        Vec<E::G1Affine>,
+    ) {
         let domain = GeneralEvaluationDomain::<E::Fr>::new(SUBGROUP_SIZE).unwrap();
 
-        let w0 =
-            DensePolynomial::from_coefficients_slice(&domain.ifft(&state.assignment.nullifier));
-        let key = DensePolynomial::from_coefficients_slice(&domain.ifft(&state.assignment.key));
-        let w1 = DensePolynomial::from_coefficients_slice(
-            &domain.ifft(&state.assignment.identity_commitment),
-        );
-        let w2 = DensePolynomial::from_coefficients_slice(
-            &domain.ifft(&state.assignment.external_nullifier),
+        // The IFFTs of the columns are independent, so they run in parallel with the parallel
+        // feature
+        let mut columns: Vec<&[E::Fr]> = vec![
+            &state.assignment.nullifier,
+            &state.assignment.key,
+            &state.assignment.identity_commitment,
+            &state.assignment.external_nullifier,
+        ];
+        columns.extend(
+            state
+                .extension
+                .as_ref()
+                .map_or(vec![], |extension| extension.columns()),
         );
+        let mut polys = cfg_iter!(columns)
+            .map(|evals| DensePolynomial::from_coefficients_slice(&domain.ifft(evals)))
+            .collect::<Vec<_>>()
+            .into_iter();
+
+        let w0 = polys.next().unwrap();
+        let key = polys.next().unwrap();
+        let w1 = polys.next().unwrap();
+        let w2 = polys.next().unwrap();
+        // The multiopen argument opens the zero polynomial in place of w2 in membership-only mode
+        let w2 = if state.membership_only {
+            DensePolynomial::zero()
+        } else {
+            w2
+        };
+        let extra: Vec<DensePolynomial<_>> = polys.collect();
 
         let omega_pow_rotation = state.domain_h.element(NUMBER_OF_MIMC_ROUNDS);
         let w1_shifted_n = shift_dense_poly(&w1, &omega_pow_rotation);
-        let a: DensePolynomial<_> = &w1_shifted_n + &w1 + &key * E::Fr::from(2u64);
+        let mut a: DensePolynomial<_> = &w1_shifted_n + &w1 + &key * E::Fr::from(2u64);
 
-        let w0_commit = commit(&state.proving_key.srs_g1, &w0);
-        let key_commit = commit(&state.proving_key.srs_g1, &key);
-        let w1_commit = commit(&state.proving_key.srs_g1, &w1);
-        let w2_commit = commit(&state.proving_key.srs_g1, &w2);
+        let w0_commit = state.proving_key.commit_g1(&w0);
+        let key_commit = state.proving_key.commit_g1(&key);
+        let w1_commit = state.proving_key.commit_g1(&w1);
+        let w2_commit = state.proving_key.commit_g1(&w2);
+
+        let extra_commits = extra
+            .iter()
+            .map(|poly| state.proving_key.commit_g1(poly).into())
+            .collect();
+
+        // The identity commitment looked up in the accumulator is MiMC7(id_nul, id_trap,
+        // attribute), i.e. attribute[n] + attribute[0] + 2 * attribute_key[0]
+        if let Some(Extension::Attribute { .. }) = state.extension {
+            let attribute_shifted_n = shift_dense_poly(&extra[1], &omega_pow_rotation);
+            a = &attribute_shifted_n + &extra[1] + &extra[0] * E::Fr::from(2u64);
+        }
+
+        // The Poseidon identity commitment is the output of the identity commitment instance,
+        // which is carried to row n, i.e. t_0[n]
+        if let Some(Extension::Poseidon { .. }) = state.extension {
+            a = shift_dense_poly(&extra[POSEIDON_WIDTH], &omega_pow_rotation);
+        }
 
         state.w0 = Some(w0);
         state.key = Some(key);
         state.w1 = Some(w1);
         state.w2 = Some(w2);
+        state.extra = extra;
         state.a = Some(a);
 
         (
@@ -363,6 +1064,7 @@ impl Prover {
             key_commit.into(),
             w1_commit.into(),
             w2_commit.into(),
+            extra_commits,
         )
     }
 
@@ -375,23 +1077,145 @@ impl Prover {
         let extended_coset_domain =
             GeneralEvaluationDomain::<E::Fr>::new(EXTENDED_DOMAIN_FACTOR * SUBGROUP_SIZE).unwrap();
 
-        let w0_coset_evals = extended_coset_domain.coset_fft(w0);
-        let key_coset_evals = extended_coset_domain.coset_fft(key);
-        let w1_coset_evals = extended_coset_domain.coset_fft(w1);
-        let w2_coset_evals = extended_coset_domain.coset_fft(w2);
+        // As in assignment_round(), the FFTs of the columns run in parallel
+        let mut polys: Vec<&DensePolynomial<E::Fr>> = vec![w0, key, w1, w2];
+        polys.extend(state.extra.iter());
+        let mut coset_evals = cfg_iter!(polys)
+            .map(|poly| extended_coset_domain.coset_fft(poly))
+            .collect::<Vec<_>>()
+            .into_iter();
+
+        let w0_coset_evals = coset_evals.next().unwrap();
+        let key_coset_evals = coset_evals.next().unwrap();
+        let w1_coset_evals = coset_evals.next().unwrap();
+        let w2_coset_evals = coset_evals.next().unwrap();
         let zeroes: Vec<_> = iter::repeat(E::Fr::zero())
             .take(extended_coset_domain.size())
             .collect();
-
-        let num_of_gates = 7;
+        let extra_coset_evals: Vec<Vec<_>> = coset_evals.collect();
+
+        // RLN mode replaces gates 5 and 6 and adds gates 7 to 20. Attribute mode adds gates 7 to
+        // 15.
+        // Poseidon mode replaces all the gates with gates 0 to 14 of the Poseidon circuit.
+        let num_of_gates = match state.extension {
+            None => 7,
+            Some(Extension::Rln { .. }) => 21,
+            Some(Extension::Attribute { .. }) => 16,
+            Some(Extension::Poseidon { .. }) => 15,
+        };
         let v_powers: Vec<E::Fr> =
             iter::successors(Some(E::Fr::one()), |v_i: &E::Fr| Some(*v_i * v))
                 .take(num_of_gates)
                 .collect();
 
+        // The state columns of the identity secret, identity commitment and nullifier hash
+        // instances of the Poseidon circuit
+        let poseidon_state_coset_evals: Vec<&[E::Fr]> = match state.extension {
+            Some(Extension::Poseidon { .. }) => extra_coset_evals
+                .iter()
+                .map(|evals| evals.as_slice())
+                .collect(),
+            _ => vec![],
+        };
+
         let mut numerator_coset_evals = vec![E::Fr::zero(); extended_coset_domain.size()];
         #[allow(clippy::needless_range_loop)]
         for i in 0..extended_coset_domain.size() {
+            // Membership-only mode enforces gates 0, 1, 3 and 4, separated by v ^ 0 to v ^ 3
+            if state.membership_only {
+                numerator_coset_evals[i] = Mimc7RoundGate::compute_in_coset(
+                    i,
+                    &w0_coset_evals,
+                    &zeroes,
+                    &state.precomputed.mimc_cts_coset_evals,
+                    &state.precomputed.q_mimc_coset_evals,
+                ) + v_powers[1]
+                    * Mimc7RoundGate::compute_in_coset(
+                        i,
+                        &w1_coset_evals,
+                        &key_coset_evals,
+                        &state.precomputed.mimc_cts_coset_evals,
+                        &state.precomputed.q_mimc_coset_evals,
+                    )
+                    + v_powers[2]
+                        * KeyEqualityGate::compute_in_coset(
+                            i,
+                            &key_coset_evals,
+                            &state.precomputed.q_mimc_coset_evals,
+                        )
+                    + v_powers[3]
+                        * KeyCopyGate::compute_in_coset(
+                            i,
+                            &w0_coset_evals,
+                            &key_coset_evals,
+                            &state.precomputed.l0_coset_evals,
+                        );
+                continue;
+            }
+
+            // Poseidon mode enforces gates 0 to 14 of the Poseidon circuit, separated by v ^ 0 to
+            // v ^ 14
+            if let Some(Extension::Poseidon { .. }) = state.extension {
+                let (s, rest) = poseidon_state_coset_evals.split_at(POSEIDON_WIDTH);
+                let (t, u) = rest.split_at(POSEIDON_SINGLE_WIDTH);
+                let poseidon = &state.precomputed.poseidon_coset_evals;
+                let poseidon_single = &state.precomputed.poseidon_single_coset_evals;
+                let l0 = &state.precomputed.l0_coset_evals;
+
+                // Gates 0 to 7: the rounds of the three instances
+                let mut gate_evals = vec![];
+                for (instance, fixed) in [(s, poseidon), (t, poseidon_single), (u, poseidon)] {
+                    let cts: Vec<&[E::Fr]> = fixed.cts.iter().map(|c| c.as_slice()).collect();
+                    for k in 0..instance.len() {
+                        gate_evals.push(PoseidonRoundGate::compute_in_coset(
+                            i,
+                            k,
+                            instance,
+                            &cts,
+                            &fixed.mds,
+                            &fixed.q_full,
+                            &fixed.q_partial,
+                            &fixed.q_pass,
+                        ));
+                    }
+                }
+
+                // Gates 8 to 10:
+                for instance in [s, t, u] {
+                    gate_evals.push(PoseidonCapacityGate::compute_in_coset(i, instance[0], l0));
+                }
+
+                // Gate 11:
+                gate_evals.push(PoseidonInputCopyGate::compute_in_coset(i, s[1], u[2], l0));
+
+                // Gate 12:
+                gate_evals.push(PoseidonSecretCopyGate::compute_in_coset(i, s[0], t[1], l0));
+
+                // Gate 13:
+                gate_evals.push(PoseidonNullifierHashGate::compute_in_coset(
+                    i,
+                    u[0],
+                    l0,
+                    state.public_input.nullifier_hash,
+                ));
+
+                // Gate 14:
+                gate_evals.push(ExternalNullifierGate::compute_in_coset(
+                    i,
+                    u[1],
+                    l0,
+                    state.public_input.external_nullifier,
+                ));
+
+                numerator_coset_evals[i] = gate_evals
+                    .iter()
+                    .zip(v_powers.iter())
+                    .fold(E::Fr::zero(), |acc, (gate_eval, v_i)| {
+                        acc + *gate_eval * v_i
+                    });
+                continue;
+            }
+
             // Gate 0:
             numerator_coset_evals[i] += v_powers[0]
                 * Mimc7RoundGate::compute_in_coset(
@@ -441,22 +1265,278 @@ impl Prover {
 
             // Gate 5:
             numerator_coset_evals[i] += v_powers[5]
-                * NullifierHashGate::compute_in_coset(
-                    i,
-                    &w2_coset_evals,
-                    &key_coset_evals,
-                    &state.precomputed.l0_coset_evals,
-                    state.public_input.nullifier_hash,
-                );
+                * match state.extension {
+                    Some(Extension::Rln { .. }) => RlnSlopeGate::compute_in_coset(
+                        i,
+                        &w2_coset_evals,
+                        &key_coset_evals,
+                        &extra_coset_evals[0],
+                        &state.precomputed.l0_coset_evals,
+                    ),
+                    _ => NullifierHashGate::compute_in_coset(
+                        i,
+                        &w2_coset_evals,
+                        &key_coset_evals,
+                        &state.precomputed.l0_coset_evals,
+                        state.public_input.nullifier_hash,
+                    ),
+                };
 
             // Gate 6:
             numerator_coset_evals[i] += v_powers[6]
-                * ExternalNullifierGate::compute_in_coset(
-                    i,
-                    &w2_coset_evals,
-                    &state.precomputed.l0_coset_evals,
-                    state.public_input.external_nullifier,
-                );
+                * match state.extension {
+                    Some(Extension::Rln { .. }) => RlnExternalNullifierGate::compute_in_coset(
+                        i,
+                        &w2_coset_evals,
+                        &extra_coset_evals[2],
+                        &extra_coset_evals[3],
+                        &state.precomputed.l0_coset_evals,
+                    ),
+                    _ => ExternalNullifierGate::compute_in_coset(
+                        i,
+                        &w2_coset_evals,
+                        &state.precomputed.l0_coset_evals,
+                        state.public_input.external_nullifier,
+                    ),
+                };
+
+            match state.extension {
+                None | Some(Extension::Poseidon { .. }) => {}
+                Some(Extension::Rln {
+                    share_y,
+                    message_limit,
+                    ..
+                }) => {
+                    let w3_coset_evals = &extra_coset_evals[0];
+                    let epoch_coset_evals = &extra_coset_evals[1];
+                    let message_key_coset_evals = &extra_coset_evals[2];
+                    let message_id_coset_evals = &extra_coset_evals[3];
+                    let message_id_range_coset_evals = &extra_coset_evals[4];
+                    let message_limit_range_coset_evals = &extra_coset_evals[5];
+
+                    // Gate 7:
+                    numerator_coset_evals[i] += v_powers[7]
+                        * Mimc7RoundGate::compute_in_coset(
+                            i,
+                            w3_coset_evals,
+                            &zeroes,
+                            &state.precomputed.mimc_cts_coset_evals,
+                            &state.precomputed.q_mimc_coset_evals,
+                        );
+
+                    // Gate 8:
+                    numerator_coset_evals[i] += v_powers[8]
+                        * RlnNullifierGate::compute_in_coset(
+                            i,
+                            w3_coset_evals,
+                            &state.precomputed.l0_coset_evals,
+                            state.public_input.nullifier_hash,
+                        );
+
+                    // Gate 9:
+                    numerator_coset_evals[i] += v_powers[9]
+                        * RlnShareGate::compute_in_coset(
+                            i,
+                            &w0_coset_evals,
+                            w3_coset_evals,
+                            &state.precomputed.l0_coset_evals,
+                            state.public_input.signal_hash,
+                            share_y,
+                        );
+
+                    // Gate 10:
+                    numerator_coset_evals[i] += v_powers[10]
+                        * Mimc7RoundGate::compute_in_coset(
+                            i,
+                            epoch_coset_evals,
+                            &zeroes,
+                            &state.precomputed.mimc_cts_coset_evals,
+                            &state.precomputed.q_mimc_coset_evals,
+                        );
+
+                    // Gate 11:
+                    numerator_coset_evals[i] += v_powers[11]
+                        * ExternalNullifierGate::compute_in_coset(
+                            i,
+                            epoch_coset_evals,
+                            &state.precomputed.l0_coset_evals,
+                            state.public_input.external_nullifier,
+                        );
+
+                    // Gate 12:
+                    numerator_coset_evals[i] += v_powers[12]
+                        * KeyEqualityGate::compute_in_coset(
+                            i,
+                            message_key_coset_evals,
+                            &state.precomputed.q_mimc_coset_evals,
+                        );
+
+                    // Gate 13:
+                    numerator_coset_evals[i] += v_powers[13]
+                        * KeyCopyGate::compute_in_coset(
+                            i,
+                            epoch_coset_evals,
+                            message_key_coset_evals,
+                            &state.precomputed.l0_coset_evals,
+                        );
+
+                    // Gate 14:
+                    numerator_coset_evals[i] += v_powers[14]
+                        * Mimc7RoundGate::compute_in_coset(
+                            i,
+                            message_id_coset_evals,
+                            message_key_coset_evals,
+                            &state.precomputed.mimc_cts_coset_evals,
+                            &state.precomputed.q_mimc_coset_evals,
+                        );
+
+                    // Gate 15:
+                    numerator_coset_evals[i] += v_powers[15]
+                        * RangeBitGate::compute_in_coset(
+                            i,
+                            message_id_range_coset_evals,
+                            &state.precomputed.q_mimc_coset_evals,
+                        );
+
+                    // Gate 16:
+                    numerator_coset_evals[i] += v_powers[16]
+                        * RangeStartGate::compute_in_coset(
+                            i,
+                            message_id_range_coset_evals,
+                            &state.precomputed.l0_coset_evals,
+                        );
+
+                    // Gate 17:
+                    numerator_coset_evals[i] += v_powers[17]
+                        * ComparisonGate::compute_in_coset(
+                            i,
+                            message_id_range_coset_evals,
+                            message_id_coset_evals,
+                            &state.precomputed.l0_coset_evals,
+                            E::Fr::zero(),
+                        );
+
+                    // Gate 18:
+                    numerator_coset_evals[i] += v_powers[18]
+                        * RangeBitGate::compute_in_coset(
+                            i,
+                            message_limit_range_coset_evals,
+                            &state.precomputed.q_mimc_coset_evals,
+                        );
+
+                    // Gate 19:
+                    numerator_coset_evals[i] += v_powers[19]
+                        * RangeStartGate::compute_in_coset(
+                            i,
+                            message_limit_range_coset_evals,
+                            &state.precomputed.l0_coset_evals,
+                        );
+
+                    // Gate 20:
+                    numerator_coset_evals[i] += v_powers[20]
+                        * RlnMessageLimitGate::compute_in_coset(
+                            i,
+                            message_limit_range_coset_evals,
+                            message_id_coset_evals,
+                            &state.precomputed.l0_coset_evals,
+                            message_limit,
+                        );
+                }
+                Some(Extension::Attribute {
+                    threshold,
+                    reveal_attribute_hash,
+                    attribute_hash,
+                    ..
+                }) => {
+                    let attribute_key_coset_evals = &extra_coset_evals[0];
+                    let attribute_coset_evals = &extra_coset_evals[1];
+                    let range_coset_evals = &extra_coset_evals[2];
+                    let attribute_hash_coset_evals = &extra_coset_evals[3];
+
+                    // Gate 7:
+                    numerator_coset_evals[i] += v_powers[7]
+                        * Mimc7RoundGate::compute_in_coset(
+                            i,
+                            attribute_coset_evals,
+                            attribute_key_coset_evals,
+                            &state.precomputed.mimc_cts_coset_evals,
+                            &state.precomputed.q_mimc_coset_evals,
+                        );
+
+                    // Gate 8:
+                    numerator_coset_evals[i] += v_powers[8]
+                        * KeyEqualityGate::compute_in_coset(
+                            i,
+                            attribute_key_coset_evals,
+                            &state.precomputed.q_mimc_coset_evals,
+                        );
+
+                    // Gate 9:
+                    numerator_coset_evals[i] += v_powers[9]
+                        * AttributeKeyCopyGate::compute_in_coset(
+                            i,
+                            &w1_coset_evals,
+                            &key_coset_evals,
+                            attribute_key_coset_evals,
+                            &state.precomputed.l0_coset_evals,
+                        );
+
+                    // Gate 10:
+                    numerator_coset_evals[i] += v_powers[10]
+                        * RangeBitGate::compute_in_coset(
+                            i,
+                            range_coset_evals,
+                            &state.precomputed.q_mimc_coset_evals,
+                        );
+
+                    // Gate 11:
+                    numerator_coset_evals[i] += v_powers[11]
+                        * RangeStartGate::compute_in_coset(
+                            i,
+                            range_coset_evals,
+                            &state.precomputed.l0_coset_evals,
+                        );
+
+                    // Gate 12:
+                    numerator_coset_evals[i] += v_powers[12]
+                        * ComparisonGate::compute_in_coset(
+                            i,
+                            range_coset_evals,
+                            attribute_coset_evals,
+                            &state.precomputed.l0_coset_evals,
+                            threshold,
+                        );
+
+                    // Gate 13:
+                    numerator_coset_evals[i] += v_powers[13]
+                        * Mimc7RoundGate::compute_in_coset(
+                            i,
+                            attribute_hash_coset_evals,
+                            &zeroes,
+                            &state.precomputed.mimc_cts_coset_evals,
+                            &state.precomputed.q_mimc_coset_evals,
+                        );
+
+                    // Gate 14:
+                    numerator_coset_evals[i] += v_powers[14]
+                        * AttributeCopyGate::compute_in_coset(
+                            i,
+                            attribute_coset_evals,
+                            attribute_hash_coset_evals,
+                            &state.precomputed.l0_coset_evals,
+                        );
+
+                    // Gate 15:
+                    numerator_coset_evals[i] += v_powers[15]
+                        * AttributeHashGate::compute_in_coset(
+                            i,
+                            attribute_hash_coset_evals,
+                            &state.precomputed.l0_coset_evals,
+                            E::Fr::from(reveal_attribute_hash as u64),
+                            attribute_hash,
+                        );
+                }
+            }
         }
 
         //// Sanity check
@@ -486,7 +1566,7 @@ impl Prover {
             &extended_coset_domain.coset_ifft(&quotient_coset_evals),
         );
 
-        let quotient_commit = commit(&state.proving_key.srs_g1, &quotient);
+        let quotient_commit = state.proving_key.commit_g1(&quotient);
         state.quotient = Some(quotient);
         quotient_commit.into()
     }
@@ -527,8 +1607,14 @@ impl Prover {
         //}
 
         // 4. define CI
+        let looked_up_value = match (state.tuple, state.beta) {
+            (Some(tuple), Some(beta)) => {
+                state.witness.identity_commitment + combine_values(&tuple.values, beta) * beta
+            }
+            _ => state.witness.identity_commitment,
+        };
         let mut ci = DensePolynomial::<E::Fr>::zero();
-        ci += &(&ts[0] * state.witness.identity_commitment);
+        ci += &(&ts[0] * looked_up_value);
 
         // 5. blind CI
         let ci_blind = &DensePolynomial::from_coefficients_slice(&[r2, r3, r4]) * &zi;
@@ -545,9 +1631,9 @@ impl Prover {
         u_prime += &u_blind;
 
         // 8. Commit
-        let zi_commitment = commit(&state.proving_key.srs_g1, &zi);
-        let ci_commitment = commit(&state.proving_key.srs_g1, &ci);
-        let u_prime_commitment = commit(&state.proving_key.srs_g1, &u_prime);
+        let zi_commitment = state.proving_key.commit_g1(&zi);
+        let ci_commitment = state.proving_key.commit_g1(&ci);
+        let u_prime_commitment = state.proving_key.commit_g1(&u_prime);
 
         // store data in the state
         state.zi = Some(zi);
@@ -567,10 +1653,26 @@ impl Prover {
         hi_2: E::Fr,
     ) -> (E::G2Affine, E::G1Affine) {
         // 1. compute linearly separated quotients in g2
-        let w1_i = state
+        let mut w1_i = state
             .precomputed
             .caulk_plus_precomputed
             .get_w1_i(&state.witness.index);
+
+        // The W1 value of the combined accumulator of a tuple lookup is the same combination of
+        // the W1 values of its columns
+        if let (Some(tuple), Some(beta)) = (state.tuple, state.beta) {
+            let mut combined = w1_i.into_projective();
+            let mut beta_pow = beta;
+            for column in 1..=tuple.values.len() {
+                let column_w1_i = state
+                    .precomputed
+                    .caulk_plus_precomputed
+                    .get_column_w1_i(column, &state.witness.index);
+                combined += column_w1_i.mul(beta_pow);
+                beta_pow *= beta;
+            }
+            w1_i = combined.into_affine();
+        }
         let w2_i = state
             .precomputed
             .caulk_plus_precomputed
@@ -590,14 +1692,15 @@ impl Prover {
         );
         let extended_domain = GeneralEvaluationDomain::<E::Fr>::new(composed_degree).unwrap();
 
-        let u_prime_evals_on_extended_domain =
-            cfg_into_iter!(extended_domain.elements()).map(|omega_i| u_prime.evaluate(&omega_i));
-        let mut zi_of_u_prime_evals = vec![E::Fr::zero(); extended_domain.size()];
-        let mut ci_of_u_prime_evals = vec![E::Fr::zero(); extended_domain.size()];
-        for (i, ui) in u_prime_evals_on_extended_domain.enumerate() {
-            zi_of_u_prime_evals[i] = zi.evaluate(&ui);
-            ci_of_u_prime_evals[i] = ci.evaluate(&ui);
-        }
+        // Rayon cannot split the elements() iterator, so collect the elements first
+        let elements: Vec<E::Fr> = extended_domain.elements().collect();
+        let (zi_of_u_prime_evals, ci_of_u_prime_evals): (Vec<E::Fr>, Vec<E::Fr>) =
+            cfg_into_iter!(elements)
+                .map(|omega_i| {
+                    let ui = u_prime.evaluate(&omega_i);
+                    (zi.evaluate(&ui), ci.evaluate(&ui))
+                })
+                .unzip();
 
         let zi_of_ui =
             DensePolynomial::from_coefficients_slice(&extended_domain.ifft(&zi_of_u_prime_evals));
@@ -620,7 +1723,7 @@ impl Prover {
         let ci_blinder_commitment = commit(&state.proving_key.srs_g2, ci_blinder);
 
         let w_commitment = w1_xi2_w2.mul(r1.inverse().unwrap().into_repr()) - ci_blinder_commitment;
-        let h_commitment = commit(&state.proving_key.srs_g1, &h);
+        let h_commitment = state.proving_key.commit_g1(&h);
 
         // store data in the state
         state.zi_of_ui = Some(zi_of_ui);
@@ -630,6 +1733,101 @@ impl Prover {
         (w_commitment.into(), h_commitment.into())
     }
 
+    // Compute the polynomials P1 and P2 of the Caulk+ lookup
+    fn caulk_plus_p1_p2<E: PairingEngine>(
+        state: &State<E>,
+        hi_1: E::Fr,
+        alpha: E::Fr,
+    ) -> (DensePolynomial<E::Fr>, DensePolynomial<E::Fr>) {
+        let zi = state.zi.as_ref().unwrap();
+        let ci = state.ci.as_ref().unwrap();
+        let u_prime = state.u_prime.as_ref().unwrap();
+        let h = state.h.as_ref().unwrap();
+        let a = state.a.as_ref().unwrap();
+
+        // 1. Compute P1
+        let p1 = zi + &(ci * hi_1);
+
+        // 2. Compute P2
+        let p2 = {
+            let u_at_alpha = u_prime.evaluate(&alpha);
+            let zi_at_u_alpha = zi.evaluate(&u_at_alpha);
+            let ci_at_u_alpha = ci.evaluate(&u_at_alpha);
+            let a_at_alpha = a.evaluate(&alpha);
+
+            let zv_alpha = state.domain_v.evaluate_vanishing_polynomial(alpha);
+
+            let free_coeff = hi_1 * ci_at_u_alpha + zi_at_u_alpha - hi_1 * a_at_alpha;
+            let mut h_zv = h * -zv_alpha;
+            h_zv[0] += free_coeff;
+
+            h_zv
+        };
+
+        (p1, p2)
+    }
+
+    /*
+     * Open the Caulk+ polynomials of the second lookup of a linked proof after the opening
+     * round, and prove these openings with a second multiopen proof, which also covers the
+     * circuit polynomials at the same points.
+     */
+    #[allow(clippy::type_complexity)]
+    fn second_lookup_opening_round(
+        state: &State<Bn254>,
+        second: &State<Bn254>,
+        hi_1: Fr,
+        alpha: Fr,
+        transcript: &mut Transcript,
+    ) -> (
+        MultiopenProof<Bn254>,
+        Fr,
+        Fr,
+        Fr,
+        DensePolynomial<Fr>,
+        DensePolynomial<Fr>,
+    ) {
+        assert!(state.extension.is_none());
+
+        let omega_alpha = state.domain_h.element(1) * alpha;
+        let omega_n_alpha = state.domain_h.element(NUMBER_OF_MIMC_ROUNDS) * alpha;
+
+        let u_prime = second.u_prime.as_ref().unwrap();
+        let (p1, p2) = Self::caulk_plus_p1_p2(second, hi_1, alpha);
+
+        let u_prime_opening = u_prime.evaluate(&alpha);
+        let p1_opening = p1.evaluate(&u_prime_opening);
+        let p2_opening = p2.evaluate(&alpha);
+
+        assert_eq!(p2_opening, Fr::zero());
+
+        transcript.update_with_f(u_prime_opening);
+        transcript.update_with_f(p1_opening);
+        transcript.update_with_f(p2_opening);
+
+        let m = MultiopenProver::prove(
+            &state.proving_key.srs_g1,
+            state.proving_key.g1_msm_table.as_ref(),
+            state.w0.as_ref().unwrap(),
+            state.w1.as_ref().unwrap(),
+            state.w2.as_ref().unwrap(),
+            state.key.as_ref().unwrap(),
+            &state.precomputed.q_mimc,
+            &state.precomputed.mimc_cts,
+            state.quotient.as_ref().unwrap(),
+            u_prime,
+            &p1,
+            &p2,
+            u_prime_opening,
+            alpha,
+            omega_alpha,
+            omega_n_alpha,
+            transcript,
+        );
+
+        (m, u_prime_opening, p1_opening, p2_opening, p1, p2)
+    }
+
     #[allow(clippy::type_complexity)]
     fn opening_round<'a>(
         state: &State<'a, Bn254>,
@@ -657,6 +1855,7 @@ impl Prover {
         Fr,
         DensePolynomial<Fr>,
         DensePolynomial<Fr>,
+        Vec<[Fr; 3]>,
     ) {
         let omega = state.domain_h.element(1);
         let omega_n = state.domain_h.element(NUMBER_OF_MIMC_ROUNDS);
@@ -673,30 +1872,9 @@ impl Prover {
         let mimc_cts = &state.precomputed.mimc_cts;
         let q_mimc = &state.precomputed.q_mimc;
 
-        let zi = state.zi.as_ref().unwrap();
-        let ci = state.ci.as_ref().unwrap();
         let u_prime = state.u_prime.as_ref().unwrap();
-        let h = state.h.as_ref().unwrap();
-        let a = state.a.as_ref().unwrap();
 
-        // 1. Compute P1
-        let p1 = zi + &(ci * hi_1);
-
-        // 2. Compute P2
-        let p2 = {
-            let u_at_alpha = u_prime.evaluate(&alpha);
-            let zi_at_u_alpha = zi.evaluate(&u_at_alpha);
-            let ci_at_u_alpha = ci.evaluate(&u_at_alpha);
-            let a_at_alpha = a.evaluate(&alpha);
-
-            let zv_alpha = state.domain_v.evaluate_vanishing_polynomial(alpha);
-
-            let free_coeff = hi_1 * ci_at_u_alpha + zi_at_u_alpha - hi_1 * a_at_alpha;
-            let mut h_zv = h * -zv_alpha;
-            h_zv[0] += free_coeff;
-
-            h_zv
-        };
+        let (p1, p2) = Self::caulk_plus_p1_p2(state, hi_1, alpha);
 
         // compute all openings
         let u_prime_opening = u_prime.evaluate(&alpha);
@@ -721,6 +1899,18 @@ impl Prover {
 
         let key_openings = [key.evaluate(&alpha), key.evaluate(&omega_alpha)];
 
+        let extra_openings: Vec<[Fr; 3]> = state
+            .extra
+            .iter()
+            .map(|poly| {
+                [
+                    poly.evaluate(&alpha),
+                    poly.evaluate(&omega_alpha),
+                    poly.evaluate(&omega_n_alpha),
+                ]
+            })
+            .collect();
+
         let q_mimc_opening = q_mimc.evaluate(&alpha);
         let mimc_cts_opening = mimc_cts.evaluate(&alpha);
         let quotient_opening = quotient.evaluate(&alpha);
@@ -729,32 +1919,68 @@ impl Prover {
 
         assert_eq!(p2_opening, Fr::zero());
 
-        transcript.round_4([
-            w0_openings[0],
-            w0_openings[1],
-            w0_openings[2],
-            w1_openings[0],
-            w1_openings[1],
-            w1_openings[2],
-            w2_openings[0],
-            w2_openings[1],
-            w2_openings[2],
-            key_openings[0],
-            key_openings[1],
-            q_mimc_opening,
-            mimc_cts_opening,
-            quotient_opening,
-            u_prime_opening,
-            p1_opening,
-            p2_opening,
-        ]);
+        if state.membership_only {
+            transcript.round_4_membership([
+                w0_openings[0],
+                w0_openings[1],
+                w0_openings[2],
+                w1_openings[0],
+                w1_openings[1],
+                w1_openings[2],
+                key_openings[0],
+                key_openings[1],
+                q_mimc_opening,
+                mimc_cts_opening,
+                quotient_opening,
+                u_prime_opening,
+                p1_opening,
+                p2_opening,
+            ]);
+        } else {
+            transcript.round_4([
+                w0_openings[0],
+                w0_openings[1],
+                w0_openings[2],
+                w1_openings[0],
+                w1_openings[1],
+                w1_openings[2],
+                w2_openings[0],
+                w2_openings[1],
+                w2_openings[2],
+                key_openings[0],
+                key_openings[1],
+                q_mimc_opening,
+                mimc_cts_opening,
+                quotient_opening,
+                u_prime_opening,
+                p1_opening,
+                p2_opening,
+            ]);
+        }
+
+        // The extra columns are opened at the same points as w2, so the multiopen argument opens
+        // the random linear combination w2 + gamma * extra[0] + gamma ^ 2 * extra[1] + ... in place
+        // of w2
+        let mut w2 = w2.clone();
+        if !state.extra.is_empty() {
+            for opening in extra_openings.iter().flatten() {
+                transcript.update_with_f(*opening);
+            }
+            let gamma = transcript.get_challenge();
+            let mut gamma_pow = gamma;
+            for poly in state.extra.iter() {
+                w2 += &(poly * gamma_pow);
+                gamma_pow *= gamma;
+            }
+        }
 
         // Compute the multiopen proof
         let m = MultiopenProver::prove(
             &state.proving_key.srs_g1,
+            state.proving_key.g1_msm_table.as_ref(),
             w0,
             w1,
-            w2,
+            &w2,
             key,
             q_mimc,
             mimc_cts,
@@ -790,6 +2016,7 @@ impl Prover {
             p2_opening,
             p1,
             p2,
+            extra_openings,
         )
     }
 }
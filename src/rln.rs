@@ -0,0 +1,98 @@
+/*
+   Rate-limiting nullifier (RLN) mode. Each message reveals a point (x, y) on the line
+   y = a0 + a1 * x, where a0 is the identity nullifier, a1 = MiMC7(a0, external_nullifier) and x is
+   the signal hash. The public RLN nullifier is MiMC7(a1), so two messages with the same external
+   nullifier can be linked, and their shares reveal a0.
+
+   To allow up to message_limit messages per epoch, the external nullifier of each message is
+   MiMC7(epoch, message_id) where 0 <= message_id < message_limit. The message ID is private: the
+   circuit computes the external nullifier from it and range-checks it against the public limit,
+   so the messages of a member in an epoch are only linkable when a message ID is reused.
+*/
+use crate::error::Error;
+use crate::identity::Identity;
+use crate::mimc7::init_mimc7;
+use ark_bn254::Fr;
+use ark_ff::{Field, Zero};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RlnShare {
+    pub x: Fr,
+    pub y: Fr,
+}
+
+/*
+ * Compute the external nullifier of a message.
+ * @param epoch: The epoch, e.g. a timestamp divided by the epoch length.
+ * @param message_id: The index of the message within the epoch.
+ * @param message_limit: The number of messages a member may send per epoch.
+ */
+pub fn rln_external_nullifier(epoch: Fr, message_id: u64, message_limit: u64) -> Result<Fr, Error> {
+    if message_id >= message_limit {
+        return Err(Error::MessageLimitExceeded);
+    }
+
+    let mimc7 = init_mimc7::<Fr>();
+    Ok(mimc7.multi_hash(&[epoch, Fr::from(message_id)], Fr::zero()))
+}
+
+// The slope a1 of the share line, which is the identity's nullifier hash
+pub fn compute_a1(identity: &Identity, external_nullifier: Fr) -> Fr {
+    identity.nullifier_hash(external_nullifier)
+}
+
+pub fn compute_share(identity: &Identity, external_nullifier: Fr, signal_hash: Fr) -> RlnShare {
+    let a1 = compute_a1(identity, external_nullifier);
+
+    RlnShare {
+        x: signal_hash,
+        y: identity.nullifier + a1 * signal_hash,
+    }
+}
+
+pub fn compute_rln_nullifier(identity: &Identity, external_nullifier: Fr) -> Fr {
+    let mimc7 = init_mimc7::<Fr>();
+    mimc7.multi_hash(&[compute_a1(identity, external_nullifier)], Fr::zero())
+}
+
+/*
+ * Recover the identity nullifier a0 from two shares with the same RLN nullifier. Returns None if
+ * the shares have the same x-coordinate, i.e. the same signal was sent twice.
+ */
+pub fn recover_secret(share_1: &RlnShare, share_2: &RlnShare) -> Option<Fr> {
+    let a1 = (share_1.y - share_2.y) * (share_1.x - share_2.x).inverse()?;
+    Some(share_1.y - a1 * share_1.x)
+}
+
+#[cfg(test)]
+mod rln_tests {
+    use super::{compute_share, recover_secret, rln_external_nullifier};
+    use crate::error::Error;
+    use crate::identity::Identity;
+    use ark_bn254::Fr;
+
+    #[test]
+    fn test_recover_secret() {
+        let identity = Identity::new(Fr::from(100u64), Fr::from(200u64));
+        let external_nullifier = rln_external_nullifier(Fr::from(1u64), 0, 2).unwrap();
+
+        let share_1 = compute_share(&identity, external_nullifier, Fr::from(1234u64));
+        let share_2 = compute_share(&identity, external_nullifier, Fr::from(5678u64));
+
+        assert_eq!(recover_secret(&share_1, &share_2), Some(identity.nullifier));
+        assert_eq!(recover_secret(&share_1, &share_1), None);
+    }
+
+    #[test]
+    fn test_message_limit() {
+        let epoch = Fr::from(1u64);
+        assert_ne!(
+            rln_external_nullifier(epoch, 0, 2).unwrap(),
+            rln_external_nullifier(epoch, 1, 2).unwrap()
+        );
+        assert_eq!(
+            rln_external_nullifier(epoch, 2, 2).unwrap_err(),
+            Error::MessageLimitExceeded
+        );
+    }
+}
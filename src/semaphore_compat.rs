@@ -0,0 +1,341 @@
+/*
+   A Semaphore-style API on top of the Semacaulk prover and verifier, for teams migrating from
+   Semaphore. It hides the Layouter, ProverPrecomputedData, WitnessInput and PublicData wiring
+   behind Identity, Group, generate_proof() and verify_proof().
+
+   Semantics which differ from Semaphore:
+    - A group is represented by a KZG accumulator (a G1 point), not a Merkle root. Proofs are
+      bound to the accumulator value at proving time, and there is no tree depth or group ID.
+    - A group has a fixed capacity, which is the size of the SRS-derived Lagrange basis used to
+      build it (2 ^ 10 to 2 ^ 28). Empty slots hold the zero leaf (keccak256("Semacaulk") mod p).
+    - Identity commitments are MiMC7(id_nul, id_trap) and nullifier hashes are
+      MiMC7(id_nul, ext_nul), rather than Poseidon hashes, so identities cannot be reused across
+      Semaphore and Semacaulk groups.
+    - The external nullifier is used as-is rather than hashed.
+    - The signal hash is keccak256(signal) >> 8, as in Semaphore and Semacaulk.sol.
+    - Proving requires the proving key (SRS) and the W1 and W2 precomputations for the member's
+      index, which are computed here and cost O(capacity) G2 operations.
+*/
+use crate::{
+    accumulator::{
+        compute_prefix_accumulator, compute_prefix_polynomial, compute_zero_leaf, Accumulator,
+    },
+    bn_solidity_utils::u256_to_f,
+    contracts::compute_signal_hash,
+    error::Error,
+    identity::{rotation_external_nullifier, Identity},
+    layouter::Layouter,
+    mimc7::init_mimc7,
+    prover::{
+        prover::{Prover, WitnessInput},
+        Proof, ProverPrecomputedData, ProvingKey, PublicData, RotationProof, RotationPublicData,
+    },
+    verifier::{Verifier, VerifyingKey},
+};
+use ark_bn254::{Bn254, Fr, G1Affine};
+use ark_poly::{
+    univariate::DensePolynomial, EvaluationDomain, GeneralEvaluationDomain, UVPolynomial,
+};
+use rand::RngCore;
+
+/*
+ * A group of identity commitments, mirroring the accumulator stored in the Semacaulk contract.
+ * Members are inserted in order, so a member's index is its insertion order.
+ */
+#[derive(Clone, Debug)]
+pub struct Group {
+    accumulator: Accumulator<Bn254>,
+    members: Vec<Fr>,
+    size: usize,
+}
+
+impl Group {
+    /*
+     * Create an empty group whose capacity is the number of Lagrange basis commitments.
+     * @param lagrange_comms: The commitments to the Lagrange basis polynomials.
+     */
+    pub fn new(lagrange_comms: &[G1Affine]) -> Self {
+        let zero = compute_zero_leaf::<Fr>();
+        Self {
+            accumulator: Accumulator::<Bn254>::new(zero, lagrange_comms),
+            members: vec![zero; lagrange_comms.len()],
+            size: 0,
+        }
+    }
+
+    /*
+     * Insert an identity commitment into the next empty slot, and return its index.
+     */
+    pub fn add_member(&mut self, identity_commitment: Fr) -> Result<usize, Error> {
+        if self.size == self.capacity() {
+            return Err(Error::GroupIsFull);
+        }
+
+        let index = self.size;
+        self.accumulator.update(index, identity_commitment);
+        self.members[index] = identity_commitment;
+        self.size += 1;
+
+        Ok(index)
+    }
+
+    /*
+     * Apply a rotation, after its proof has been verified with verify_rotation_proof(), and
+     * return the delta which was added to the accumulator. The delta is computed from the
+     * public inputs of the proof, so it replaces the identity commitment at the proven index.
+     */
+    pub fn rotate_member(
+        &mut self,
+        public_input: &RotationPublicData<Bn254>,
+    ) -> Result<G1Affine, Error> {
+        let index = public_input.index;
+        if index >= self.size || self.members[index] != public_input.old_identity_commitment {
+            return Err(Error::IdentityNotInGroup);
+        }
+
+        let delta = public_input.delta(&self.accumulator.lagrange_comms)?;
+        self.accumulator.update_with_point(delta);
+        self.members[index] = public_input.new_identity_commitment;
+
+        Ok(delta)
+    }
+
+    pub fn lagrange_comms(&self) -> &[G1Affine] {
+        &self.accumulator.lagrange_comms
+    }
+
+    pub fn index_of(&self, identity_commitment: Fr) -> Option<usize> {
+        self.members[..self.size]
+            .iter()
+            .position(|m| *m == identity_commitment)
+    }
+
+    pub fn members(&self) -> &[Fr] {
+        &self.members[..self.size]
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.members.len()
+    }
+
+    // The counterpart of a Semaphore group's Merkle root
+    pub fn accumulator(&self) -> G1Affine {
+        self.accumulator.point
+    }
+
+    /*
+     * The accumulator of the members whose index is less than k, against which a member proves
+     * that they joined before index k (see Prover::prove_prefix()).
+     */
+    pub fn prefix_accumulator(&self, k: usize) -> G1Affine {
+        compute_prefix_accumulator::<Bn254>(
+            self.accumulator.zero,
+            &self.accumulator.lagrange_comms,
+            &self.members,
+            k,
+        )
+    }
+
+    pub fn prefix_polynomial(&self, k: usize) -> DensePolynomial<Fr> {
+        compute_prefix_polynomial(self.accumulator.zero, &self.members, k, self.capacity())
+    }
+
+    /*
+     * The polynomial C(X) whose evaluations over the table domain are the members, padded with
+     * the zero leaf. Its commitment is the accumulator.
+     */
+    pub fn polynomial(&self) -> DensePolynomial<Fr> {
+        let domain = GeneralEvaluationDomain::<Fr>::new(self.capacity()).unwrap();
+        DensePolynomial::from_coefficients_slice(&domain.ifft(&self.members))
+    }
+}
+
+pub fn generate_nullifier_hash(identity: &Identity, external_nullifier: Fr) -> Fr {
+    identity.nullifier_hash(external_nullifier)
+}
+
+pub fn hash_signal(signal: &str) -> Fr {
+    u256_to_f(compute_signal_hash(signal))
+}
+
+/*
+ * Generate a proof that the identity is a member of the group, for the given external nullifier
+ * and signal.
+ * @param pk: The proving key, whose SRS must match the one used to build the group.
+ * @param identity: The prover's identity.
+ * @param group: The group, which must contain the identity's commitment.
+ * @param external_nullifier: The external nullifier.
+ * @param signal: The signal, which is hashed with hash_signal().
+ * @param rng: The random number generator used for blinding.
+ */
+pub fn generate_proof<R: RngCore>(
+    pk: &ProvingKey<Bn254>,
+    identity: &Identity,
+    group: &Group,
+    external_nullifier: Fr,
+    signal: &str,
+    rng: &mut R,
+) -> Result<Proof<Bn254>, Error> {
+    let identity_commitment = identity.commitment();
+    let index = group
+        .index_of(identity_commitment)
+        .ok_or(Error::IdentityNotInGroup)?;
+    let table_size = group.capacity();
+
+    let mimc7 = init_mimc7::<Fr>();
+    let assignment = Layouter::assign(
+        identity.nullifier,
+        identity.trapdoor,
+        external_nullifier,
+        &mimc7.cts,
+        rng,
+    );
+
+    let precomputed =
+        ProverPrecomputedData::index(pk, &mimc7.cts, &[index], &group.polynomial(), table_size);
+
+    let witness = WitnessInput {
+        identity_nullifier: identity.nullifier,
+        identity_trapdoor: identity.trapdoor,
+        identity_commitment,
+        index,
+    };
+
+    let public_input = PublicData::<Bn254> {
+        accumulator: group.accumulator(),
+        external_nullifier,
+        signal_hash: hash_signal(signal),
+        nullifier_hash: generate_nullifier_hash(identity, external_nullifier),
+    };
+
+    Ok(Prover::prove(
+        pk,
+        &witness,
+        &assignment,
+        &public_input,
+        &precomputed,
+        rng,
+        table_size,
+    ))
+}
+
+/*
+ * Verify a proof against a group accumulator, in the same way that Semacaulk.sol does.
+ * @param vk: The verifying key.
+ * @param accumulator: The group accumulator, in place of Semaphore's Merkle root.
+ * @param nullifier_hash: The nullifier hash.
+ * @param signal: The signal.
+ * @param external_nullifier: The external nullifier.
+ * @param proof: The proof.
+ */
+pub fn verify_proof(
+    vk: &VerifyingKey<Bn254>,
+    accumulator: G1Affine,
+    nullifier_hash: Fr,
+    signal: &str,
+    external_nullifier: Fr,
+    proof: &Proof<Bn254>,
+) -> bool {
+    let public_input = PublicData::<Bn254> {
+        accumulator,
+        external_nullifier,
+        signal_hash: hash_signal(signal),
+        nullifier_hash,
+    };
+
+    Verifier::verify(proof, vk.srs_g1_t, vk.srs_g2_1, accumulator, &public_input)
+}
+
+/*
+ * Generate a proof which authorises the replacement of the identity's commitment in the group
+ * with new_identity_commitment, e.g. after its trapdoor has leaked. The proof reveals the
+ * identity's index and commitment, so the rotation is linkable to the member's slot.
+ * @param pk: The proving key, whose SRS must match the one used to build the group.
+ * @param identity: The identity to rotate.
+ * @param new_identity_commitment: The commitment of the identity which replaces it.
+ * @param group: The group, which must contain the identity's commitment.
+ * @param rng: The random number generator used for blinding.
+ */
+pub fn generate_rotation_proof<R: RngCore>(
+    pk: &ProvingKey<Bn254>,
+    identity: &Identity,
+    new_identity_commitment: Fr,
+    group: &Group,
+    rng: &mut R,
+) -> Result<(RotationProof<Bn254>, RotationPublicData<Bn254>), Error> {
+    let identity_commitment = identity.commitment();
+    let index = group
+        .index_of(identity_commitment)
+        .ok_or(Error::IdentityNotInGroup)?;
+    let table_size = group.capacity();
+    let external_nullifier = rotation_external_nullifier::<Fr>();
+
+    let mimc7 = init_mimc7::<Fr>();
+    let assignment = Layouter::assign(
+        identity.nullifier,
+        identity.trapdoor,
+        external_nullifier,
+        &mimc7.cts,
+        rng,
+    );
+
+    let precomputed = ProverPrecomputedData::precompute_fixed(&mimc7.cts);
+
+    let witness = WitnessInput {
+        identity_nullifier: identity.nullifier,
+        identity_trapdoor: identity.trapdoor,
+        identity_commitment,
+        index,
+    };
+
+    let public_input = RotationPublicData::<Bn254> {
+        accumulator: group.accumulator(),
+        index,
+        old_identity_commitment: identity_commitment,
+        new_identity_commitment,
+        nullifier_hash: generate_nullifier_hash(identity, external_nullifier),
+    };
+
+    let proof = Prover::prove_rotation(
+        pk,
+        group.lagrange_comms(),
+        &group.polynomial(),
+        &witness,
+        &assignment,
+        &public_input,
+        &precomputed,
+        rng,
+        table_size,
+    )?;
+
+    Ok((proof, public_input))
+}
+
+/*
+ * Verify a rotation proof against the group, which must hold the old identity commitment at
+ * the proven index. The caller must also check that the nullifier hash has not been used before,
+ * and then apply the rotation with Group::rotate_member().
+ * @param vk: The verifying key.
+ * @param group: The group.
+ * @param public_input: The public inputs of the rotation proof.
+ * @param proof: The proof.
+ */
+pub fn verify_rotation_proof(
+    vk: &VerifyingKey<Bn254>,
+    group: &Group,
+    public_input: &RotationPublicData<Bn254>,
+    proof: &RotationProof<Bn254>,
+) -> bool {
+    public_input.accumulator == group.accumulator()
+        && Verifier::verify_rotation(
+            proof,
+            vk.srs_g1_t,
+            vk.srs_g2_1,
+            group.lagrange_comms(),
+            public_input,
+        )
+}
@@ -0,0 +1,331 @@
+/*
+   A versioned binary bundle of everything which the setup produces for one table size, so that
+   clients do not re-read the SRS from a ptau file on every run. All integers are little-endian:
+
+   | Offset | Size                  | Content                                                  |
+   |--------|-----------------------|----------------------------------------------------------|
+   | 0      | 8                     | ARTIFACT_MAGIC                                           |
+   | 8      | 4                     | The format version, ARTIFACT_VERSION                     |
+   | 12     | 4                     | log_2 of the table size                                  |
+   | 16     | 32                    | The root of the Lagrange commitment tree                 |
+   | 48     | G1_SIZE + G2_SIZE     | The verifying key: [X^table_size]_1 and [X]_2            |
+   | 240    | (n + 1) * G1_SIZE     | The SRS G1 points [X^0]_1 .. [X^n]_1                     |
+   |        | n * G2_SIZE           | The SRS G2 points [X^0]_2 .. [X^(n - 1)]_2               |
+   |        | n * G1_SIZE           | The commitments to the Lagrange basis polynomials        |
+   |        | 32                    | keccak256 of all the preceding bytes                     |
+
+   where n is the table size. Points are serialised uncompressed with CanonicalSerialize. The
+   loader checks the magic, version, length and hash, that each point it parses is on the curve,
+   and that G2 points are in the prime-order subgroup (in a batch with setup::check_g2_subgroup()
+   when many are read). Use setup::verify_srs() to check that the SRS points are consistent
+   powers of τ.
+
+   The prover only needs the first TrimConfig::default() powers once the W1 and W2 values are
+   supplied, but the whole SRS of the table size is kept: the G2 powers are needed to compute W1
+   and W2 without a precompute server, [X^n]_1 is in the verifying key, and verify_srs() needs
+   every power to check the artifact against its source. Points are read lazily from the memory
+   map, so a client which only needs a prefix reads it with trimmed_proving_key() or srs_g1().
+*/
+use crate::accumulator::compute_lagrange_tree;
+use crate::error::Error;
+use crate::prover::{ProvingKey, TrimConfig};
+use crate::setup::check_g2_subgroup;
+use crate::verifier::VerifyingKey;
+use ark_bn254::{Bn254, Fr, G1Affine, G2Affine};
+use ark_ec::AffineCurve;
+use ark_ff::{FpParameters, PrimeField, Zero};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use memmap2::Mmap;
+use std::fs::File;
+use std::io::{BufWriter, Read, Write};
+use std::path::Path;
+use tiny_keccak::{Hasher, Keccak};
+
+pub const ARTIFACT_MAGIC: [u8; 8] = *b"SCAULKSU";
+pub const ARTIFACT_VERSION: u32 = 1;
+
+const G1_SIZE: usize = 64;
+const G2_SIZE: usize = 128;
+const HASH_SIZE: usize = 32;
+const HEADER_SIZE: usize = 16 + HASH_SIZE + G1_SIZE + G2_SIZE;
+
+pub struct SetupArtifact {
+    mmap: Mmap,
+    log_2_table_size: usize,
+}
+
+impl SetupArtifact {
+    /*
+     * Write the artifact of a table size.
+     * @param path: The file to write.
+     * @param pk: The proving key, with at least table_size + 1 G1 points and table_size G2 points.
+     * @param lagrange_comms: The commitments to the Lagrange basis polynomials.
+     */
+    pub fn write<P: AsRef<Path>>(
+        path: P,
+        pk: &ProvingKey<Bn254>,
+        lagrange_comms: &[G1Affine],
+        log_2_table_size: usize,
+    ) -> Result<(), Error> {
+        let table_size = 1usize << log_2_table_size;
+        if pk.srs_g1.len() <= table_size || pk.srs_g2.len() < table_size {
+            return Err(Error::SrsTooShort);
+        }
+        if lagrange_comms.len() != table_size {
+            return Err(Error::LagrangeCommsWrongLength);
+        }
+
+        let file = File::create(path).map_err(io_error)?;
+        let mut writer = HashingWriter {
+            inner: BufWriter::new(file),
+            hasher: Keccak::v256(),
+        };
+
+        let root = compute_lagrange_tree::<Bn254>(&lagrange_comms.to_vec()).root();
+
+        writer.write_all(&ARTIFACT_MAGIC).map_err(io_error)?;
+        writer
+            .write_all(&ARTIFACT_VERSION.to_le_bytes())
+            .map_err(io_error)?;
+        writer
+            .write_all(&(log_2_table_size as u32).to_le_bytes())
+            .map_err(io_error)?;
+        writer.write_all(&root).map_err(io_error)?;
+
+        // The verifying key
+        write_point(&mut writer, &pk.srs_g1[table_size])?;
+        write_point(&mut writer, &pk.srs_g2[1])?;
+
+        for p in pk.srs_g1[..=table_size].iter() {
+            write_point(&mut writer, p)?;
+        }
+        for p in pk.srs_g2[..table_size].iter() {
+            write_point(&mut writer, p)?;
+        }
+        for p in lagrange_comms.iter() {
+            write_point(&mut writer, p)?;
+        }
+
+        let mut hash = [0u8; HASH_SIZE];
+        writer.hasher.finalize(&mut hash);
+        writer.inner.write_all(&hash).map_err(io_error)?;
+        writer.inner.flush().map_err(io_error)
+    }
+
+    // Memory-map an artifact, and check its header, length and hash
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let file = File::open(path).map_err(io_error)?;
+
+        // Safety: the map is read-only, and the artifact is not expected to be modified while it
+        // is open. Its hash is checked below, before any point is read.
+        let mmap = unsafe { Mmap::map(&file) }.map_err(io_error)?;
+
+        if mmap.len() < HEADER_SIZE + HASH_SIZE {
+            return Err(Error::ArtifactTruncated);
+        }
+        if mmap[..8] != ARTIFACT_MAGIC {
+            return Err(Error::InvalidArtifactMagic);
+        }
+        let version = u32::from_le_bytes(mmap[8..12].try_into().unwrap());
+        if version != ARTIFACT_VERSION {
+            return Err(Error::UnsupportedArtifactVersion(version));
+        }
+        let log_2_table_size = u32::from_le_bytes(mmap[12..16].try_into().unwrap()) as usize;
+        if !(10..=28).contains(&log_2_table_size) {
+            return Err(Error::InvalidArtifactTableSize);
+        }
+
+        let artifact = Self {
+            mmap,
+            log_2_table_size,
+        };
+        if artifact.mmap.len() != artifact.hash_offset() + HASH_SIZE {
+            return Err(Error::ArtifactTruncated);
+        }
+
+        let mut hasher = Keccak::v256();
+        hasher.update(&artifact.mmap[..artifact.hash_offset()]);
+        let mut hash = [0u8; HASH_SIZE];
+        hasher.finalize(&mut hash);
+        if hash[..] != artifact.mmap[artifact.hash_offset()..] {
+            return Err(Error::ArtifactChecksumMismatch);
+        }
+
+        Ok(artifact)
+    }
+
+    // Whether the file starts with ARTIFACT_MAGIC, i.e. is an artifact rather than a ptau file
+    pub fn is_artifact<P: AsRef<Path>>(path: P) -> bool {
+        let mut magic = [0u8; 8];
+        File::open(path)
+            .and_then(|mut file| file.read_exact(&mut magic))
+            .map(|_| magic == ARTIFACT_MAGIC)
+            .unwrap_or(false)
+    }
+
+    pub fn log_2_table_size(&self) -> usize {
+        self.log_2_table_size
+    }
+
+    pub fn table_size(&self) -> usize {
+        1 << self.log_2_table_size
+    }
+
+    // The root of the Lagrange commitment tree, which the contract is deployed with
+    pub fn lagrange_root(&self) -> [u8; 32] {
+        self.mmap[16..48].try_into().unwrap()
+    }
+
+    // The keccak256 hash of the artifact's content
+    pub fn content_hash(&self) -> [u8; 32] {
+        self.mmap[self.hash_offset()..].try_into().unwrap()
+    }
+
+    pub fn verifying_key(&self) -> Result<VerifyingKey<Bn254>, Error> {
+        Ok(VerifyingKey {
+            srs_g1_t: read_g1(&self.mmap[48..48 + G1_SIZE])?,
+            srs_g2_1: read_g2(&self.mmap[48 + G1_SIZE..HEADER_SIZE])?,
+        })
+    }
+
+    // The proving key with the whole SRS of the table size
+    pub fn proving_key(&self) -> Result<ProvingKey<Bn254>, Error> {
+        self.trimmed_proving_key(TrimConfig {
+            num_g1_points: self.table_size() + 1,
+            num_g2_points: self.table_size(),
+        })
+    }
+
+    /*
+     * The proving key with only the first powers of tau, as ProvingKey::trim() keeps, which only
+     * reads those points from the artifact.
+     * @param config: The number of G1 and G2 points to read.
+     */
+    pub fn trimmed_proving_key(&self, config: TrimConfig) -> Result<ProvingKey<Bn254>, Error> {
+        if config.num_g1_points > self.table_size() + 1 || config.num_g2_points > self.table_size()
+        {
+            return Err(Error::SrsTooShort);
+        }
+
+        let srs_g1 = self.srs_g1_bytes()[..config.num_g1_points * G1_SIZE]
+            .chunks(G1_SIZE)
+            .map(read_g1)
+            .collect::<Result<Vec<_>, _>>()?;
+        let srs_g2 = read_g2_batch(&self.srs_g2_bytes()[..config.num_g2_points * G2_SIZE])?;
+        Ok(ProvingKey::new(srs_g1, srs_g2))
+    }
+
+    // [X^index]_1, without reading the other SRS points
+    pub fn srs_g1(&self, index: usize) -> Result<G1Affine, Error> {
+        if index > self.table_size() {
+            return Err(Error::ArtifactIndexOutOfRange);
+        }
+        read_g1(&self.srs_g1_bytes()[index * G1_SIZE..(index + 1) * G1_SIZE])
+    }
+
+    // [X^index]_2, without reading the other SRS points
+    pub fn srs_g2(&self, index: usize) -> Result<G2Affine, Error> {
+        if index >= self.table_size() {
+            return Err(Error::ArtifactIndexOutOfRange);
+        }
+        read_g2(&self.srs_g2_bytes()[index * G2_SIZE..(index + 1) * G2_SIZE])
+    }
+
+    pub fn lagrange_comms(&self) -> Result<Vec<G1Affine>, Error> {
+        self.lagrange_comms_bytes()
+            .chunks(G1_SIZE)
+            .map(read_g1)
+            .collect()
+    }
+
+    // The commitment to one Lagrange basis polynomial, without reading the others
+    pub fn lagrange_comm(&self, index: usize) -> Result<G1Affine, Error> {
+        if index >= self.table_size() {
+            return Err(Error::ArtifactIndexOutOfRange);
+        }
+        read_g1(&self.lagrange_comms_bytes()[index * G1_SIZE..(index + 1) * G1_SIZE])
+    }
+
+    fn srs_g1_bytes(&self) -> &[u8] {
+        let len = (self.table_size() + 1) * G1_SIZE;
+        &self.mmap[HEADER_SIZE..HEADER_SIZE + len]
+    }
+
+    fn srs_g2_bytes(&self) -> &[u8] {
+        let start = HEADER_SIZE + (self.table_size() + 1) * G1_SIZE;
+        &self.mmap[start..start + self.table_size() * G2_SIZE]
+    }
+
+    fn lagrange_comms_bytes(&self) -> &[u8] {
+        let start = self.hash_offset() - self.table_size() * G1_SIZE;
+        &self.mmap[start..self.hash_offset()]
+    }
+
+    fn hash_offset(&self) -> usize {
+        let n = self.table_size();
+        HEADER_SIZE + (n + 1) * G1_SIZE + n * G2_SIZE + n * G1_SIZE
+    }
+}
+
+// Writes to the inner writer, and hashes everything written
+struct HashingWriter<W: Write> {
+    inner: W,
+    hasher: Keccak,
+}
+
+impl<W: Write> Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hasher.update(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+fn write_point<W: Write, P: CanonicalSerialize>(writer: &mut W, point: &P) -> Result<(), Error> {
+    point
+        .serialize_uncompressed(writer)
+        .map_err(|e| Error::ArtifactIoError(e.to_string()))
+}
+
+fn read_g1(bytes: &[u8]) -> Result<G1Affine, Error> {
+    let p = G1Affine::deserialize_unchecked(bytes).map_err(|_| Error::InvalidArtifactPoint)?;
+    if !p.is_on_curve() {
+        return Err(Error::InvalidArtifactPoint);
+    }
+    Ok(p)
+}
+
+fn read_g2_on_curve(bytes: &[u8]) -> Result<G2Affine, Error> {
+    let p = G2Affine::deserialize_unchecked(bytes).map_err(|_| Error::InvalidArtifactPoint)?;
+    if !p.is_on_curve() {
+        return Err(Error::InvalidArtifactPoint);
+    }
+    Ok(p)
+}
+
+// Read one G2 point, and check that it is in the prime-order subgroup
+fn read_g2(bytes: &[u8]) -> Result<G2Affine, Error> {
+    let p = read_g2_on_curve(bytes)?;
+    if !p.mul(<Fr as PrimeField>::Params::MODULUS).is_zero() {
+        return Err(Error::InvalidArtifactPoint);
+    }
+    Ok(p)
+}
+
+// Read consecutive G2 points, and check that they are in the prime-order subgroup in a batch
+fn read_g2_batch(bytes: &[u8]) -> Result<Vec<G2Affine>, Error> {
+    let points = bytes
+        .chunks(G2_SIZE)
+        .map(read_g2_on_curve)
+        .collect::<Result<Vec<_>, _>>()?;
+    check_g2_subgroup(&points, &mut rand::thread_rng()).map_err(|_| Error::InvalidArtifactPoint)?;
+    Ok(points)
+}
+
+fn io_error(e: std::io::Error) -> Error {
+    Error::ArtifactIoError(e.to_string())
+}
@@ -0,0 +1,151 @@
+/*
+   Re-randomises a public SRS with a local secret s, so that nobody but the contributor knows the
+   new τ' = τ * s, as one more contribution to a powers of tau ceremony would. Unlike
+   kzg::unsafe_setup(), which samples τ itself, the result is secure as long as either τ or s
+   stays unknown.
+
+   The contributor proves knowledge of s as in the Perpetual Powers of Tau ceremony, with
+   random r:
+
+   - r_g1 = r * g1 and rs_g1 = (r * s) * g1
+   - h = a G2 point hashed from the previous SRS, r_g1 and rs_g1, whose discrete log nobody knows
+   - s_h = s * h
+
+   The verifier checks e(r_g1, s_h) = e(rs_g1, h), i.e. that s_h is s * h for the s of the proof,
+   and e(srs_g1'[1], h) = e(srs_g1[1], s_h), i.e. that τ' = τ * s. verify_srs() checks that the
+   new SRS is made of consistent powers of τ'.
+*/
+use crate::error::Error;
+use crate::rng::{FiatShamirRng, SimpleHashFiatShamirRng};
+use crate::setup::verify_srs;
+use ark_bn254::{Bn254, Fr, G1Affine, G1Projective, G2Affine, G2Projective};
+use ark_ec::{AffineCurve, PairingEngine, ProjectiveCurve};
+use ark_ff::{FpParameters, One, PrimeField, UniformRand, Zero};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Read, SerializationError, Write};
+use rand::rngs::StdRng;
+use rand::{CryptoRng, RngCore};
+use sha3::Keccak256;
+
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ContributionProof {
+    pub r_g1: G1Affine,
+    pub rs_g1: G1Affine,
+    pub s_h: G2Affine,
+}
+
+/*
+ * Multiply the i-th G1 and G2 powers of tau by s^i for a random secret s, which is discarded.
+ * Returns the new SRS and a proof of knowledge of s.
+ * @param srs_g1: The G1 powers of tau of the previous SRS, starting with the generator.
+ * @param srs_g2: The G2 powers of tau of the previous SRS, starting with the generator.
+ * @param rng: The source of s, which must be cryptographically secure.
+ */
+pub fn contribute<R: RngCore + CryptoRng>(
+    srs_g1: &[G1Affine],
+    srs_g2: &[G2Affine],
+    rng: &mut R,
+) -> Result<(Vec<G1Affine>, Vec<G2Affine>, ContributionProof), Error> {
+    if srs_g1.len() < 2 || srs_g2.len() < 2 {
+        return Err(Error::SrsTooShort);
+    }
+
+    let s = nonzero_scalar(rng);
+    let r = nonzero_scalar(rng);
+
+    let powers_of_s = powers(s, srs_g1.len().max(srs_g2.len()));
+    let new_g1: Vec<G1Projective> = srs_g1
+        .iter()
+        .zip(powers_of_s.iter())
+        .map(|(p, s_i)| p.mul(s_i.into_repr()))
+        .collect();
+    let new_g2: Vec<G2Projective> = srs_g2
+        .iter()
+        .zip(powers_of_s.iter())
+        .map(|(p, s_i)| p.mul(s_i.into_repr()))
+        .collect();
+
+    let g1 = G1Affine::prime_subgroup_generator();
+    let r_g1 = g1.mul(r.into_repr()).into_affine();
+    let rs_g1 = g1.mul((r * s).into_repr()).into_affine();
+    let h = hash_to_g2(srs_g1[1], srs_g2[1], r_g1, rs_g1);
+    let s_h = h.mul(s.into_repr()).into_affine();
+
+    Ok((
+        G1Projective::batch_normalization_into_affine(&new_g1),
+        G2Projective::batch_normalization_into_affine(&new_g2),
+        ContributionProof { r_g1, rs_g1, s_h },
+    ))
+}
+
+/*
+ * Check that a new SRS was derived from the previous one by a contributor who knows s, and that
+ * it is made of consistent powers of τ * s.
+ * @param rng: The source of the random scalars of verify_srs().
+ */
+pub fn verify_contribution<R: RngCore>(
+    srs_g1: &[G1Affine],
+    srs_g2: &[G2Affine],
+    new_g1: &[G1Affine],
+    new_g2: &[G2Affine],
+    proof: &ContributionProof,
+    rng: &mut R,
+) -> Result<(), Error> {
+    if srs_g1.len() < 2 || srs_g2.len() < 2 {
+        return Err(Error::SrsTooShort);
+    }
+    if new_g1.len() != srs_g1.len() || new_g2.len() != srs_g2.len() {
+        return Err(Error::ContributionLengthMismatch);
+    }
+    verify_srs(new_g1, new_g2, rng)?;
+
+    let ContributionProof { r_g1, rs_g1, s_h } = *proof;
+    if r_g1.is_zero() || rs_g1.is_zero() || !r_g1.is_on_curve() || !rs_g1.is_on_curve() {
+        return Err(Error::InvalidContributionProof);
+    }
+    if !s_h.is_on_curve() || !s_h.mul(<Fr as PrimeField>::Params::MODULUS).is_zero() {
+        return Err(Error::InvalidContributionProof);
+    }
+
+    let h = hash_to_g2(srs_g1[1], srs_g2[1], r_g1, rs_g1);
+    if Bn254::pairing(r_g1, s_h) != Bn254::pairing(rs_g1, h) {
+        return Err(Error::InvalidContributionProof);
+    }
+    if Bn254::pairing(new_g1[1], h) != Bn254::pairing(srs_g1[1], s_h) {
+        return Err(Error::ContributionNotFromPreviousSrs);
+    }
+
+    Ok(())
+}
+
+// A G2 point whose discrete log is unknown, derived from the previous SRS and the proof's G1 points
+fn hash_to_g2(tau_g1: G1Affine, tau_g2: G2Affine, r_g1: G1Affine, rs_g1: G1Affine) -> G2Affine {
+    let mut bytes = vec![];
+    tau_g1.serialize_uncompressed(&mut bytes).unwrap();
+    tau_g2.serialize_uncompressed(&mut bytes).unwrap();
+    r_g1.serialize_uncompressed(&mut bytes).unwrap();
+    rs_g1.serialize_uncompressed(&mut bytes).unwrap();
+
+    // G2Projective::rand() picks a random x-coordinate and clears the cofactor, rather than
+    // multiplying the generator by a known scalar
+    let mut rng = SimpleHashFiatShamirRng::<Keccak256, StdRng>::initialize(&bytes);
+    G2Projective::rand(&mut rng).into_affine()
+}
+
+fn nonzero_scalar<R: RngCore>(rng: &mut R) -> Fr {
+    loop {
+        let x = Fr::rand(rng);
+        if !x.is_zero() {
+            return x;
+        }
+    }
+}
+
+fn powers(x: Fr, n: usize) -> Vec<Fr> {
+    let mut powers = Vec::with_capacity(n);
+    let mut power = Fr::one();
+    for _ in 0..n {
+        powers.push(power);
+        power *= x;
+    }
+    powers
+}
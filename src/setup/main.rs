@@ -1,69 +1,640 @@
+use ark_bn254::{Bn254, G1Affine, G2Affine};
+use ark_ec::AffineCurve;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use clap::{Parser, Subcommand, ValueEnum};
+use clap_num::number_range;
+use rand::rngs::OsRng;
+use semacaulk::accumulator::compute_lagrange_tree;
 use semacaulk::bn_solidity_utils::{f_to_hex, f_to_hex_le};
-use semacaulk::setup::setup;
-use std::env;
+use semacaulk::error::Error;
+use semacaulk::out_of_core::{OutOfCore, ScratchFile};
+use semacaulk::prover::{ProvingKey, TrimConfig};
+use semacaulk::setup::artifact::SetupArtifact;
+use semacaulk::setup::contribution::{self, ContributionProof};
+use semacaulk::setup::ppot::{blake2b_hash_file, write_challenge, PpotFile};
+use semacaulk::setup::ptau::{hash_file, is_ptau, PtauHeader};
+use semacaulk::setup::{
+    g2_to_g2_str, load_setup, read_srs, setup, try_load_lagrange_comms_from_file,
+    verify_lagrange_comms, verify_setup, verify_srs,
+};
+use semacaulk::utils::compute_lagrange_basis_commitments;
 use std::fs::File;
 use std::io::prelude::*;
-use std::io::LineWriter;
+use std::io::{BufWriter, LineWriter};
+use std::process;
+
+#[derive(Debug, Parser)]
+#[command(name = "setup")]
+#[command(about = "Process the output of a powers of tau ceremony for Semacaulk", long_about = None)]
+struct Cli {
+    #[command(subcommand)]
+    command: Commands,
+}
+
+fn log_2_capacity_range(s: &str) -> Result<usize, String> {
+    number_range(s, 10, 28)
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum Format {
+    /// One point per line, in the format which try_g1_str_to_g1() and try_g2_str_to_g2() parse
+    Hex,
+    /// Concatenated uncompressed points, serialised with CanonicalSerialize
+    Binary,
+}
+
+#[derive(Debug, Subcommand)]
+enum Commands {
+    /// Print the header of a ptau or raw PPOT file, its number of powers, and its hash
+    Inspect {
+        /// The powers of tau (PTAU) file containing a phase 1 trusted setup output, or a raw
+        /// PPOT challenge or response file
+        #[arg(short, long, required = true)]
+        ptau: String,
+
+        /// If specified, check that a raw PPOT file starts with the BLAKE2b hash of this
+        /// challenge or response file, i.e. was made from it
+        #[arg(long, required = false)]
+        previous: Option<String>,
+    },
+    /// Export the G1 and G2 powers of tau which a capacity needs
+    Export {
+        /// The powers of tau (PTAU) file containing a phase 1 trusted setup output, or a raw
+        /// PPOT challenge or response file
+        #[arg(short, long, required = true)]
+        ptau: String,
+
+        /// The capacity of the accumulator expressed in log_2 (e.g. log_2(1024) = 10)
+        #[arg(short, long, required = true, value_parser=log_2_capacity_range)]
+        log_2_capacity: usize,
+
+        /// The file to write the 2^log_2_capacity + 1 G1 points to
+        #[arg(long = "g1_out", required = true)]
+        g1_out: String,
+
+        /// The file to write the 2^log_2_capacity G2 points to
+        #[arg(long = "g2_out", required = true)]
+        g2_out: String,
+
+        #[arg(short, long, value_enum, default_value_t = Format::Hex)]
+        format: Format,
+    },
+    /// Write the commitments to the Lagrange basis polynomials, and print the Constants.sol values
+    Lagrange {
+        /// The powers of tau (PTAU) file containing a phase 1 trusted setup output, a raw PPOT
+        /// challenge or response file, or a setup artifact written by `setup artifact`
+        #[arg(short, long, required = true)]
+        ptau: String,
+
+        /// The capacity of the accumulator expressed in log_2 (e.g. log_2(1024) = 10)
+        #[arg(short, long, required = true, value_parser=log_2_capacity_range)]
+        log_2_capacity: usize,
+
+        /// The file to write the G1 Lagrange commitments to, one per line
+        #[arg(short, long, required = true)]
+        out: String,
+
+        /// If specified, also write the G2 Lagrange commitments to this file, one per line
+        #[arg(long = "g2_out", required = false)]
+        g2_out: Option<String>,
+
+        /// If specified, compute the commitments out of core, with memory-mapped scratch files in
+        /// this directory, rather than in memory. Use this for capacities of 2^24 or more
+        #[arg(long = "scratch_dir", required = false)]
+        scratch_dir: Option<String>,
+
+        /// The largest number of points to hold in memory when computing out of core. It must be
+        /// a power of two, and at least the square root of 2^log_2_capacity
+        #[arg(long = "chunk_size", default_value_t = 1 << 20)]
+        chunk_size: usize,
+    },
+    /// Print the root of the Lagrange basis commitment tree
+    Tree {
+        /// The powers of tau (PTAU) file containing a phase 1 trusted setup output, a raw PPOT
+        /// challenge or response file, or a setup artifact written by `setup artifact`
+        #[arg(short, long, required = true)]
+        ptau: String,
+
+        /// The capacity of the accumulator expressed in log_2 (e.g. log_2(1024) = 10)
+        #[arg(short, long, required = true, value_parser=log_2_capacity_range)]
+        log_2_capacity: usize,
+
+        /// If specified, compute the commitments out of core, with memory-mapped scratch files in
+        /// this directory, rather than in memory. Use this for capacities of 2^24 or more
+        #[arg(long = "scratch_dir", required = false)]
+        scratch_dir: Option<String>,
+
+        /// The largest number of points to hold in memory when computing out of core. It must be
+        /// a power of two, and at least the square root of 2^log_2_capacity
+        #[arg(long = "chunk_size", default_value_t = 1 << 20)]
+        chunk_size: usize,
+    },
+    /// Check that a file of Lagrange commitments, such as one which `setup lagrange` wrote, holds
+    /// the commitments of the powers of tau
+    VerifyLagrange {
+        /// The powers of tau (PTAU) file containing a phase 1 trusted setup output, or a raw
+        /// PPOT challenge or response file
+        #[arg(short, long, required = true)]
+        ptau: String,
+
+        /// The capacity of the accumulator expressed in log_2 (e.g. log_2(1024) = 10)
+        #[arg(short, long, required = true, value_parser=log_2_capacity_range)]
+        log_2_capacity: usize,
+
+        /// The file of G1 Lagrange commitments, one per line
+        #[arg(short = 'c', long = "lagrange_comms", required = true)]
+        lagrange_comms: String,
+    },
+    /// Check that the G1 and G2 powers of tau are consistent powers of the same tau
+    Verify {
+        /// The powers of tau (PTAU) file containing a phase 1 trusted setup output, or a raw
+        /// PPOT challenge or response file
+        #[arg(short, long, required = true)]
+        ptau: String,
+
+        /// The capacity of the accumulator expressed in log_2 (e.g. log_2(1024) = 10)
+        #[arg(short, long, required = true, value_parser=log_2_capacity_range)]
+        log_2_capacity: usize,
+    },
+    /// Check the powers of tau, and write the SRS, Lagrange commitments, Lagrange tree root and
+    /// verifying key to a binary artifact
+    Artifact {
+        /// The powers of tau (PTAU) file containing a phase 1 trusted setup output, or a raw
+        /// PPOT challenge or response file
+        #[arg(short, long, required = true)]
+        ptau: String,
+
+        /// The capacity of the accumulator expressed in log_2 (e.g. log_2(1024) = 10)
+        #[arg(short, long, required = true, value_parser=log_2_capacity_range)]
+        log_2_capacity: usize,
+
+        /// The artifact file to write
+        #[arg(short, long, required = true)]
+        out: String,
+    },
+    /// Write a proving key with only the powers of tau which a prover needs once its W1 and W2
+    /// values are supplied, e.g. by a precompute server
+    Trim {
+        /// The powers of tau (PTAU) file containing a phase 1 trusted setup output, or a raw
+        /// PPOT challenge or response file
+        #[arg(short, long, required = true)]
+        ptau: String,
+
+        /// The file to write the serialised proving key to
+        #[arg(short, long, required = true)]
+        out: String,
+    },
+    /// Re-randomise the powers of tau with a local secret, and write them as a raw PPOT challenge
+    /// file with a proof of knowledge of the secret
+    Contribute {
+        /// The powers of tau (PTAU) file containing a phase 1 trusted setup output, or a raw
+        /// PPOT challenge or response file
+        #[arg(short, long, required = true)]
+        ptau: String,
+
+        /// The log_2 of the number of G2 powers to re-randomise, which is the largest capacity
+        /// which the output supports (e.g. log_2(1024) = 10)
+        #[arg(short, long, required = true, value_parser=log_2_capacity_range)]
+        log_2_capacity: usize,
+
+        /// The challenge file to write
+        #[arg(short, long, required = true)]
+        out: String,
+
+        /// The file to write the proof of knowledge to
+        #[arg(long = "proof_out", required = true)]
+        proof_out: String,
+    },
+    /// Check that a contribution was derived from the previous powers of tau by someone who
+    /// knows its secret
+    VerifyContribution {
+        /// The powers of tau which the contribution was made to
+        #[arg(short, long, required = true)]
+        ptau: String,
+
+        /// The challenge file which `setup contribute` wrote
+        #[arg(short, long, required = true)]
+        new: String,
+
+        /// The proof of knowledge which `setup contribute` wrote
+        #[arg(long, required = true)]
+        proof: String,
+
+        /// The log_2_capacity which the contribution was made with
+        #[arg(short, long, required = true, value_parser=log_2_capacity_range)]
+        log_2_capacity: usize,
+    },
+}
 
-/*
- * Usage: cargo run setup <table_size> <ptau_filename> <lagrange_comms_out>
- * Reads the SRS G1 and G2 points from <ptau_filename>, and writes the commitments to the Lagrange
- * basis polynomials to <lagrange_comms_out>.
- */
 fn main() {
-    let args: Vec<String> = env::args().collect();
-    if args.len() <= 3 {
-        println!("Usage: cargo run setup <table_size> <ptau_filename> <lagrange_comms_out>");
+    let args = Cli::parse();
+    let result = match args.command {
+        Commands::Inspect { ptau, previous } => inspect(&ptau, previous),
+        Commands::Export {
+            ptau,
+            log_2_capacity,
+            g1_out,
+            g2_out,
+            format,
+        } => export(&ptau, log_2_capacity, &g1_out, &g2_out, format),
+        Commands::Lagrange {
+            ptau,
+            log_2_capacity,
+            out,
+            g2_out,
+            scratch_dir: None,
+            ..
+        } => lagrange(&ptau, log_2_capacity, &out, g2_out),
+        Commands::Lagrange {
+            ptau,
+            log_2_capacity,
+            out,
+            g2_out,
+            scratch_dir: Some(scratch_dir),
+            chunk_size,
+        } => lagrange_out_of_core(
+            &ptau,
+            log_2_capacity,
+            &out,
+            g2_out,
+            &scratch_dir,
+            chunk_size,
+        ),
+        Commands::Tree {
+            ptau,
+            log_2_capacity,
+            scratch_dir: None,
+            ..
+        } => tree(&ptau, log_2_capacity),
+        Commands::Tree {
+            ptau,
+            log_2_capacity,
+            scratch_dir: Some(scratch_dir),
+            chunk_size,
+        } => tree_out_of_core(&ptau, log_2_capacity, &scratch_dir, chunk_size),
+        Commands::VerifyLagrange {
+            ptau,
+            log_2_capacity,
+            lagrange_comms,
+        } => verify_lagrange(&ptau, log_2_capacity, &lagrange_comms),
+        Commands::Verify {
+            ptau,
+            log_2_capacity,
+        } => verify_setup(log_2_capacity, &ptau).map(|_| println!("The SRS in {} is valid.", ptau)),
+        Commands::Artifact {
+            ptau,
+            log_2_capacity,
+            out,
+        } => artifact(&ptau, log_2_capacity, &out),
+        Commands::Trim { ptau, out } => trim(&ptau, &out),
+        Commands::Contribute {
+            ptau,
+            log_2_capacity,
+            out,
+            proof_out,
+        } => contribute(&ptau, log_2_capacity, &out, &proof_out),
+        Commands::VerifyContribution {
+            ptau,
+            new,
+            proof,
+            log_2_capacity,
+        } => verify_contribution(&ptau, &new, &proof, log_2_capacity),
+    };
+
+    if let Err(e) = result {
+        println!("Error: {:?}", e);
+        process::exit(1);
+    }
+}
+
+fn inspect(ptau: &str, previous: Option<String>) -> Result<(), Error> {
+    if !is_ptau(ptau) {
+        return inspect_ppot(ptau, previous);
+    }
+
+    let header = PtauHeader::read(ptau)?;
+    println!("Version: {}", header.version);
+    println!("Field element size: {} bytes", header.n8);
+    println!("BN254: {}", header.is_bn254());
+    println!("Power: {}", header.power);
+    println!("Ceremony power: {}", header.ceremony_power);
+    println!("G1 powers: {}", header.num_g1_points());
+    println!("G2 powers: {}", header.num_g2_points());
+    println!(
+        "Maximum capacity: 2^{}",
+        header.max_log_2_table_size().min(28)
+    );
+    println!("Sections:");
+    for section in header.sections.iter() {
         println!(
-            "Reads the SRS G1 and G2 points from <ptau_filename>, and writes the commitments to the Lagrange basis polynomials to <lagrange_comms_out>."
+            "\t{}: {} bytes at offset {}",
+            section.id, section.size, section.offset
         );
-        return;
     }
-    assert!(args.len() > 3);
-    let log_2_table_size = &args[args.len() - 3];
-    let ptau_filename = &args[args.len() - 2];
-    let lagrange_comms_out = &args[args.len() - 1];
+    println!("keccak256: 0x{}", hex::encode(hash_file(ptau)?));
+    Ok(())
+}
 
-    let log_2_table_size: usize = log_2_table_size.parse().unwrap();
+fn inspect_ppot(path: &str, previous: Option<String>) -> Result<(), Error> {
+    let file = PpotFile::open(path)?;
+    let kind = if file.is_response() {
+        "response (compressed)"
+    } else {
+        "challenge (uncompressed)"
+    };
+    println!("Raw PPOT {}", kind);
+    println!("Power: {}", file.power());
+    println!("G1 powers: {}", file.num_g1_points());
+    println!("G2 powers: {}", file.num_g2_points());
+    println!("Maximum capacity: 2^{}", file.power().min(28));
+    println!("Previous hash: 0x{}", hex::encode(file.previous_hash()?));
+    println!("BLAKE2b: 0x{}", hex::encode(blake2b_hash_file(path)?));
+    if let Some(previous) = previous {
+        file.check_previous(&previous)?;
+        println!("{} was made from {}.", path, previous);
+    }
+    Ok(())
+}
 
-    let (pk, lagrange_comms) = setup(log_2_table_size, ptau_filename.as_str());
-    let srs_g1 = pk.srs_g1;
-    let srs_g2 = pk.srs_g2;
+fn export(
+    ptau: &str,
+    log_2_capacity: usize,
+    g1_out: &str,
+    g2_out: &str,
+    format: Format,
+) -> Result<(), Error> {
+    let table_size = 1usize << log_2_capacity;
+    let (srs_g1, srs_g2) = read_srs(ptau, table_size + 1, table_size)?;
 
-    let file = File::create(lagrange_comms_out).unwrap();
-    let mut file = LineWriter::new(file);
+    match format {
+        Format::Hex => {
+            write_lines(g1_out, srs_g1.iter().map(g1_to_str))?;
+            write_lines(g2_out, srs_g2.iter().map(g2_to_g2_str))?;
+        }
+        Format::Binary => {
+            write_binary(g1_out, &srs_g1)?;
+            write_binary(g2_out, &srs_g2)?;
+        }
+    }
+    println!(
+        "Wrote {} G1 points to {} and {} G2 points to {}",
+        srs_g1.len(),
+        g1_out,
+        srs_g2.len(),
+        g2_out
+    );
+    Ok(())
+}
+
+fn lagrange(
+    ptau: &str,
+    log_2_capacity: usize,
+    out: &str,
+    g2_out: Option<String>,
+) -> Result<(), Error> {
+    let (pk, lagrange_comms) = load_setup(log_2_capacity, ptau)?;
+    verify_srs(&pk.srs_g1, &pk.srs_g2, &mut rand::thread_rng())?;
 
-    for comm in lagrange_comms {
-        let line = format!("{}{}", f_to_hex_le(comm.x), f_to_hex_le(comm.y));
-        file.write_all(line.as_bytes()).unwrap();
-        file.write_all(b"\n").unwrap();
+    write_lines(out, lagrange_comms.iter().map(g1_to_str))?;
+
+    let table_size: usize = 1 << log_2_capacity;
+    if let Some(g2_out) = g2_out {
+        let lagrange_comms_g2 =
+            compute_lagrange_basis_commitments(pk.srs_g2[..table_size].to_vec());
+        write_lines(&g2_out, lagrange_comms_g2.iter().map(g2_to_g2_str))?;
     }
-    file.flush().unwrap();
 
-    let table_size: usize = 2u64.pow(log_2_table_size as u32) as usize;
+    print_constants(&pk.srs_g1[table_size], &pk.srs_g2[1]);
+    Ok(())
+}
+
+/*
+ * As lagrange(), but with the SRS and commitments in memory-mapped scratch files, so that only
+ * chunk_size points are in memory at a time. The SRS is not checked with verify_srs(), which
+ * needs it in memory; run `setup verify-lagrange` on the output instead.
+ */
+fn lagrange_out_of_core(
+    ptau: &str,
+    log_2_capacity: usize,
+    out: &str,
+    g2_out: Option<String>,
+    scratch_dir: &str,
+    chunk_size: usize,
+) -> Result<(), Error> {
+    let table_size: usize = 1 << log_2_capacity;
+    let ooc = out_of_core(scratch_dir, chunk_size, table_size)?;
+    let (srs_g1, srs_g2) = ooc.read_srs(ptau, table_size + 1, table_size)?;
+
+    let lagrange_comms = ooc.lagrange_comms(&srs_g1, table_size, "lagrange_comms.bin")?;
+    write_scratch_lines(out, &lagrange_comms, chunk_size, g1_to_str)?;
+
+    if let Some(g2_out) = g2_out {
+        let lagrange_comms_g2 = ooc.lagrange_comms(&srs_g2, table_size, "lagrange_comms_g2.bin")?;
+        write_scratch_lines(&g2_out, &lagrange_comms_g2, chunk_size, g2_to_g2_str)?;
+    }
+
+    print_constants(&srs_g1.get(table_size)?, &srs_g2.get(1)?);
+    Ok(())
+}
+
+fn print_constants(srs_g1_t: &G1Affine, srs_g2_1: &G2Affine) {
     println!("Update Constants.sol with these values:");
-    println!(
-        "uint256 constant SRS_G1_T_X = 0x{};",
-        f_to_hex(srs_g1[table_size].x)
-    );
-    println!(
-        "uint256 constant SRS_G1_T_Y = 0x{};",
-        f_to_hex(srs_g1[table_size].y)
-    );
+    println!("uint256 constant SRS_G1_T_X = 0x{};", f_to_hex(srs_g1_t.x));
+    println!("uint256 constant SRS_G1_T_Y = 0x{};", f_to_hex(srs_g1_t.y));
     println!(
         "uint256 constant SRS_G2_1_X_0 = 0x{};",
-        f_to_hex(srs_g2[1].x.c1)
+        f_to_hex(srs_g2_1.x.c1)
     );
     println!(
         "uint256 constant SRS_G2_1_X_1 = 0x{};",
-        f_to_hex(srs_g2[1].x.c0)
+        f_to_hex(srs_g2_1.x.c0)
     );
     println!(
         "uint256 constant SRS_G2_1_Y_0 = 0x{};",
-        f_to_hex(srs_g2[1].y.c1)
+        f_to_hex(srs_g2_1.y.c1)
     );
     println!(
         "uint256 constant SRS_G2_1_Y_1 = 0x{};",
-        f_to_hex(srs_g2[1].y.c0)
+        f_to_hex(srs_g2_1.y.c0)
+    );
+}
+
+fn tree(ptau: &str, log_2_capacity: usize) -> Result<(), Error> {
+    let (_, lagrange_comms) = load_setup(log_2_capacity, ptau)?;
+    let root = compute_lagrange_tree::<Bn254>(&lagrange_comms).root();
+    println!("Lagrange tree root: 0x{}", hex::encode(root));
+    Ok(())
+}
+
+fn tree_out_of_core(
+    ptau: &str,
+    log_2_capacity: usize,
+    scratch_dir: &str,
+    chunk_size: usize,
+) -> Result<(), Error> {
+    let table_size: usize = 1 << log_2_capacity;
+    let ooc = out_of_core(scratch_dir, chunk_size, table_size)?;
+    let (srs_g1, _) = ooc.read_srs(ptau, table_size, 1)?;
+    let lagrange_comms = ooc.lagrange_comms(&srs_g1, table_size, "lagrange_comms.bin")?;
+    let root = ooc.lagrange_root::<Bn254>(&lagrange_comms)?;
+    println!("Lagrange tree root: 0x{}", hex::encode(root));
+    Ok(())
+}
+
+fn verify_lagrange(ptau: &str, log_2_capacity: usize, lagrange_comms: &str) -> Result<(), Error> {
+    let table_size = 1usize << log_2_capacity;
+    let (srs_g1, _) = read_srs(ptau, table_size + 1, table_size)?;
+    let comms = try_load_lagrange_comms_from_file(lagrange_comms)?;
+    verify_lagrange_comms(&srs_g1, &comms, table_size, &mut rand::thread_rng())?;
+
+    let root = compute_lagrange_tree::<Bn254>(&comms).root();
+    println!(
+        "The {} Lagrange commitments in {} match the SRS in {}.",
+        comms.len(),
+        lagrange_comms,
+        ptau
+    );
+    println!("Lagrange tree root: 0x{}", hex::encode(root));
+    Ok(())
+}
+
+fn artifact(ptau: &str, log_2_capacity: usize, out: &str) -> Result<(), Error> {
+    let (pk, lagrange_comms) = setup(log_2_capacity, ptau)?;
+    verify_srs(&pk.srs_g1, &pk.srs_g2, &mut rand::thread_rng())?;
+    SetupArtifact::write(out, &pk, &lagrange_comms, log_2_capacity)?;
+
+    let artifact = SetupArtifact::open(out)?;
+    println!("Wrote {}", out);
+    println!(
+        "Lagrange tree root: 0x{}",
+        hex::encode(artifact.lagrange_root())
+    );
+    println!("Content hash: 0x{}", hex::encode(artifact.content_hash()));
+    Ok(())
+}
+
+fn trim(ptau: &str, out: &str) -> Result<(), Error> {
+    let config = TrimConfig::default();
+    let (srs_g1, srs_g2) = read_srs(ptau, config.num_g1_points, config.num_g2_points)?;
+    verify_srs(&srs_g1, &srs_g2, &mut rand::thread_rng())?;
+    let pk = ProvingKey::<Bn254>::new(srs_g1, srs_g2);
+
+    let file = File::create(out).map_err(io_error)?;
+    let mut file = BufWriter::new(file);
+    pk.serialize(&mut file)
+        .map_err(|e| Error::PtauIoError(e.to_string()))?;
+    file.flush().map_err(io_error)?;
+
+    println!(
+        "Wrote the {} G1 and {} G2 points of the trimmed proving key to {}",
+        pk.srs_g1.len(),
+        pk.srs_g2.len(),
+        out
     );
+    Ok(())
+}
+
+fn contribute(ptau: &str, log_2_capacity: usize, out: &str, proof_out: &str) -> Result<(), Error> {
+    let n = 1usize << log_2_capacity;
+    let (srs_g1, srs_g2) = read_srs(ptau, 2 * n - 1, n)?;
+    verify_srs(&srs_g1, &srs_g2, &mut rand::thread_rng())?;
+
+    let (new_g1, new_g2, proof) = contribution::contribute(&srs_g1, &srs_g2, &mut OsRng)?;
+    write_challenge(out, &blake2b_hash_file(ptau)?, &new_g1, &new_g2)?;
+
+    let mut bytes = vec![];
+    proof
+        .serialize(&mut bytes)
+        .map_err(|e| Error::PpotIoError(e.to_string()))?;
+    std::fs::write(proof_out, bytes).map_err(io_error)?;
+
+    println!("Wrote {} and {}", out, proof_out);
+    println!("BLAKE2b: 0x{}", hex::encode(blake2b_hash_file(out)?));
+    Ok(())
+}
+
+fn verify_contribution(
+    ptau: &str,
+    new: &str,
+    proof: &str,
+    log_2_capacity: usize,
+) -> Result<(), Error> {
+    let n = 1usize << log_2_capacity;
+    let (srs_g1, srs_g2) = read_srs(ptau, 2 * n - 1, n)?;
+    let (new_g1, new_g2) = read_srs(new, 2 * n - 1, n)?;
+    PpotFile::open(new)?.check_previous(ptau)?;
+
+    let bytes = std::fs::read(proof).map_err(io_error)?;
+    let proof = ContributionProof::deserialize(bytes.as_slice())
+        .map_err(|_| Error::InvalidContributionProof)?;
+    contribution::verify_contribution(
+        &srs_g1,
+        &srs_g2,
+        &new_g1,
+        &new_g2,
+        &proof,
+        &mut rand::thread_rng(),
+    )?;
+
+    println!("{} is a valid contribution to {}.", new, ptau);
+    Ok(())
+}
+
+// The format which Lagrange commitments have always been written in, and try_g1_str_to_g1() parses
+fn g1_to_str(p: &G1Affine) -> String {
+    format!("{}{}", f_to_hex_le(p.x), f_to_hex_le(p.y))
+}
+
+fn write_lines<I: Iterator<Item = String>>(path: &str, lines: I) -> Result<(), Error> {
+    let file = File::create(path).map_err(io_error)?;
+    let mut file = LineWriter::new(file);
+    for line in lines {
+        file.write_all(line.as_bytes()).map_err(io_error)?;
+        file.write_all(b"\n").map_err(io_error)?;
+    }
+    file.flush().map_err(io_error)
+}
+
+// Write the points of a scratch file, one per line, a chunk at a time
+fn write_scratch_lines<C: AffineCurve, F: Fn(&C) -> String>(
+    path: &str,
+    points: &ScratchFile<C>,
+    chunk_size: usize,
+    to_str: F,
+) -> Result<(), Error> {
+    let file = File::create(path).map_err(io_error)?;
+    let mut file = BufWriter::new(file);
+    for start in (0..points.len()).step_by(chunk_size) {
+        for p in points.read(start, chunk_size.min(points.len() - start))? {
+            file.write_all(to_str(&p).as_bytes()).map_err(io_error)?;
+            file.write_all(b"\n").map_err(io_error)?;
+        }
+    }
+    file.flush().map_err(io_error)
+}
+
+fn out_of_core(
+    scratch_dir: &str,
+    chunk_size: usize,
+    table_size: usize,
+) -> Result<OutOfCore, Error> {
+    if !chunk_size.is_power_of_two() || chunk_size * chunk_size < table_size {
+        eprintln!("The chunk size must be a power of two, and at least the square root of the table size.");
+        process::exit(1);
+    }
+    OutOfCore::new(scratch_dir, chunk_size)
+}
+
+fn write_binary<P: CanonicalSerialize>(path: &str, points: &[P]) -> Result<(), Error> {
+    let file = File::create(path).map_err(io_error)?;
+    let mut file = BufWriter::new(file);
+    for p in points {
+        p.serialize_uncompressed(&mut file)
+            .map_err(|e| Error::PtauIoError(e.to_string()))?;
+    }
+    file.flush().map_err(io_error)
+}
+
+fn io_error(e: std::io::Error) -> Error {
+    Error::PtauIoError(e.to_string())
 }
@@ -1,25 +1,238 @@
 use crate::accumulator::commit_to_lagrange_bases;
+use crate::error::Error;
 use crate::prover::ProvingKey;
-use ark_bn254::{Bn254, Fq, Fq2, G1Affine, G2Affine};
-use ark_ff::bytes::FromBytes;
+use crate::setup::artifact::SetupArtifact;
+use crate::setup::ppot::PpotFile;
+use ark_bn254::{Bn254, Fq, Fq2, Fr, G1Affine, G1Projective, G2Affine};
+use ark_ec::{msm::VariableBaseMSM, AffineCurve, PairingEngine, ProjectiveCurve};
+use ark_ff::{
+    bytes::{FromBytes, ToBytes},
+    FpParameters, PrimeField, UniformRand, Zero,
+};
+use ark_poly::{EvaluationDomain, GeneralEvaluationDomain};
+use rand::RngCore;
 use std::fs::File;
 use std::io::{self, BufRead};
 use std::path::Path;
 
+pub mod artifact;
+pub mod contribution;
+pub mod ppot;
+pub mod ptau;
 #[cfg(test)]
 pub mod tests;
 
-pub fn setup(log_2_table_size: usize, ptau_filepath: &str) -> (ProvingKey<Bn254>, Vec<G1Affine>) {
-    assert!((10..28).contains(&log_2_table_size));
+pub fn setup(
+    log_2_table_size: usize,
+    ptau_filepath: &str,
+) -> Result<(ProvingKey<Bn254>, Vec<G1Affine>), Error> {
+    check_log_2_table_size(log_2_table_size)?;
     let table_size: usize = 2u64.pow(log_2_table_size as u32) as usize;
     let num_g1_points = table_size + 1;
     let num_g2_points = table_size;
 
-    let (srs_g1, srs_g2) =
-        ppot_rs::ptau::read(ptau_filepath, num_g1_points, num_g2_points).unwrap();
+    let (srs_g1, srs_g2) = read_srs(ptau_filepath, num_g1_points, num_g2_points)?;
 
     let lagrange_comms = commit_to_lagrange_bases::<Bn254>(table_size, &srs_g1);
-    (ProvingKey::<Bn254> { srs_g1, srs_g2 }, lagrange_comms)
+    Ok((ProvingKey::<Bn254>::new(srs_g1, srs_g2), lagrange_comms))
+}
+
+// The table sizes which setup(), verify_setup() and load_setup() support
+fn check_log_2_table_size(log_2_table_size: usize) -> Result<(), Error> {
+    if !(10..=28).contains(&log_2_table_size) {
+        return Err(Error::UnsupportedTableSize(log_2_table_size));
+    }
+    Ok(())
+}
+
+/*
+ * Read the first G1 and G2 powers of tau from either a .ptau file or a raw PPOT challenge or
+ * response file (see ppot::PpotFile).
+ * @param path: The .ptau, challenge or response file.
+ * @param num_g1_points: The number of G1 points to read.
+ * @param num_g2_points: The number of G2 points to read.
+ */
+pub fn read_srs(
+    path: &str,
+    num_g1_points: usize,
+    num_g2_points: usize,
+) -> Result<(Vec<G1Affine>, Vec<G2Affine>), Error> {
+    if ptau::is_ptau(path) {
+        return ppot_rs::ptau::read(path, num_g1_points, num_g2_points)
+            .map_err(|e| Error::PtauIoError(e.to_string()));
+    }
+    PpotFile::open(path)?.read(num_g1_points, num_g2_points)
+}
+
+/*
+ * Check that the SRS points read from a ptau file are consistent powers of the same τ, i.e.
+ * srs_g1[i] = τ^i * g1 and srs_g2[i] = τ^i * g2, so that a corrupted or tampered file is rejected
+ * before it is used. Each point must be on the curve and in the prime-order subgroup, and the
+ * first two powers must be the generators and a nonzero point. The powers are checked with two
+ * randomised pairing checks:
+ *
+ * e(Σ r_i * srs_g1[i + 1], g2) = e(Σ r_i * srs_g1[i], srs_g2[1])
+ * e(srs_g1[1], Σ r_i * srs_g2[i]) = e(g1, Σ r_i * srs_g2[i + 1])
+ *
+ * @param rng: The source of the random r_i, which must not be known to whoever produced the SRS.
+ */
+pub fn verify_srs<R: RngCore>(
+    srs_g1: &[G1Affine],
+    srs_g2: &[G2Affine],
+    rng: &mut R,
+) -> Result<(), Error> {
+    if srs_g1.len() < 2 || srs_g2.len() < 2 {
+        return Err(Error::SrsTooShort);
+    }
+    if srs_g1[0] != G1Affine::prime_subgroup_generator()
+        || srs_g2[0] != G2Affine::prime_subgroup_generator()
+        || srs_g1[1].is_zero()
+        || srs_g2[1].is_zero()
+    {
+        return Err(Error::InvalidSrsGenerator);
+    }
+
+    // BN254 G1 has cofactor 1, so its points only need to be on the curve
+    if !srs_g1.iter().all(|p| p.is_on_curve()) || !srs_g2.iter().all(|p| p.is_on_curve()) {
+        return Err(Error::SrsPointNotOnCurve);
+    }
+    check_g2_subgroup(srs_g2, rng)?;
+
+    let r = random_scalars(srs_g1.len() - 1, rng);
+    let g1_lhs = VariableBaseMSM::multi_scalar_mul(&srs_g1[1..], &r);
+    let g1_rhs = VariableBaseMSM::multi_scalar_mul(&srs_g1[..srs_g1.len() - 1], &r);
+    if Bn254::pairing(g1_lhs, srs_g2[0]) != Bn254::pairing(g1_rhs, srs_g2[1]) {
+        return Err(Error::SrsG1PowersInconsistent);
+    }
+
+    let r = random_scalars(srs_g2.len() - 1, rng);
+    let g2_lhs = VariableBaseMSM::multi_scalar_mul(&srs_g2[..srs_g2.len() - 1], &r);
+    let g2_rhs = VariableBaseMSM::multi_scalar_mul(&srs_g2[1..], &r);
+    if Bn254::pairing(srs_g1[1], g2_lhs) != Bn254::pairing(srs_g1[0], g2_rhs) {
+        return Err(Error::SrsG2PowersInconsistent);
+    }
+
+    Ok(())
+}
+
+// The number of random linear combinations which check_g2_subgroup() checks. The smallest prime
+// factor of the cofactor of BN254 G2 is 10069, so it accepts a point outside the subgroup with
+// probability at most 10069 ^ -10 < 2 ^ -132.
+const G2_SUBGROUP_CHECK_REPETITIONS: usize = 10;
+
+/*
+ * Check that G2 points, which must be on the curve, are in the prime-order subgroup, with one
+ * multi-scalar multiplication and one scalar multiplication by the group order per repetition
+ * rather than one scalar multiplication by the group order per point. If a point has a component
+ * outside the subgroup, a random linear combination of the points with 64-bit scalars is in the
+ * subgroup with probability at most 1 / 10069, so the check is repeated
+ * G2_SUBGROUP_CHECK_REPETITIONS times.
+ * @param points: The G2 points to check.
+ * @param rng: The source of the random scalars, which must not be known to whoever produced the
+ *             points.
+ */
+pub fn check_g2_subgroup<R: RngCore>(points: &[G2Affine], rng: &mut R) -> Result<(), Error> {
+    let modulus = <Fr as PrimeField>::Params::MODULUS;
+    for _ in 0..G2_SUBGROUP_CHECK_REPETITIONS {
+        let r: Vec<_> = (0..points.len())
+            .map(|_| <Fr as PrimeField>::BigInt::from(rng.next_u64()))
+            .collect();
+        let combination = VariableBaseMSM::multi_scalar_mul(points, &r).into_affine();
+        if !combination.mul(modulus).is_zero() {
+            return Err(Error::SrsPointNotInSubgroup);
+        }
+    }
+    Ok(())
+}
+
+/*
+ * Check the SRS of the given table size in a ptau file with verify_srs().
+ * @param log_2_table_size: The log_2 of the table size.
+ * @param ptau_filepath: The ptau file, or a raw PPOT challenge or response file.
+ */
+pub fn verify_setup(log_2_table_size: usize, ptau_filepath: &str) -> Result<(), Error> {
+    check_log_2_table_size(log_2_table_size)?;
+    let table_size: usize = 2u64.pow(log_2_table_size as u32) as usize;
+
+    let (srs_g1, srs_g2) = read_srs(ptau_filepath, table_size + 1, table_size)?;
+    verify_srs(&srs_g1, &srs_g2, &mut rand::thread_rng())
+}
+
+/*
+ * Read the proving key and the Lagrange commitments from either a setup artifact (see
+ * artifact::SetupArtifact) or a ptau file.
+ * @param log_2_table_size: The log_2 of the table size, which must match that of an artifact.
+ * @param path: The artifact or ptau file.
+ */
+pub fn load_setup(
+    log_2_table_size: usize,
+    path: &str,
+) -> Result<(ProvingKey<Bn254>, Vec<G1Affine>), Error> {
+    check_log_2_table_size(log_2_table_size)?;
+    if !SetupArtifact::is_artifact(path) {
+        return setup(log_2_table_size, path);
+    }
+
+    let artifact = SetupArtifact::open(path)?;
+    if artifact.log_2_table_size() != log_2_table_size {
+        return Err(Error::InvalidArtifactTableSize);
+    }
+    Ok((artifact.proving_key()?, artifact.lagrange_comms()?))
+}
+
+/*
+ * Check that the commitments to the Lagrange basis polynomials, which the contract's Lagrange
+ * tree root is built from, are those of the given SRS, i.e. lagrange_comms[i] = L_i(τ) * g1:
+ *
+ * - There must be one per element of the domain, and each must be on the curve.
+ * - Σ L_i(X) = 1, so Σ lagrange_comms[i] must be g1.
+ * - With random r_i, Σ r_i * lagrange_comms[i] must be the commitment to Σ r_i * L_i(X), whose
+ *   coefficients are the inverse FFT of the r_i.
+ *
+ * @param srs_g1: The G1 powers of tau, of which the first table_size are used.
+ * @param lagrange_comms: The commitments to check.
+ * @param table_size: The size of the domain.
+ * @param rng: The source of the random r_i, which must not be known to whoever produced the
+ *             commitments.
+ */
+pub fn verify_lagrange_comms<R: RngCore>(
+    srs_g1: &[G1Affine],
+    lagrange_comms: &[G1Affine],
+    table_size: usize,
+    rng: &mut R,
+) -> Result<(), Error> {
+    assert!(table_size.is_power_of_two());
+    if srs_g1.len() < table_size {
+        return Err(Error::SrsTooShort);
+    }
+    if lagrange_comms.len() != table_size {
+        return Err(Error::LagrangeCommsWrongLength);
+    }
+    if !lagrange_comms.iter().all(|p| p.is_on_curve()) {
+        return Err(Error::G1PointNotOnCurve);
+    }
+
+    let sum: G1Projective = lagrange_comms.iter().map(|p| p.into_projective()).sum();
+    if sum.into_affine() != G1Affine::prime_subgroup_generator() {
+        return Err(Error::LagrangeCommsSumNotOne);
+    }
+
+    let domain = GeneralEvaluationDomain::<Fr>::new(table_size).unwrap();
+    let r: Vec<Fr> = (0..table_size).map(|_| Fr::rand(rng)).collect();
+    let coeffs: Vec<_> = domain.ifft(&r).iter().map(|c| c.into_repr()).collect();
+    let r: Vec<_> = r.iter().map(|r_i| r_i.into_repr()).collect();
+
+    let lhs = VariableBaseMSM::multi_scalar_mul(lagrange_comms, &r);
+    let rhs = VariableBaseMSM::multi_scalar_mul(&srs_g1[..table_size], &coeffs);
+    if lhs != rhs {
+        return Err(Error::LagrangeCommsInconsistent);
+    }
+
+    Ok(())
+}
+
+fn random_scalars<R: RngCore>(n: usize, rng: &mut R) -> Vec<<Fr as PrimeField>::BigInt> {
+    (0..n).map(|_| Fr::rand(rng).into_repr()).collect()
 }
 
 fn read_lines<P>(filename: P) -> io::Result<io::Lines<io::BufReader<File>>>
@@ -30,66 +243,85 @@ where
     Ok(io::BufReader::new(file).lines())
 }
 
-pub fn load_lagrange_comms_from_file(filename: &str) -> Vec<G1Affine> {
+/*
+ * Read a file of Lagrange commitments, one per line in the format which try_g1_str_to_g1()
+ * parses. Malformed lines are an error rather than skipped. Only empty lines are skipped.
+ */
+pub fn try_load_lagrange_comms_from_file(filename: &str) -> Result<Vec<G1Affine>, Error> {
+    let lines = read_lines(filename).map_err(|e| Error::LagrangeCommsIoError(e.to_string()))?;
     let mut lagrange_comms = vec![];
-    let lines = read_lines(filename).unwrap();
     for line in lines {
-        let val = line.unwrap();
-        if val.len() == 128 {
-            lagrange_comms.push(g1_str_to_g1(&val));
+        let line = line.map_err(|e| Error::LagrangeCommsIoError(e.to_string()))?;
+        if !line.trim().is_empty() {
+            lagrange_comms.push(try_g1_str_to_g1(&line)?);
         }
     }
-    lagrange_comms
+    Ok(lagrange_comms)
 }
 
-pub fn hex_to_fq(val: &str) -> Fq {
-    assert_eq!(val.len(), 64);
-    let bytes_vec = hex::decode(val).unwrap();
-    let bytes_slice: &[u8] = bytes_vec.as_slice();
+/*
+ * Parse a G1 point in the format which the setup binary writes Lagrange commitments in, i.e. the
+ * little-endian hex of x followed by that of y. BN254 G1 has cofactor 1, so every point on the
+ * curve is in the prime-order subgroup.
+ */
+pub fn try_g1_str_to_g1(val: &str) -> Result<G1Affine, Error> {
+    let val = val.trim();
+    if val.len() != 128 {
+        return Err(Error::InvalidG1String);
+    }
 
-    Fq::read(bytes_slice).unwrap()
-}
+    let bytes = hex::decode(val).map_err(|_| Error::InvalidG1String)?;
+    let x = Fq::read(&bytes[..32]).map_err(|_| Error::InvalidG1String)?;
+    let y = Fq::read(&bytes[32..]).map_err(|_| Error::InvalidG1String)?;
 
-pub fn g1_str_to_g1(v: &str) -> G1Affine {
-    assert_eq!(v.len(), 128);
-    let val: String = v.to_string();
-    let x;
-    let y;
-    unsafe {
-        let x_str = val.get_unchecked(0..64);
-        let y_str = val.get_unchecked(64..128);
-        x = hex_to_fq(x_str);
-        y = hex_to_fq(y_str);
-    }
     let g1 = G1Affine::new(x, y, false);
-    assert!(g1.is_on_curve());
-    g1
+    if !g1.is_on_curve() {
+        return Err(Error::G1PointNotOnCurve);
+    }
+    Ok(g1)
+}
+
+// Encode a G2 point in the format which try_g2_str_to_g2() parses
+pub fn g2_to_g2_str(g2: &G2Affine) -> String {
+    [g2.x.c0, g2.x.c1, g2.y.c0, g2.y.c1]
+        .iter()
+        .map(fq_to_hex)
+        .collect::<Vec<String>>()
+        .concat()
 }
 
-pub fn g2_str_to_g2(val: &str) -> G2Affine {
-    assert_eq!(val.len(), 256);
-    let val = val.to_string();
-    let x0_str;
-    let x1_str;
-    let y0_str;
-    let y1_str;
+fn fq_to_hex(val: &Fq) -> String {
+    let mut bytes = Vec::with_capacity(32);
+    val.write(&mut bytes).unwrap();
+    hex::encode(bytes)
+}
 
-    unsafe {
-        x0_str = val.get_unchecked(0..64);
-        x1_str = val.get_unchecked(64..128);
-        y0_str = val.get_unchecked(128..192);
-        y1_str = val.get_unchecked(192..256);
+/*
+ * Parse a G2 point from untrusted input, such as a precompute server's response, in the format
+ * which g2_to_g2_str() writes, i.e. the little-endian hex of x.c0, x.c1, y.c0 and y.c1. The point
+ * must be on the curve and in the prime-order subgroup.
+ */
+pub fn try_g2_str_to_g2(val: &str) -> Result<G2Affine, Error> {
+    let val = val.trim();
+    if val.len() != 256 {
+        return Err(Error::InvalidG2String);
     }
 
-    let x0 = hex_to_fq(x0_str);
-    let x1 = hex_to_fq(x1_str);
-    let y0 = hex_to_fq(y0_str);
-    let y1 = hex_to_fq(y1_str);
-
-    let x = Fq2::new(x0, x1);
-    let y = Fq2::new(y0, y1);
+    let bytes = hex::decode(val).map_err(|_| Error::InvalidG2String)?;
+    let mut coords = [Fq::zero(); 4];
+    for (coord, chunk) in coords.iter_mut().zip(bytes.chunks(32)) {
+        *coord = Fq::read(chunk).map_err(|_| Error::InvalidG2String)?;
+    }
 
+    let x = Fq2::new(coords[0], coords[1]);
+    let y = Fq2::new(coords[2], coords[3]);
     let g2 = G2Affine::new(x, y, false);
-    assert!(g2.is_on_curve());
-    g2
+    if !g2.is_on_curve() {
+        return Err(Error::G2PointNotOnCurve);
+    }
+    if !g2.mul(<Fr as PrimeField>::Params::MODULUS).is_zero() {
+        return Err(Error::G2PointNotInSubgroup);
+    }
+
+    Ok(g2)
 }
@@ -0,0 +1,384 @@
+/*
+   Reads the raw challenge and response files of the Perpetual Powers of Tau (PPOT) ceremony, as
+   written by the powersoftau tool of phase2-bn254, so that the latest contribution can be used
+   without converting it to a .ptau file. For a ceremony of power p, both files are laid out as:
+
+   | Size                     | Content                                                       |
+   |--------------------------|---------------------------------------------------------------|
+   | 64                       | BLAKE2b hash of the previous file                             |
+   | (2^(p + 1) - 1) * G1     | The G1 powers of tau                                          |
+   | 2^p * G2                 | The G2 powers of tau                                          |
+   | 2^p * G1                 | alpha times the G1 powers of tau                              |
+   | 2^p * G1                 | beta times the G1 powers of tau                               |
+   | G2                       | beta in G2                                                    |
+   | PUBLIC_KEY_SIZE          | Response files only: the contributor's public key             |
+
+   A challenge's hash is that of the response which it was made from, and a response's hash is
+   that of the challenge which it answers. Challenge points are uncompressed and response points
+   are compressed. Field elements are big-endian, and the top two bits of the first byte of a
+   point are flags: 0x40 marks the point at infinity, and in a compressed point, 0x80 marks the
+   larger of the two possible y-coordinates. Only the G1 and G2 powers of tau are read.
+*/
+use crate::error::Error;
+use ark_bn254::{Fq, Fq2, G1Affine, G2Affine};
+use ark_ff::{BigInteger256, FromBytes, PrimeField, ToBytes, Zero};
+use blake2::{Blake2b, Digest};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+pub const HASH_SIZE: usize = 64;
+// The six G1 and three G2 points of the public key, uncompressed
+pub const PUBLIC_KEY_SIZE: usize = 6 * 64 + 3 * 128;
+pub const MAX_POWER: u32 = 28;
+
+const INFINITY_FLAG: u8 = 1 << 6;
+const GREATEST_FLAG: u8 = 1 << 7;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PpotEncoding {
+    // Challenge files
+    Uncompressed,
+    // Response files
+    Compressed,
+}
+
+impl PpotEncoding {
+    pub fn g1_size(&self) -> usize {
+        match self {
+            PpotEncoding::Uncompressed => 64,
+            PpotEncoding::Compressed => 32,
+        }
+    }
+
+    pub fn g2_size(&self) -> usize {
+        2 * self.g1_size()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PpotFile {
+    path: String,
+    encoding: PpotEncoding,
+    power: u32,
+}
+
+impl PpotFile {
+    /*
+     * Open a challenge or response file. The files do not record the power of the ceremony, so
+     * it and the encoding are inferred from the size of the file.
+     */
+    pub fn open(path: &str) -> Result<Self, Error> {
+        let len = std::fs::metadata(path).map_err(io_error)?.len();
+        for power in 0..=MAX_POWER {
+            for encoding in [PpotEncoding::Uncompressed, PpotEncoding::Compressed] {
+                if file_size(encoding, power) == len {
+                    return Ok(Self {
+                        path: path.to_string(),
+                        encoding,
+                        power,
+                    });
+                }
+            }
+        }
+        Err(Error::InvalidPpotFileSize)
+    }
+
+    pub fn encoding(&self) -> PpotEncoding {
+        self.encoding
+    }
+
+    // Whether this is a response file, which ends with the contributor's public key
+    pub fn is_response(&self) -> bool {
+        self.encoding == PpotEncoding::Compressed
+    }
+
+    pub fn power(&self) -> u32 {
+        self.power
+    }
+
+    pub fn num_g1_points(&self) -> usize {
+        (1 << (self.power + 1)) - 1
+    }
+
+    pub fn num_g2_points(&self) -> usize {
+        1 << self.power
+    }
+
+    // The BLAKE2b hash of the file which this file was made from
+    pub fn previous_hash(&self) -> Result<[u8; HASH_SIZE], Error> {
+        let mut file = File::open(&self.path).map_err(io_error)?;
+        let mut hash = [0u8; HASH_SIZE];
+        file.read_exact(&mut hash).map_err(io_error)?;
+        Ok(hash)
+    }
+
+    // Check that this file was made from the given challenge or response file
+    pub fn check_previous(&self, previous_path: &str) -> Result<(), Error> {
+        if self.previous_hash()? != blake2b_hash_file(previous_path)? {
+            return Err(Error::PpotHashMismatch);
+        }
+        Ok(())
+    }
+
+    /*
+     * Read the first G1 and G2 powers of tau. Each point must be on the curve. Use
+     * setup::verify_srs() to check that they are consistent powers of the same tau.
+     * @param num_g1_points: The number of G1 points to read.
+     * @param num_g2_points: The number of G2 points to read.
+     */
+    pub fn read(
+        &self,
+        num_g1_points: usize,
+        num_g2_points: usize,
+    ) -> Result<(Vec<G1Affine>, Vec<G2Affine>), Error> {
+        Ok((
+            self.read_g1_range(0, num_g1_points)?,
+            self.read_g2_range(0, num_g2_points)?,
+        ))
+    }
+
+    // Read count G1 powers of tau, starting from the start-th
+    pub fn read_g1_range(&self, start: usize, count: usize) -> Result<Vec<G1Affine>, Error> {
+        if start + count > self.num_g1_points() {
+            return Err(Error::PpotPowerTooSmall);
+        }
+        let g1_size = self.encoding.g1_size();
+        let offset = HASH_SIZE + start * g1_size;
+        self.read_bytes(offset, count * g1_size)?
+            .chunks(g1_size)
+            .map(|bytes| decode_g1(bytes, self.encoding))
+            .collect()
+    }
+
+    // Read count G2 powers of tau, starting from the start-th
+    pub fn read_g2_range(&self, start: usize, count: usize) -> Result<Vec<G2Affine>, Error> {
+        if start + count > self.num_g2_points() {
+            return Err(Error::PpotPowerTooSmall);
+        }
+        let g2_size = self.encoding.g2_size();
+        let offset = HASH_SIZE + self.num_g1_points() * self.encoding.g1_size() + start * g2_size;
+        self.read_bytes(offset, count * g2_size)?
+            .chunks(g2_size)
+            .map(|bytes| decode_g2(bytes, self.encoding))
+            .collect()
+    }
+
+    fn read_bytes(&self, offset: usize, len: usize) -> Result<Vec<u8>, Error> {
+        let mut file = BufReader::new(File::open(&self.path).map_err(io_error)?);
+        file.seek(SeekFrom::Start(offset as u64))
+            .map_err(io_error)?;
+        let mut bytes = vec![0u8; len];
+        file.read_exact(&mut bytes).map_err(io_error)?;
+        Ok(bytes)
+    }
+}
+
+/*
+ * Write G1 and G2 powers of tau as a challenge file, which setup::setup() reads like any other.
+ * Semacaulk does not use the alpha and beta points, so the point at infinity is written in their
+ * place, and the file cannot be used for a Groth16 phase 2.
+ * @param previous_hash: The BLAKE2b hash of the file which the powers were derived from.
+ * @param srs_g1: The 2^(p + 1) - 1 G1 powers of tau.
+ * @param srs_g2: The 2^p G2 powers of tau. Other lengths return Error::InvalidPpotFileSize.
+ */
+pub fn write_challenge(
+    path: &str,
+    previous_hash: &[u8; HASH_SIZE],
+    srs_g1: &[G1Affine],
+    srs_g2: &[G2Affine],
+) -> Result<(), Error> {
+    // Any other lengths would not make a file of a PPOT power
+    let n = srs_g2.len();
+    if !n.is_power_of_two() || srs_g1.len() != 2 * n - 1 {
+        return Err(Error::InvalidPpotFileSize);
+    }
+    let encoding = PpotEncoding::Uncompressed;
+
+    let mut file = BufWriter::new(File::create(path).map_err(io_error)?);
+    file.write_all(previous_hash).map_err(io_error)?;
+    for p in srs_g1.iter() {
+        file.write_all(&encode_g1(p, encoding)).map_err(io_error)?;
+    }
+    for p in srs_g2.iter() {
+        file.write_all(&encode_g2(p, encoding)).map_err(io_error)?;
+    }
+    let zero_g1 = encode_g1(&G1Affine::zero(), encoding);
+    for _ in 0..2 * n {
+        file.write_all(&zero_g1).map_err(io_error)?;
+    }
+    file.write_all(&encode_g2(&G2Affine::zero(), encoding))
+        .map_err(io_error)?;
+    file.flush().map_err(io_error)
+}
+
+// The size of a challenge (uncompressed) or response (compressed) file of the given power
+pub fn file_size(encoding: PpotEncoding, power: u32) -> u64 {
+    let n = 1u64 << power;
+    let g1_size = encoding.g1_size() as u64;
+    let g2_size = encoding.g2_size() as u64;
+    let public_key_size = match encoding {
+        PpotEncoding::Uncompressed => 0,
+        PpotEncoding::Compressed => PUBLIC_KEY_SIZE as u64,
+    };
+    HASH_SIZE as u64
+        + (2 * n - 1) * g1_size
+        + n * g2_size
+        + 2 * n * g1_size
+        + g2_size
+        + public_key_size
+}
+
+// The BLAKE2b hash of a file, which the next file of the ceremony starts with
+pub fn blake2b_hash_file<P: AsRef<Path>>(path: P) -> Result<[u8; HASH_SIZE], Error> {
+    let mut file = BufReader::new(File::open(path).map_err(io_error)?);
+    let mut hasher = Blake2b::new();
+    let mut buf = vec![0u8; 1 << 20];
+    loop {
+        let n = file.read(&mut buf).map_err(io_error)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    let mut hash = [0u8; HASH_SIZE];
+    hash.copy_from_slice(&hasher.finalize());
+    Ok(hash)
+}
+
+pub fn encode_g1(p: &G1Affine, encoding: PpotEncoding) -> Vec<u8> {
+    let mut bytes = vec![0u8; encoding.g1_size()];
+    if p.is_zero() {
+        bytes[0] |= INFINITY_FLAG;
+        return bytes;
+    }
+    bytes[..32].copy_from_slice(&fq_to_be_bytes(&p.x));
+    match encoding {
+        PpotEncoding::Uncompressed => bytes[32..].copy_from_slice(&fq_to_be_bytes(&p.y)),
+        PpotEncoding::Compressed => {
+            if p.y > -p.y {
+                bytes[0] |= GREATEST_FLAG;
+            }
+        }
+    }
+    bytes
+}
+
+// Fq2 elements are written as c1 followed by c0
+pub fn encode_g2(p: &G2Affine, encoding: PpotEncoding) -> Vec<u8> {
+    let mut bytes = vec![0u8; encoding.g2_size()];
+    if p.is_zero() {
+        bytes[0] |= INFINITY_FLAG;
+        return bytes;
+    }
+    bytes[..64].copy_from_slice(&fq2_to_be_bytes(&p.x));
+    match encoding {
+        PpotEncoding::Uncompressed => bytes[64..].copy_from_slice(&fq2_to_be_bytes(&p.y)),
+        PpotEncoding::Compressed => {
+            if p.y > -p.y {
+                bytes[0] |= GREATEST_FLAG;
+            }
+        }
+    }
+    bytes
+}
+
+pub fn decode_g1(bytes: &[u8], encoding: PpotEncoding) -> Result<G1Affine, Error> {
+    if bytes.len() != encoding.g1_size() {
+        return Err(Error::InvalidPpotPoint);
+    }
+    let (flags, bytes) = split_flags(bytes);
+    if flags & INFINITY_FLAG != 0 {
+        return infinity(&bytes, G1Affine::zero());
+    }
+
+    let x = fq_from_be_bytes(&bytes[..32])?;
+    let p = match encoding {
+        PpotEncoding::Uncompressed => {
+            if flags & GREATEST_FLAG != 0 {
+                return Err(Error::InvalidPpotPoint);
+            }
+            G1Affine::new(x, fq_from_be_bytes(&bytes[32..])?, false)
+        }
+        PpotEncoding::Compressed => G1Affine::get_point_from_x(x, flags & GREATEST_FLAG != 0)
+            .ok_or(Error::InvalidPpotPoint)?,
+    };
+    if !p.is_on_curve() {
+        return Err(Error::InvalidPpotPoint);
+    }
+    Ok(p)
+}
+
+pub fn decode_g2(bytes: &[u8], encoding: PpotEncoding) -> Result<G2Affine, Error> {
+    if bytes.len() != encoding.g2_size() {
+        return Err(Error::InvalidPpotPoint);
+    }
+    let (flags, bytes) = split_flags(bytes);
+    if flags & INFINITY_FLAG != 0 {
+        return infinity(&bytes, G2Affine::zero());
+    }
+
+    let x = fq2_from_be_bytes(&bytes[..64])?;
+    let p = match encoding {
+        PpotEncoding::Uncompressed => {
+            if flags & GREATEST_FLAG != 0 {
+                return Err(Error::InvalidPpotPoint);
+            }
+            G2Affine::new(x, fq2_from_be_bytes(&bytes[64..])?, false)
+        }
+        PpotEncoding::Compressed => G2Affine::get_point_from_x(x, flags & GREATEST_FLAG != 0)
+            .ok_or(Error::InvalidPpotPoint)?,
+    };
+    if !p.is_on_curve() {
+        return Err(Error::InvalidPpotPoint);
+    }
+    Ok(p)
+}
+
+// The flags of a point, and its bytes with the flags cleared
+fn split_flags(bytes: &[u8]) -> (u8, Vec<u8>) {
+    let mut bytes = bytes.to_vec();
+    let flags = bytes[0] & (INFINITY_FLAG | GREATEST_FLAG);
+    bytes[0] &= !(INFINITY_FLAG | GREATEST_FLAG);
+    (flags, bytes)
+}
+
+// The point at infinity must have no other bits set
+fn infinity<P>(bytes: &[u8], zero: P) -> Result<P, Error> {
+    if bytes.iter().any(|b| *b != 0) {
+        return Err(Error::InvalidPpotPoint);
+    }
+    Ok(zero)
+}
+
+fn fq_to_be_bytes(f: &Fq) -> [u8; 32] {
+    let mut bytes = [0u8; 32];
+    f.into_repr().write(&mut bytes[..]).unwrap();
+    bytes.reverse();
+    bytes
+}
+
+fn fq2_to_be_bytes(f: &Fq2) -> [u8; 64] {
+    let mut bytes = [0u8; 64];
+    bytes[..32].copy_from_slice(&fq_to_be_bytes(&f.c1));
+    bytes[32..].copy_from_slice(&fq_to_be_bytes(&f.c0));
+    bytes
+}
+
+// Rejects values which are not less than the modulus
+fn fq_from_be_bytes(bytes: &[u8]) -> Result<Fq, Error> {
+    let mut le = bytes.to_vec();
+    le.reverse();
+    let repr = BigInteger256::read(le.as_slice()).map_err(|_| Error::InvalidPpotPoint)?;
+    Fq::from_repr(repr).ok_or(Error::InvalidPpotPoint)
+}
+
+fn fq2_from_be_bytes(bytes: &[u8]) -> Result<Fq2, Error> {
+    let c1 = fq_from_be_bytes(&bytes[..32])?;
+    let c0 = fq_from_be_bytes(&bytes[32..])?;
+    Ok(Fq2::new(c0, c1))
+}
+
+fn io_error(e: std::io::Error) -> Error {
+    Error::PpotIoError(e.to_string())
+}
@@ -0,0 +1,270 @@
+/*
+   Reads the header and section table of a .ptau file, as written by snarkjs. The file starts with
+   the magic string "ptau", a u32 version and a u32 number of sections. Each section is a u32 id,
+   a u64 size, and the section's data. All integers are little-endian. Section 1 is the header:
+
+   - n8: u32, the size of a base field element in bytes
+   - q: n8 bytes, the base field modulus
+   - power: u32, the log_2 of the number of powers in the file
+   - ceremony_power: u32, the log_2 of the number of powers of the ceremony
+
+   Section 2 holds the 2^(power + 1) - 1 G1 powers of tau, and section 3 the 2^power G2 powers.
+   setup::setup() reads the points with ppot_rs::ptau::read(). For capacities too large to hold
+   in memory, read_g1_range() and read_g2_range() read them a chunk at a time. Coordinates are
+   n8 little-endian bytes in Montgomery form, and G2 coordinates are c0 followed by c1.
+*/
+use crate::error::Error;
+use ark_bn254::{Fq, Fq2, FqParameters, G1Affine, G2Affine};
+use ark_ff::{BigInteger, BigInteger256, FpParameters, FromBytes, Zero};
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+use tiny_keccak::{Hasher, Keccak};
+
+pub const PTAU_MAGIC: [u8; 4] = *b"ptau";
+const HEADER_SECTION: u32 = 1;
+const TAU_G1_SECTION: u32 = 2;
+const TAU_G2_SECTION: u32 = 3;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PtauSection {
+    pub id: u32,
+    // The offset of the section's data from the start of the file
+    pub offset: u64,
+    pub size: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PtauHeader {
+    pub version: u32,
+    pub n8: u32,
+    // The little-endian bytes of the base field modulus
+    pub q: Vec<u8>,
+    pub power: u32,
+    pub ceremony_power: u32,
+    pub sections: Vec<PtauSection>,
+}
+
+impl PtauHeader {
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let mut file = BufReader::new(File::open(path).map_err(io_error)?);
+
+        let mut magic = [0u8; 4];
+        file.read_exact(&mut magic).map_err(io_error)?;
+        if magic != PTAU_MAGIC {
+            return Err(Error::InvalidPtauMagic);
+        }
+        let version = read_u32(&mut file)?;
+        let num_sections = read_u32(&mut file)?;
+
+        let mut sections = Vec::with_capacity(num_sections as usize);
+        let mut offset = 12u64;
+        for _ in 0..num_sections {
+            let id = read_u32(&mut file)?;
+            let size = read_u64(&mut file)?;
+            offset += 12;
+            sections.push(PtauSection { id, offset, size });
+            offset += size;
+            file.seek(SeekFrom::Start(offset)).map_err(io_error)?;
+        }
+
+        let header = sections
+            .iter()
+            .find(|s| s.id == HEADER_SECTION)
+            .ok_or(Error::InvalidPtauHeader)?
+            .clone();
+        file.seek(SeekFrom::Start(header.offset))
+            .map_err(io_error)?;
+        let n8 = read_u32(&mut file)?;
+        if header.size != 4 + n8 as u64 + 8 {
+            return Err(Error::InvalidPtauHeader);
+        }
+        let mut q = vec![0u8; n8 as usize];
+        file.read_exact(&mut q).map_err(io_error)?;
+        let power = read_u32(&mut file)?;
+        let ceremony_power = read_u32(&mut file)?;
+
+        Ok(Self {
+            version,
+            n8,
+            q,
+            power,
+            ceremony_power,
+            sections,
+        })
+    }
+
+    // Whether the base field of the file is that of BN254
+    pub fn is_bn254(&self) -> bool {
+        self.q == FqParameters::MODULUS.to_bytes_le()
+    }
+
+    // The number of G1 powers of tau in the file
+    pub fn num_g1_points(&self) -> u64 {
+        self.section_size(TAU_G1_SECTION) / (2 * self.n8 as u64)
+    }
+
+    // The number of G2 powers of tau in the file
+    pub fn num_g2_points(&self) -> u64 {
+        self.section_size(TAU_G2_SECTION) / (4 * self.n8 as u64)
+    }
+
+    // The largest log_2 of a table size which the file has enough powers for
+    pub fn max_log_2_table_size(&self) -> u32 {
+        let num_powers = self
+            .num_g2_points()
+            .min(self.num_g1_points().saturating_sub(1));
+        63 - num_powers.max(1).leading_zeros()
+    }
+
+    /*
+     * Read count G1 powers of tau, starting from the start-th, without reading the rest of the
+     * file. Each point must be on the curve.
+     */
+    pub fn read_g1_range<P: AsRef<Path>>(
+        &self,
+        path: P,
+        start: usize,
+        count: usize,
+    ) -> Result<Vec<G1Affine>, Error> {
+        let bytes = self.read_section_range(path, TAU_G1_SECTION, 2, start, count)?;
+        bytes
+            .chunks(64)
+            .map(|b| {
+                let x = fq_from_montgomery_le(&b[..32])?;
+                let y = fq_from_montgomery_le(&b[32..])?;
+                if x.is_zero() && y.is_zero() {
+                    return Ok(G1Affine::zero());
+                }
+                let p = G1Affine::new(x, y, false);
+                if !p.is_on_curve() {
+                    return Err(Error::InvalidPtauPoint);
+                }
+                Ok(p)
+            })
+            .collect()
+    }
+
+    /*
+     * Read count G2 powers of tau, starting from the start-th, without reading the rest of the
+     * file. Each point must be on the curve.
+     */
+    pub fn read_g2_range<P: AsRef<Path>>(
+        &self,
+        path: P,
+        start: usize,
+        count: usize,
+    ) -> Result<Vec<G2Affine>, Error> {
+        let bytes = self.read_section_range(path, TAU_G2_SECTION, 4, start, count)?;
+        bytes
+            .chunks(128)
+            .map(|b| {
+                let x = Fq2::new(
+                    fq_from_montgomery_le(&b[..32])?,
+                    fq_from_montgomery_le(&b[32..64])?,
+                );
+                let y = Fq2::new(
+                    fq_from_montgomery_le(&b[64..96])?,
+                    fq_from_montgomery_le(&b[96..])?,
+                );
+                if x.is_zero() && y.is_zero() {
+                    return Ok(G2Affine::zero());
+                }
+                let p = G2Affine::new(x, y, false);
+                if !p.is_on_curve() {
+                    return Err(Error::InvalidPtauPoint);
+                }
+                Ok(p)
+            })
+            .collect()
+    }
+
+    // The bytes of count points of num_coords coordinates each, from the start-th point on
+    fn read_section_range<P: AsRef<Path>>(
+        &self,
+        path: P,
+        id: u32,
+        num_coords: u64,
+        start: usize,
+        count: usize,
+    ) -> Result<Vec<u8>, Error> {
+        if self.n8 != 32 {
+            return Err(Error::InvalidPtauHeader);
+        }
+        let point_size = num_coords * self.n8 as u64;
+        let section = self
+            .sections
+            .iter()
+            .find(|s| s.id == id)
+            .ok_or(Error::InvalidPtauHeader)?;
+        if (start + count) as u64 * point_size > section.size {
+            return Err(Error::PtauPowerTooSmall);
+        }
+
+        let mut file = BufReader::new(File::open(path).map_err(io_error)?);
+        file.seek(SeekFrom::Start(section.offset + start as u64 * point_size))
+            .map_err(io_error)?;
+        let mut bytes = vec![0u8; count * point_size as usize];
+        file.read_exact(&mut bytes).map_err(io_error)?;
+        Ok(bytes)
+    }
+
+    fn section_size(&self, id: u32) -> u64 {
+        self.sections
+            .iter()
+            .filter(|s| s.id == id)
+            .map(|s| s.size)
+            .sum()
+    }
+}
+
+// Whether the file starts with PTAU_MAGIC, i.e. is a .ptau file rather than a raw PPOT file
+pub fn is_ptau<P: AsRef<Path>>(path: P) -> bool {
+    let mut magic = [0u8; 4];
+    File::open(path)
+        .and_then(|mut file| file.read_exact(&mut magic))
+        .map(|_| magic == PTAU_MAGIC)
+        .unwrap_or(false)
+}
+
+// The keccak256 hash of a file
+pub fn hash_file<P: AsRef<Path>>(path: P) -> Result<[u8; 32], Error> {
+    let mut file = BufReader::new(File::open(path).map_err(io_error)?);
+    let mut hasher = Keccak::v256();
+    let mut buf = vec![0u8; 1 << 20];
+    loop {
+        let n = file.read(&mut buf).map_err(io_error)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    let mut hash = [0u8; 32];
+    hasher.finalize(&mut hash);
+    Ok(hash)
+}
+
+// Rejects values which are not less than the modulus
+fn fq_from_montgomery_le(bytes: &[u8]) -> Result<Fq, Error> {
+    let repr = BigInteger256::read(bytes).map_err(|_| Error::InvalidPtauPoint)?;
+    if repr >= FqParameters::MODULUS {
+        return Err(Error::InvalidPtauPoint);
+    }
+    Ok(Fq::new(repr))
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> Result<u32, Error> {
+    let mut bytes = [0u8; 4];
+    reader.read_exact(&mut bytes).map_err(io_error)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_u64<R: Read>(reader: &mut R) -> Result<u64, Error> {
+    let mut bytes = [0u8; 8];
+    reader.read_exact(&mut bytes).map_err(io_error)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+fn io_error(e: std::io::Error) -> Error {
+    Error::PtauIoError(e.to_string())
+}
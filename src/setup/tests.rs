@@ -1,27 +1,515 @@
-use super::{g1_str_to_g1, g2_str_to_g2, setup};
+use super::artifact::SetupArtifact;
+use super::contribution::{contribute, verify_contribution};
+use super::ppot::{
+    blake2b_hash_file, decode_g1, decode_g2, encode_g1, encode_g2, write_challenge, PpotEncoding,
+    PpotFile, HASH_SIZE, PUBLIC_KEY_SIZE,
+};
+use super::ptau::{hash_file, PtauHeader};
+use super::{
+    check_g2_subgroup, g2_to_g2_str, load_setup, read_srs, setup, try_g1_str_to_g1,
+    try_g2_str_to_g2, try_load_lagrange_comms_from_file, verify_lagrange_comms, verify_setup,
+    verify_srs,
+};
+use crate::accumulator::compute_lagrange_tree;
+use crate::error::Error;
+use crate::prover::TrimConfig;
+use ark_bn254::{Bn254, Fq, Fq2, Fr, G1Affine, G2Affine};
+use ark_ec::{AffineCurve, ProjectiveCurve};
+use ark_ff::{FpParameters, PrimeField, ToBytes, UniformRand, Zero};
+use ark_std::test_rng;
 
 #[test]
 pub fn test_g1() {
     //let g1_str = "01000000000000000000000000000000000000000000000000000000000000000200000000000000000000000000000000000000000000000000000000000000";
     let g1_str = "65C58017927150D104B032050F73EC6A18D1135615069F6A4AFF45847C0D29274A46B7E1C915691021D130F9837D066F30CCCFB9CCBD78EB335382F4591E7E20";
-    let g1 = g1_str_to_g1(&String::from(g1_str));
+    let g1 = try_g1_str_to_g1(g1_str).unwrap();
     assert!(g1.is_on_curve());
 }
 
 #[test]
 pub fn test_g2() {
     let g2_str = "edf692d95cbdde46ddda5ef7d422436779445c5e66006a42761e1f12efde0018c212f3aeb785e49712e7a9353349aaf1255dfb31b7bf60723a480d9293938e19aa7dfa6601cce64c7bd3430c69e7d1e38f40cb8d8071ab4aeb6d8cdba55ec8125b9722d1dcdaac55f38eb37033314bbc95330c69ad999eec75f05f58d0890609";
-    let g2 = g2_str_to_g2(&String::from(g2_str));
+    let g2 = try_g2_str_to_g2(g2_str).unwrap();
     assert!(g2.is_on_curve());
 
     let g2_str = "7fa3b682a9ca88c26d79614ba1983b2b9592ccff25bdeeb4f20a2b8dbafbd116267e64cba10106b2fe21069bdf1455802bec49d0d0166a675d5fc9d17ec43b0833a2d46d92dea96d2b0471181d6cd1ed8d08026bc9d358b1b1da0cb1011ee4011b90877c247ea9c4fc2312d977361e50949e426f27646d42f98ff36ae9ffe918";
-    let g2 = g2_str_to_g2(&String::from(g2_str));
+    let g2 = try_g2_str_to_g2(g2_str).unwrap();
     assert!(g2.is_on_curve());
+    assert_eq!(g2_to_g2_str(&g2), g2_str);
+}
+
+#[test]
+pub fn test_try_g2_str_to_g2() {
+    let g2_str = "edf692d95cbdde46ddda5ef7d422436779445c5e66006a42761e1f12efde0018c212f3aeb785e49712e7a9353349aaf1255dfb31b7bf60723a480d9293938e19aa7dfa6601cce64c7bd3430c69e7d1e38f40cb8d8071ab4aeb6d8cdba55ec8125b9722d1dcdaac55f38eb37033314bbc95330c69ad999eec75f05f58d0890609";
+    let g2 = try_g2_str_to_g2(g2_str).unwrap();
+    assert_eq!(g2_to_g2_str(&g2), g2_str);
+
+    assert_eq!(try_g2_str_to_g2(&g2_str[2..]), Err(Error::InvalidG2String));
+    assert_eq!(
+        try_g2_str_to_g2(&g2_str.replace('e', "z")),
+        Err(Error::InvalidG2String)
+    );
+
+    // Change the last byte of the y coordinate
+    let off_curve = format!("{}00", &g2_str[..254]);
+    assert_eq!(try_g2_str_to_g2(&off_curve), Err(Error::G2PointNotOnCurve));
+
+    // A point on the twist which is not in the prime-order subgroup
+    let outside_subgroup = (1u64..)
+        .find_map(|i| G2Affine::get_point_from_x(Fq2::new(Fq::from(i), Fq::zero()), false))
+        .unwrap();
+    assert_eq!(
+        try_g2_str_to_g2(&g2_to_g2_str(&outside_subgroup)),
+        Err(Error::G2PointNotInSubgroup)
+    );
 }
 
 #[test]
 pub fn test_setup() {
-    let pk = setup(11, "./11.ptau");
+    let pk = setup(11, "./11.ptau").unwrap();
     assert_eq!(pk.0.srs_g1.len(), pk.0.srs_g2.len() + 1);
     assert_eq!(pk.1.len(), pk.0.srs_g2.len());
+
+    // Unsupported table sizes are an error rather than a panic
+    assert_eq!(
+        setup(9, "./11.ptau").err(),
+        Some(Error::UnsupportedTableSize(9))
+    );
+    assert_eq!(
+        verify_setup(29, "./11.ptau"),
+        Err(Error::UnsupportedTableSize(29))
+    );
+    assert_eq!(
+        load_setup(29, "./11.ptau").err(),
+        Some(Error::UnsupportedTableSize(29))
+    );
+}
+
+#[test]
+pub fn test_verify_srs() {
+    let (pk, _) = setup(10, "./11.ptau").unwrap();
+    let mut rng = test_rng();
+    assert_eq!(verify_srs(&pk.srs_g1, &pk.srs_g2, &mut rng), Ok(()));
+
+    // Tamper with one G1 power
+    let mut srs_g1 = pk.srs_g1.clone();
+    srs_g1[5] = srs_g1[5] + G1Affine::prime_subgroup_generator();
+    assert_eq!(
+        verify_srs(&srs_g1, &pk.srs_g2, &mut rng),
+        Err(Error::SrsG1PowersInconsistent)
+    );
+
+    // Tamper with one G2 power
+    let mut srs_g2 = pk.srs_g2.clone();
+    srs_g2[7] = srs_g2[7] + G2Affine::prime_subgroup_generator();
+    assert_eq!(
+        verify_srs(&pk.srs_g1, &srs_g2, &mut rng),
+        Err(Error::SrsG2PowersInconsistent)
+    );
+
+    // Swap in a different generator
+    let mut srs_g1 = pk.srs_g1.clone();
+    srs_g1[0] = srs_g1[1];
+    assert_eq!(
+        verify_srs(&srs_g1, &pk.srs_g2, &mut rng),
+        Err(Error::InvalidSrsGenerator)
+    );
+}
+
+// A point on the G2 curve which is not in the prime-order subgroup
+pub fn g2_point_outside_subgroup() -> G2Affine {
+    let modulus = <Fr as PrimeField>::Params::MODULUS;
+    (1u64..)
+        .filter_map(|i| G2Affine::get_point_from_x(Fq2::new(Fq::from(i), Fq::zero()), false))
+        .find(|p| !p.mul(modulus).is_zero())
+        .unwrap()
+}
+
+#[test]
+pub fn test_check_g2_subgroup() {
+    let (pk, _) = setup(10, "./11.ptau").unwrap();
+    let mut rng = test_rng();
+    assert_eq!(check_g2_subgroup(&pk.srs_g2, &mut rng), Ok(()));
+
+    let mut srs_g2 = pk.srs_g2.clone();
+    srs_g2[3] = g2_point_outside_subgroup();
+    assert!(srs_g2[3].is_on_curve());
+    assert_eq!(
+        check_g2_subgroup(&srs_g2, &mut rng),
+        Err(Error::SrsPointNotInSubgroup)
+    );
+}
+
+#[test]
+pub fn test_setup_artifact() {
+    let log_2_table_size = 10;
+    let table_size = 1 << log_2_table_size;
+    let (pk, lagrange_comms) = setup(log_2_table_size, "./11.ptau").unwrap();
+
+    let path = std::env::temp_dir().join("semacaulk_test_setup_artifact.bin");
+    SetupArtifact::write(&path, &pk, &lagrange_comms, log_2_table_size).unwrap();
+    assert!(SetupArtifact::is_artifact(&path));
+    assert!(!SetupArtifact::is_artifact("./11.ptau"));
+
+    let artifact = SetupArtifact::open(&path).unwrap();
+    assert_eq!(artifact.table_size(), table_size);
+    assert_eq!(
+        artifact.lagrange_root(),
+        compute_lagrange_tree::<Bn254>(&lagrange_comms).root()
+    );
+    assert_eq!(artifact.lagrange_comms().unwrap(), lagrange_comms);
+    assert_eq!(artifact.lagrange_comm(7).unwrap(), lagrange_comms[7]);
+
+    let artifact_pk = artifact.proving_key().unwrap();
+    assert_eq!(artifact_pk.srs_g1, pk.srs_g1[..=table_size]);
+    assert_eq!(artifact_pk.srs_g2, pk.srs_g2[..table_size]);
+
+    let vk = artifact.verifying_key().unwrap();
+    assert_eq!(vk.srs_g1_t, pk.srs_g1[table_size]);
+    assert_eq!(vk.srs_g2_1, pk.srs_g2[1]);
+
+    // Points are read lazily
+    let config = TrimConfig::default();
+    let trimmed_pk = artifact.trimmed_proving_key(config).unwrap();
+    assert_eq!(trimmed_pk.srs_g1, pk.srs_g1[..config.num_g1_points]);
+    assert_eq!(trimmed_pk.srs_g2, pk.srs_g2[..config.num_g2_points]);
+    assert_eq!(artifact.srs_g1(table_size).unwrap(), pk.srs_g1[table_size]);
+    assert_eq!(artifact.srs_g2(5).unwrap(), pk.srs_g2[5]);
+    assert_eq!(
+        artifact.srs_g1(table_size + 1).err(),
+        Some(Error::ArtifactIndexOutOfRange)
+    );
+    assert_eq!(
+        artifact.lagrange_comm(table_size).err(),
+        Some(Error::ArtifactIndexOutOfRange)
+    );
+    assert_eq!(
+        artifact
+            .trimmed_proving_key(TrimConfig {
+                num_g1_points: table_size + 2,
+                num_g2_points: 1,
+            })
+            .err(),
+        Some(Error::SrsTooShort)
+    );
+
+    let (loaded_pk, loaded_comms) = load_setup(log_2_table_size, path.to_str().unwrap()).unwrap();
+    assert_eq!(loaded_pk.srs_g2, artifact_pk.srs_g2);
+    assert_eq!(loaded_comms, lagrange_comms);
+    assert_eq!(
+        load_setup(11, path.to_str().unwrap()).err(),
+        Some(Error::InvalidArtifactTableSize)
+    );
+
+    // Corrupt one byte of a Lagrange commitment
+    let mut bytes = std::fs::read(&path).unwrap();
+    let len = bytes.len();
+    bytes[len - 40] ^= 1;
+    std::fs::write(&path, &bytes).unwrap();
+    assert_eq!(
+        SetupArtifact::open(&path).err(),
+        Some(Error::ArtifactChecksumMismatch)
+    );
+
+    std::fs::write(&path, &bytes[..len - 1]).unwrap();
+    assert_eq!(
+        SetupArtifact::open(&path).err(),
+        Some(Error::ArtifactTruncated)
+    );
+
+    bytes[0] = 0;
+    std::fs::write(&path, &bytes).unwrap();
+    assert_eq!(
+        SetupArtifact::open(&path).err(),
+        Some(Error::InvalidArtifactMagic)
+    );
+
+    // A G2 point outside the subgroup is rejected when it is read
+    let mut bad_pk = pk.clone();
+    bad_pk.srs_g2[3] = g2_point_outside_subgroup();
+    SetupArtifact::write(&path, &bad_pk, &lagrange_comms, log_2_table_size).unwrap();
+    let artifact = SetupArtifact::open(&path).unwrap();
+    assert_eq!(
+        artifact.proving_key().err(),
+        Some(Error::InvalidArtifactPoint)
+    );
+    assert_eq!(artifact.srs_g2(3).err(), Some(Error::InvalidArtifactPoint));
+
+    assert_eq!(
+        SetupArtifact::write(&path, &pk, &lagrange_comms[1..], log_2_table_size).err(),
+        Some(Error::LagrangeCommsWrongLength)
+    );
+    assert_eq!(
+        SetupArtifact::write(&path, &pk, &lagrange_comms, 12).err(),
+        Some(Error::SrsTooShort)
+    );
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+pub fn test_try_g1_str_to_g1() {
+    let g1_str = "65C58017927150D104B032050F73EC6A18D1135615069F6A4AFF45847C0D29274A46B7E1C915691021D130F9837D066F30CCCFB9CCBD78EB335382F4591E7E20";
+    let g1 = try_g1_str_to_g1(g1_str).unwrap();
+    assert!(g1.is_on_curve());
+    assert_eq!(try_g1_str_to_g1(&g1_str[2..]), Err(Error::InvalidG1String));
+    assert_eq!(
+        try_g1_str_to_g1(&g1_str.replace('C', "z")),
+        Err(Error::InvalidG1String)
+    );
+
+    let off_curve = format!("{}00", &g1_str[..126]);
+    assert_eq!(try_g1_str_to_g1(&off_curve), Err(Error::G1PointNotOnCurve));
+}
+
+#[test]
+pub fn test_ptau_header() {
+    let header = PtauHeader::read("./11.ptau").unwrap();
+    assert!(header.is_bn254());
+    assert_eq!(header.n8, 32);
+    assert_eq!(header.power, 11);
+    assert_eq!(header.ceremony_power, 28);
+    assert_eq!(header.num_g1_points(), 4095);
+    assert_eq!(header.num_g2_points(), 2048);
+    assert_eq!(header.max_log_2_table_size(), 11);
+    assert_eq!(hash_file("./11.ptau").unwrap().len(), 32);
+
+    // An artifact is not a ptau file
+    let (pk, lagrange_comms) = setup(10, "./11.ptau").unwrap();
+    let path = std::env::temp_dir().join("semacaulk_test_ptau_header.bin");
+    SetupArtifact::write(&path, &pk, &lagrange_comms, 10).unwrap();
+    assert_eq!(PtauHeader::read(&path), Err(Error::InvalidPtauMagic));
+    std::fs::remove_file(&path).unwrap();
+}
+
+// Write a raw PPOT file of the given power, with the generators in place of alpha and beta
+fn write_ppot_file(
+    path: &std::path::Path,
+    encoding: PpotEncoding,
+    previous_hash: &[u8; HASH_SIZE],
+    power: u32,
+) {
+    let n = 1usize << power;
+    let (srs_g1, srs_g2) = read_srs("./11.ptau", 2 * n - 1, n).unwrap();
+    let g1 = G1Affine::prime_subgroup_generator();
+    let g2 = G2Affine::prime_subgroup_generator();
+
+    let mut bytes = previous_hash.to_vec();
+    srs_g1
+        .iter()
+        .for_each(|p| bytes.extend(encode_g1(p, encoding)));
+    srs_g2
+        .iter()
+        .for_each(|p| bytes.extend(encode_g2(p, encoding)));
+    vec![g1; 2 * n]
+        .iter()
+        .for_each(|p| bytes.extend(encode_g1(p, encoding)));
+    bytes.extend(encode_g2(&g2, encoding));
+    if encoding == PpotEncoding::Compressed {
+        bytes.extend(vec![0u8; PUBLIC_KEY_SIZE]);
+    }
+    std::fs::write(path, bytes).unwrap();
+}
+
+#[test]
+pub fn test_ppot_files() {
+    let log_2_table_size = 10;
+    let dir = std::env::temp_dir();
+    let challenge = dir.join("semacaulk_test_ppot_challenge");
+    let response = dir.join("semacaulk_test_ppot_response");
+
+    write_ppot_file(
+        &challenge,
+        PpotEncoding::Uncompressed,
+        &[1u8; HASH_SIZE],
+        log_2_table_size,
+    );
+    let challenge_hash = blake2b_hash_file(&challenge).unwrap();
+    write_ppot_file(
+        &response,
+        PpotEncoding::Compressed,
+        &challenge_hash,
+        log_2_table_size,
+    );
+    let challenge = challenge.to_str().unwrap();
+    let response = response.to_str().unwrap();
+
+    let file = PpotFile::open(challenge).unwrap();
+    assert!(!file.is_response());
+    assert_eq!(file.power(), log_2_table_size);
+    assert_eq!(file.previous_hash().unwrap(), [1u8; HASH_SIZE]);
+
+    let file = PpotFile::open(response).unwrap();
+    assert!(file.is_response());
+    assert_eq!(file.power(), log_2_table_size);
+    assert_eq!(file.check_previous(challenge), Ok(()));
+    assert_eq!(file.check_previous(response), Err(Error::PpotHashMismatch));
+    assert_eq!(file.read(2048, 1024), Err(Error::PpotPowerTooSmall));
+
+    // Both files give the same setup as the .ptau file
+    let expected = setup(log_2_table_size as usize, "./11.ptau").unwrap();
+    for path in [challenge, response] {
+        let (pk, lagrange_comms) = setup(log_2_table_size as usize, path).unwrap();
+        assert_eq!(pk.srs_g1, expected.0.srs_g1);
+        assert_eq!(pk.srs_g2, expected.0.srs_g2);
+        assert_eq!(lagrange_comms, expected.1);
+    }
+
+    std::fs::remove_file(challenge).unwrap();
+    std::fs::remove_file(response).unwrap();
+}
+
+#[test]
+pub fn test_ppot_point_encoding() {
+    let mut rng = test_rng();
+    for encoding in [PpotEncoding::Uncompressed, PpotEncoding::Compressed] {
+        for _ in 0..8 {
+            let g1 = G1Affine::prime_subgroup_generator()
+                .mul(Fr::rand(&mut rng))
+                .into_affine();
+            let g2 = G2Affine::prime_subgroup_generator()
+                .mul(Fr::rand(&mut rng))
+                .into_affine();
+            assert_eq!(decode_g1(&encode_g1(&g1, encoding), encoding), Ok(g1));
+            assert_eq!(decode_g2(&encode_g2(&g2, encoding), encoding), Ok(g2));
+        }
+
+        let zero = encode_g1(&G1Affine::zero(), encoding);
+        assert_eq!(zero[0], 0x40);
+        assert_eq!(decode_g1(&zero, encoding), Ok(G1Affine::zero()));
+
+        // The point at infinity must have no other bits set
+        let mut invalid = zero;
+        invalid[1] = 1;
+        assert_eq!(decode_g1(&invalid, encoding), Err(Error::InvalidPpotPoint));
+    }
+
+    // (1, 1) is not on the curve
+    let mut bytes = [0u8; 64];
+    bytes[31] = 1;
+    bytes[63] = 1;
+    assert_eq!(
+        decode_g1(&bytes, PpotEncoding::Uncompressed),
+        Err(Error::InvalidPpotPoint)
+    );
+
+    // Points of the wrong length
+    assert_eq!(
+        decode_g1(&bytes[..32], PpotEncoding::Uncompressed),
+        Err(Error::InvalidPpotPoint)
+    );
+    assert_eq!(
+        decode_g2(&bytes, PpotEncoding::Uncompressed),
+        Err(Error::InvalidPpotPoint)
+    );
+}
+
+#[test]
+pub fn test_contribution() {
+    let mut rng = test_rng();
+    let log_2_table_size = 10;
+    let n = 1usize << log_2_table_size;
+    let (srs_g1, srs_g2) = read_srs("./11.ptau", 2 * n - 1, n).unwrap();
+
+    let (new_g1, new_g2, proof) = contribute(&srs_g1, &srs_g2, &mut rng).unwrap();
+    assert_ne!(new_g1[1], srs_g1[1]);
+    assert_eq!(
+        verify_contribution(&srs_g1, &srs_g2, &new_g1, &new_g2, &proof, &mut rng),
+        Ok(())
+    );
+
+    // The proof of another contribution to the same SRS does not match
+    let (other_g1, other_g2, other_proof) = contribute(&srs_g1, &srs_g2, &mut rng).unwrap();
+    assert_eq!(
+        verify_contribution(&srs_g1, &srs_g2, &other_g1, &other_g2, &proof, &mut rng),
+        Err(Error::ContributionNotFromPreviousSrs)
+    );
+
+    // Neither does a contribution to another SRS
+    assert_eq!(
+        verify_contribution(
+            &new_g1,
+            &new_g2,
+            &other_g1,
+            &other_g2,
+            &other_proof,
+            &mut rng
+        ),
+        Err(Error::InvalidContributionProof)
+    );
+    assert_eq!(
+        verify_contribution(&srs_g1, &srs_g2, &new_g1[..n], &new_g2, &proof, &mut rng),
+        Err(Error::ContributionLengthMismatch)
+    );
+    assert_eq!(
+        contribute(&srs_g1[..1], &srs_g2, &mut rng).err(),
+        Some(Error::SrsTooShort)
+    );
+
+    // setup() reads the challenge file which the contribution is written to
+    let path = std::env::temp_dir().join("semacaulk_test_contribution");
+    let path = path.to_str().unwrap();
+    write_challenge(path, &[0u8; HASH_SIZE], &new_g1, &new_g2).unwrap();
+    let (pk, _) = setup(log_2_table_size, path).unwrap();
+    assert_eq!(pk.srs_g1, new_g1[..=n]);
+    assert_eq!(pk.srs_g2, new_g2);
+    std::fs::remove_file(path).unwrap();
+
+    assert_eq!(
+        write_challenge(path, &[0u8; HASH_SIZE], &new_g1[..n], &new_g2),
+        Err(Error::InvalidPpotFileSize)
+    );
+}
+
+#[test]
+pub fn test_verify_lagrange_comms() {
+    let mut rng = test_rng();
+    let table_size = 1 << 10;
+    let (pk, lagrange_comms) = setup(10, "./11.ptau").unwrap();
+    assert_eq!(
+        verify_lagrange_comms(&pk.srs_g1, &lagrange_comms, table_size, &mut rng),
+        Ok(())
+    );
+
+    assert_eq!(
+        verify_lagrange_comms(&pk.srs_g1, &lagrange_comms[1..], table_size, &mut rng),
+        Err(Error::LagrangeCommsWrongLength)
+    );
+
+    // Swapping two commitments keeps their sum, but not their order
+    let mut swapped = lagrange_comms.clone();
+    swapped.swap(1, 2);
+    assert_eq!(
+        verify_lagrange_comms(&pk.srs_g1, &swapped, table_size, &mut rng),
+        Err(Error::LagrangeCommsInconsistent)
+    );
+
+    let mut replaced = lagrange_comms.clone();
+    replaced[5] = G1Affine::prime_subgroup_generator();
+    assert_eq!(
+        verify_lagrange_comms(&pk.srs_g1, &replaced, table_size, &mut rng),
+        Err(Error::LagrangeCommsSumNotOne)
+    );
+
+    // Files are read strictly
+    let path = std::env::temp_dir().join("semacaulk_test_verify_lagrange_comms");
+    let path = path.to_str().unwrap();
+    let lines: Vec<String> = lagrange_comms
+        .iter()
+        .map(|p| {
+            let mut bytes = vec![];
+            p.x.write(&mut bytes).unwrap();
+            p.y.write(&mut bytes).unwrap();
+            hex::encode(bytes)
+        })
+        .collect();
+    std::fs::write(path, lines.join("\n") + "\n").unwrap();
+    assert_eq!(try_load_lagrange_comms_from_file(path), Ok(lagrange_comms));
+
+    std::fs::write(path, lines[..4].join("\n") + "\nabcd\n").unwrap();
+    assert_eq!(
+        try_load_lagrange_comms_from_file(path),
+        Err(Error::InvalidG1String)
+    );
+    std::fs::remove_file(path).unwrap();
 }
@@ -0,0 +1,138 @@
+use crate::error::Error;
+use crate::identity::{attribute_hash, Identity};
+use crate::layouter::Layouter;
+use crate::mimc7::init_mimc7;
+use crate::prover::prover::{Prover, WitnessInput};
+use crate::prover::{AttributePublicData, ProverPrecomputedData};
+use crate::semaphore_compat::{hash_signal, Group};
+use crate::setup::setup;
+use crate::verifier::{Verifier, VerifyingKey};
+use ark_bn254::{Bn254, Fr};
+use ark_std::test_rng;
+
+#[test]
+pub fn test_attribute_prove_and_verify() {
+    let mut rng = test_rng();
+    let log_2_table_size = 10;
+    let table_size: usize = 1 << log_2_table_size;
+    let (pk, lagrange_comms) = setup(log_2_table_size, "./11.ptau").unwrap();
+    let vk = VerifyingKey::from_proving_key(&pk, table_size).unwrap();
+    let mimc7 = init_mimc7::<Fr>();
+
+    // A member whose tier is 3
+    let identity = Identity::new(Fr::from(100u64), Fr::from(200u64));
+    let tier = Fr::from(3u64);
+    let commitment = identity.commitment_with_attribute(tier);
+    let mut group = Group::new(&lagrange_comms);
+    group.add_member(Fr::from(1u64)).unwrap();
+    let index = group.add_member(commitment).unwrap();
+
+    let precomputed =
+        ProverPrecomputedData::index(&pk, &mimc7.cts, &[index], &group.polynomial(), table_size);
+    let witness = WitnessInput {
+        identity_nullifier: identity.nullifier,
+        identity_trapdoor: identity.trapdoor,
+        identity_commitment: commitment,
+        index,
+    };
+
+    let external_nullifier = Fr::from(300u64);
+    let threshold = Fr::from(2u64);
+    let public_input = AttributePublicData::<Bn254> {
+        accumulator: group.accumulator(),
+        external_nullifier,
+        signal_hash: hash_signal("hello"),
+        nullifier_hash: identity.nullifier_hash(external_nullifier),
+        threshold,
+        reveal_attribute_hash: true,
+        attribute_hash: attribute_hash(tier),
+    };
+
+    // Prove that tier >= 2 and reveal the hash of the tier
+    let assignment = Layouter::assign_with_attribute(
+        identity.nullifier,
+        identity.trapdoor,
+        tier,
+        threshold,
+        external_nullifier,
+        &mimc7.cts,
+        &mut rng,
+    )
+    .unwrap();
+    let proof = Prover::prove_with_attribute(
+        &pk,
+        &witness,
+        &assignment,
+        &public_input,
+        &precomputed,
+        &mut rng,
+        table_size,
+    );
+    assert!(Verifier::verify_with_attribute(
+        &proof,
+        vk.srs_g1_t,
+        vk.srs_g2_1,
+        group.accumulator(),
+        &public_input,
+    ));
+
+    // The proof is bound to the threshold
+    let mut wrong_public_input = public_input.clone();
+    wrong_public_input.threshold = Fr::from(1u64);
+    assert!(!Verifier::verify_with_attribute(
+        &proof,
+        vk.srs_g1_t,
+        vk.srs_g2_1,
+        group.accumulator(),
+        &wrong_public_input,
+    ));
+
+    // The proof is bound to the attribute hash if it is revealed
+    let mut wrong_public_input = public_input.clone();
+    wrong_public_input.attribute_hash = attribute_hash(Fr::from(4u64));
+    assert!(!Verifier::verify_with_attribute(
+        &proof,
+        vk.srs_g1_t,
+        vk.srs_g2_1,
+        group.accumulator(),
+        &wrong_public_input,
+    ));
+
+    // Without revealing the attribute hash
+    let public_input = AttributePublicData::<Bn254> {
+        reveal_attribute_hash: false,
+        attribute_hash: Fr::from(0u64),
+        ..public_input
+    };
+    let proof = Prover::prove_with_attribute(
+        &pk,
+        &witness,
+        &assignment,
+        &public_input,
+        &precomputed,
+        &mut rng,
+        table_size,
+    );
+    assert!(Verifier::verify_with_attribute(
+        &proof,
+        vk.srs_g1_t,
+        vk.srs_g2_1,
+        group.accumulator(),
+        &public_input,
+    ));
+
+    // A member whose tier is 3 cannot satisfy tier >= 4
+    assert_eq!(
+        Layouter::assign_with_attribute(
+            identity.nullifier,
+            identity.trapdoor,
+            tier,
+            Fr::from(4u64),
+            external_nullifier,
+            &mimc7.cts,
+            &mut rng,
+        )
+        .err(),
+        Some(Error::AttributePredicateNotSatisfied)
+    );
+}
@@ -0,0 +1,117 @@
+use crate::identity::Identity;
+use crate::layouter::Layouter;
+use crate::mimc7::init_mimc7;
+use crate::prover::prover::{Prover, WitnessInput};
+use crate::prover::{LinkedPublicData, ProverPrecomputedData};
+use crate::semaphore_compat::{hash_signal, Group};
+use crate::setup::setup;
+use crate::verifier::{Verifier, VerifyingKey};
+use ark_bn254::{Bn254, Fr};
+use ark_std::test_rng;
+
+#[test]
+pub fn test_linked_prove_and_verify() {
+    let mut rng = test_rng();
+    let log_2_table_size = 10;
+    let table_size: usize = 1 << log_2_table_size;
+    let (pk, lagrange_comms) = setup(log_2_table_size, "./11.ptau").unwrap();
+    let vk = VerifyingKey::from_proving_key(&pk, table_size).unwrap();
+    let mimc7 = init_mimc7::<Fr>();
+
+    // The member is at index 1 of the first group and at index 3 of the second group
+    let identity = Identity::new(Fr::from(100u64), Fr::from(200u64));
+    let mut group = Group::new(&lagrange_comms);
+    group.add_member(Fr::from(1u64)).unwrap();
+    let index = group.add_member(identity.commitment()).unwrap();
+
+    let mut second_group = Group::new(&lagrange_comms);
+    second_group.add_member(Fr::from(2u64)).unwrap();
+    second_group.add_member(Fr::from(3u64)).unwrap();
+    second_group.add_member(Fr::from(4u64)).unwrap();
+    let second_index = second_group.add_member(identity.commitment()).unwrap();
+
+    let precomputed =
+        ProverPrecomputedData::index(&pk, &mimc7.cts, &[index], &group.polynomial(), table_size);
+    let second_precomputed = ProverPrecomputedData::index(
+        &pk,
+        &mimc7.cts,
+        &[second_index],
+        &second_group.polynomial(),
+        table_size,
+    );
+    let witness = WitnessInput {
+        identity_nullifier: identity.nullifier,
+        identity_trapdoor: identity.trapdoor,
+        identity_commitment: identity.commitment(),
+        index,
+    };
+
+    let external_nullifier = Fr::from(300u64);
+    let public_input = LinkedPublicData::<Bn254> {
+        accumulator: group.accumulator(),
+        second_accumulator: second_group.accumulator(),
+        external_nullifier,
+        signal_hash: hash_signal("vote"),
+        nullifier_hash: identity.nullifier_hash(external_nullifier),
+    };
+
+    let assignment = Layouter::assign(
+        identity.nullifier,
+        identity.trapdoor,
+        external_nullifier,
+        &mimc7.cts,
+        &mut rng,
+    );
+    let proof = Prover::prove_linked(
+        &pk,
+        &witness,
+        second_index,
+        &assignment,
+        &public_input,
+        &precomputed,
+        &second_precomputed,
+        &mut rng,
+        table_size,
+    );
+    assert!(Verifier::verify_linked(
+        &proof,
+        vk.srs_g1_t,
+        vk.srs_g2_1,
+        &public_input
+    ));
+
+    // The proof is bound to both accumulators
+    let mut third_group = Group::new(&lagrange_comms);
+    third_group.add_member(Fr::from(5u64)).unwrap();
+    let wrong_public_input = LinkedPublicData::<Bn254> {
+        second_accumulator: third_group.accumulator(),
+        ..public_input.clone()
+    };
+    assert!(!Verifier::verify_linked(
+        &proof,
+        vk.srs_g1_t,
+        vk.srs_g2_1,
+        &wrong_public_input
+    ));
+
+    let wrong_public_input = LinkedPublicData::<Bn254> {
+        accumulator: second_group.accumulator(),
+        second_accumulator: group.accumulator(),
+        ..public_input.clone()
+    };
+    assert!(!Verifier::verify_linked(
+        &proof,
+        vk.srs_g1_t,
+        vk.srs_g2_1,
+        &wrong_public_input
+    ));
+
+    // The transcript binds the first lookup to the second, so it is not a standalone proof
+    assert!(!Verifier::verify(
+        &proof.proof,
+        vk.srs_g1_t,
+        vk.srs_g2_1,
+        public_input.accumulator,
+        &public_input.to_public_data()
+    ));
+}
@@ -0,0 +1,83 @@
+use crate::identity::Identity;
+use crate::layouter::Layouter;
+use crate::mimc7::init_mimc7;
+use crate::prover::prover::{Prover, WitnessInput};
+use crate::prover::{MembershipPublicData, ProverPrecomputedData};
+use crate::semaphore_compat::{hash_signal, Group};
+use crate::setup::setup;
+use crate::verifier::{Verifier, VerifyingKey};
+use ark_bn254::{Bn254, Fr};
+use ark_std::test_rng;
+
+#[test]
+pub fn test_membership_prove_and_verify() {
+    let mut rng = test_rng();
+    let log_2_table_size = 10;
+    let table_size: usize = 1 << log_2_table_size;
+    let (pk, lagrange_comms) = setup(log_2_table_size, "./11.ptau").unwrap();
+    let vk = VerifyingKey::from_proving_key(&pk, table_size).unwrap();
+    let mimc7 = init_mimc7::<Fr>();
+
+    let identity = Identity::new(Fr::from(100u64), Fr::from(200u64));
+    let mut group = Group::new(&lagrange_comms);
+    group.add_member(Fr::from(1u64)).unwrap();
+    let index = group.add_member(identity.commitment()).unwrap();
+
+    let precomputed =
+        ProverPrecomputedData::index(&pk, &mimc7.cts, &[index], &group.polynomial(), table_size);
+    let witness = WitnessInput {
+        identity_nullifier: identity.nullifier,
+        identity_trapdoor: identity.trapdoor,
+        identity_commitment: identity.commitment(),
+        index,
+    };
+
+    // The signal is a challenge issued by an off-chain API
+    let public_input = MembershipPublicData::<Bn254> {
+        accumulator: group.accumulator(),
+        signal_hash: hash_signal("challenge"),
+    };
+
+    let assignment =
+        Layouter::assign_membership(identity.nullifier, identity.trapdoor, &mimc7.cts, &mut rng);
+    let proof = Prover::prove_membership(
+        &pk,
+        &witness,
+        &assignment,
+        &public_input,
+        &precomputed,
+        &mut rng,
+        table_size,
+    );
+    assert!(Verifier::verify_membership(
+        &proof,
+        vk.srs_g1_t,
+        vk.srs_g2_1,
+        group.accumulator(),
+        &public_input,
+    ));
+
+    // The proof is bound to the signal hash
+    let wrong_public_input = MembershipPublicData::<Bn254> {
+        signal_hash: hash_signal("another challenge"),
+        ..public_input.clone()
+    };
+    assert!(!Verifier::verify_membership(
+        &proof,
+        vk.srs_g1_t,
+        vk.srs_g2_1,
+        group.accumulator(),
+        &wrong_public_input,
+    ));
+
+    // The proof is bound to the accumulator
+    let mut other_group = group.clone();
+    other_group.add_member(Fr::from(3u64)).unwrap();
+    assert!(!Verifier::verify_membership(
+        &proof,
+        vk.srs_g1_t,
+        vk.srs_g2_1,
+        other_group.accumulator(),
+        &public_input,
+    ));
+}
@@ -1,2 +1,22 @@
 #[cfg(test)]
 pub mod prover_and_verifier;
+#[cfg(test)]
+pub mod semaphore_compat;
+#[cfg(test)]
+pub mod rln;
+#[cfg(test)]
+pub mod attributes;
+#[cfg(test)]
+pub mod tuple_lookup;
+#[cfg(test)]
+pub mod membership;
+#[cfg(test)]
+pub mod prefix;
+#[cfg(test)]
+pub mod linked;
+#[cfg(test)]
+pub mod rotation;
+#[cfg(test)]
+pub mod precomputed_w1;
+#[cfg(test)]
+pub mod poseidon;
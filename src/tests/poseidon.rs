@@ -0,0 +1,206 @@
+use crate::identity::Identity;
+use crate::layouter::Layouter;
+use crate::mimc7::init_mimc7;
+use crate::poseidon::init_semaphore_poseidon;
+use crate::prover::prover::{Prover, WitnessInput};
+use crate::prover::{ProverPrecomputedData, PublicData};
+use crate::semaphore_compat::{hash_signal, Group};
+use crate::setup::setup;
+use crate::verifier::{Verifier, VerifyingKey};
+use ark_bn254::{Bn254, Fr};
+use ark_ff::One;
+use ark_std::test_rng;
+
+#[test]
+pub fn test_poseidon_prove_and_verify() {
+    let mut rng = test_rng();
+    let log_2_table_size = 10;
+    let table_size: usize = 1 << log_2_table_size;
+    let (pk, lagrange_comms) = setup(log_2_table_size, "./11.ptau").unwrap();
+    let vk = VerifyingKey::from_proving_key(&pk, table_size).unwrap();
+    let mimc7 = init_mimc7::<Fr>();
+    let poseidon = init_semaphore_poseidon::<Fr>();
+
+    // A group of Semaphore v2 identity commitments
+    let identity = Identity::new(Fr::from(100u64), Fr::from(200u64));
+    let commitment = identity.poseidon_commitment();
+    let mut group = Group::new(&lagrange_comms);
+    group.add_member(Fr::from(1u64)).unwrap();
+    let index = group.add_member(commitment).unwrap();
+
+    let precomputed =
+        ProverPrecomputedData::index(&pk, &mimc7.cts, &[index], &group.polynomial(), table_size);
+    let witness = WitnessInput {
+        identity_nullifier: identity.nullifier,
+        identity_trapdoor: identity.trapdoor,
+        identity_commitment: commitment,
+        index,
+    };
+
+    let external_nullifier = Fr::from(300u64);
+    let public_input = PublicData::<Bn254> {
+        accumulator: group.accumulator(),
+        external_nullifier,
+        signal_hash: hash_signal("hello"),
+        nullifier_hash: identity.poseidon_nullifier_hash(external_nullifier),
+    };
+
+    let assignment = Layouter::assign_poseidon(
+        identity.nullifier,
+        identity.trapdoor,
+        external_nullifier,
+        &poseidon,
+        &mut rng,
+    );
+    let proof = Prover::prove_poseidon(
+        &pk,
+        &witness,
+        &assignment,
+        &public_input,
+        &precomputed,
+        &mut rng,
+        table_size,
+    );
+    assert!(Verifier::verify_poseidon(
+        &proof,
+        vk.srs_g1_t,
+        vk.srs_g2_1,
+        group.accumulator(),
+        &public_input,
+    ));
+
+    // The proof is bound to the nullifier hash and the external nullifier
+    let mut wrong_public_input = public_input.clone();
+    wrong_public_input.nullifier_hash = identity.nullifier_hash(external_nullifier);
+    assert!(!Verifier::verify_poseidon(
+        &proof,
+        vk.srs_g1_t,
+        vk.srs_g2_1,
+        group.accumulator(),
+        &wrong_public_input,
+    ));
+
+    let mut wrong_public_input = public_input.clone();
+    wrong_public_input.external_nullifier += Fr::one();
+    assert!(!Verifier::verify_poseidon(
+        &proof,
+        vk.srs_g1_t,
+        vk.srs_g2_1,
+        group.accumulator(),
+        &wrong_public_input,
+    ));
+
+    // The proof is not valid against another accumulator
+    let mut other_group = Group::new(&lagrange_comms);
+    other_group.add_member(identity.commitment()).unwrap();
+    assert!(!Verifier::verify_poseidon(
+        &proof,
+        vk.srs_g1_t,
+        vk.srs_g2_1,
+        other_group.accumulator(),
+        &public_input,
+    ));
+
+    // The proof does not verify as a MiMC7 proof
+    assert!(!Verifier::verify(
+        &proof.proof,
+        vk.srs_g1_t,
+        vk.srs_g2_1,
+        group.accumulator(),
+        &public_input,
+    ));
+}
+
+#[test]
+pub fn test_poseidon_invalid_witness() {
+    let mut rng = test_rng();
+    let log_2_table_size = 10;
+    let table_size: usize = 1 << log_2_table_size;
+    let (pk, lagrange_comms) = setup(log_2_table_size, "./11.ptau").unwrap();
+    let vk = VerifyingKey::from_proving_key(&pk, table_size).unwrap();
+    let mimc7 = init_mimc7::<Fr>();
+    let poseidon = init_semaphore_poseidon::<Fr>();
+
+    let identity = Identity::new(Fr::from(100u64), Fr::from(200u64));
+    let commitment = identity.poseidon_commitment();
+    let mut group = Group::new(&lagrange_comms);
+    let index = group.add_member(commitment).unwrap();
+
+    let precomputed =
+        ProverPrecomputedData::index(&pk, &mimc7.cts, &[index], &group.polynomial(), table_size);
+    let witness = WitnessInput {
+        identity_nullifier: identity.nullifier,
+        identity_trapdoor: identity.trapdoor,
+        identity_commitment: commitment,
+        index,
+    };
+
+    // A prover cannot claim the nullifier hash of another external nullifier
+    let external_nullifier = Fr::from(300u64);
+    let public_input = PublicData::<Bn254> {
+        accumulator: group.accumulator(),
+        external_nullifier,
+        signal_hash: hash_signal("hello"),
+        nullifier_hash: identity.poseidon_nullifier_hash(external_nullifier + Fr::one()),
+    };
+
+    let assignment = Layouter::assign_poseidon(
+        identity.nullifier,
+        identity.trapdoor,
+        external_nullifier,
+        &poseidon,
+        &mut rng,
+    );
+    let proof = Prover::prove_poseidon(
+        &pk,
+        &witness,
+        &assignment,
+        &public_input,
+        &precomputed,
+        &mut rng,
+        table_size,
+    );
+    assert!(!Verifier::verify_poseidon(
+        &proof,
+        vk.srs_g1_t,
+        vk.srs_g2_1,
+        group.accumulator(),
+        &public_input,
+    ));
+
+    // A prover cannot deviate from the Poseidon rounds of any instance
+    let public_input = PublicData::<Bn254> {
+        nullifier_hash: identity.poseidon_nullifier_hash(external_nullifier),
+        ..public_input
+    };
+    for instance in 0..3 {
+        let mut assignment = Layouter::assign_poseidon(
+            identity.nullifier,
+            identity.trapdoor,
+            external_nullifier,
+            &poseidon,
+            &mut rng,
+        );
+        match instance {
+            0 => assignment.identity_secret[1][30] += Fr::one(),
+            1 => assignment.identity_commitment[1][30] += Fr::one(),
+            _ => assignment.nullifier_hash[1][30] += Fr::one(),
+        }
+        let proof = Prover::prove_poseidon(
+            &pk,
+            &witness,
+            &assignment,
+            &public_input,
+            &precomputed,
+            &mut rng,
+            table_size,
+        );
+        assert!(!Verifier::verify_poseidon(
+            &proof,
+            vk.srs_g1_t,
+            vk.srs_g2_1,
+            group.accumulator(),
+            &public_input,
+        ));
+    }
+}
@@ -0,0 +1,80 @@
+use crate::error::Error;
+use crate::identity::Identity;
+use crate::mimc7::init_mimc7;
+use crate::prover::ProverPrecomputedData;
+use crate::semaphore_compat::Group;
+use crate::setup::setup;
+use ark_bn254::{Bn254, Fr};
+
+#[test]
+pub fn test_verify_w1() {
+    let log_2_table_size = 10;
+    let table_size: usize = 1 << log_2_table_size;
+    let (pk, lagrange_comms) = setup(log_2_table_size, "./11.ptau").unwrap();
+    let mimc7 = init_mimc7::<Fr>();
+
+    let identity = Identity::new(Fr::from(100u64), Fr::from(200u64));
+    let mut group = Group::new(&lagrange_comms);
+    group.add_member(Fr::from(1u64)).unwrap();
+    let index = group.add_member(identity.commitment()).unwrap();
+
+    let served = ProverPrecomputedData::<Bn254>::index(
+        &pk,
+        &mimc7.cts,
+        &[index],
+        &group.polynomial(),
+        table_size,
+    );
+    let w1 = served.caulk_plus_precomputed.get_w1_i(&index);
+
+    let mut precomputed = ProverPrecomputedData::<Bn254>::precompute_fixed(&mimc7.cts);
+    assert_eq!(
+        precomputed.update_w1_checked(
+            &pk,
+            group.accumulator(),
+            index,
+            identity.commitment(),
+            w1,
+            table_size
+        ),
+        Ok(())
+    );
+
+    // W1 for another index or value
+    assert_eq!(
+        ProverPrecomputedData::verify_w1(
+            &pk,
+            group.accumulator(),
+            index + 1,
+            identity.commitment(),
+            w1,
+            table_size
+        ),
+        Err(Error::W1PairingCheckFailed)
+    );
+    assert_eq!(
+        ProverPrecomputedData::verify_w1(
+            &pk,
+            group.accumulator(),
+            index,
+            Fr::from(1u64),
+            w1,
+            table_size
+        ),
+        Err(Error::W1PairingCheckFailed)
+    );
+
+    // A stale W1, from before another member was inserted
+    group.add_member(Fr::from(2u64)).unwrap();
+    assert_eq!(
+        ProverPrecomputedData::verify_w1(
+            &pk,
+            group.accumulator(),
+            index,
+            identity.commitment(),
+            w1,
+            table_size
+        ),
+        Err(Error::W1PairingCheckFailed)
+    );
+}
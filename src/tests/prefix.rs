@@ -0,0 +1,191 @@
+use crate::error::Error;
+use crate::identity::Identity;
+use crate::layouter::Layouter;
+use crate::mimc7::init_mimc7;
+use crate::prover::prover::{Prover, WitnessInput};
+use crate::prover::{PrefixPublicData, ProverPrecomputedData};
+use crate::semaphore_compat::{hash_signal, Group};
+use crate::setup::setup;
+use crate::verifier::{Verifier, VerifyingKey};
+use ark_bn254::{Bn254, Fr};
+use ark_std::test_rng;
+
+#[test]
+pub fn test_prefix_prove_and_verify() {
+    let mut rng = test_rng();
+    let log_2_table_size = 10;
+    let table_size: usize = 1 << log_2_table_size;
+    let (pk, lagrange_comms) = setup(log_2_table_size, "./11.ptau").unwrap();
+    let vk = VerifyingKey::from_proving_key(&pk, table_size).unwrap();
+    let mimc7 = init_mimc7::<Fr>();
+
+    // The member joins at index 2, and two more members join after them
+    let identity = Identity::new(Fr::from(100u64), Fr::from(200u64));
+    let mut group = Group::new(&lagrange_comms);
+    group.add_member(Fr::from(1u64)).unwrap();
+    group.add_member(Fr::from(2u64)).unwrap();
+    let index = group.add_member(identity.commitment()).unwrap();
+    group.add_member(Fr::from(3u64)).unwrap();
+    group.add_member(Fr::from(4u64)).unwrap();
+
+    let k = 3;
+    let precomputed = ProverPrecomputedData::index(
+        &pk,
+        &mimc7.cts,
+        &[index],
+        &group.prefix_polynomial(k),
+        table_size,
+    );
+    let witness = WitnessInput {
+        identity_nullifier: identity.nullifier,
+        identity_trapdoor: identity.trapdoor,
+        identity_commitment: identity.commitment(),
+        index,
+    };
+
+    let external_nullifier = Fr::from(300u64);
+    let public_input = PrefixPublicData::<Bn254> {
+        accumulator: group.accumulator(),
+        k,
+        external_nullifier,
+        signal_hash: hash_signal("airdrop"),
+        nullifier_hash: identity.nullifier_hash(external_nullifier),
+    };
+
+    let assignment = Layouter::assign(
+        identity.nullifier,
+        identity.trapdoor,
+        external_nullifier,
+        &mimc7.cts,
+        &mut rng,
+    );
+    let proof = Prover::prove_prefix(
+        &pk,
+        &lagrange_comms,
+        group.members(),
+        &witness,
+        &assignment,
+        &public_input,
+        &precomputed,
+        &mut rng,
+        table_size,
+    )
+    .unwrap();
+    assert!(Verifier::verify_prefix(
+        &proof,
+        vk.srs_g1_t,
+        vk.srs_g2_1,
+        &lagrange_comms,
+        group.members(),
+        &public_input
+    ));
+
+    // The proof is bound to k
+    let wrong_public_input = PrefixPublicData::<Bn254> {
+        k: 4,
+        ..public_input.clone()
+    };
+    assert!(!Verifier::verify_prefix(
+        &proof,
+        vk.srs_g1_t,
+        vk.srs_g2_1,
+        &lagrange_comms,
+        group.members(),
+        &wrong_public_input
+    ));
+
+    // The prefix accumulator is computed from members which open the group accumulator, so a
+    // proof against the full group is not a prefix proof
+    let full_public_input = PrefixPublicData::<Bn254> {
+        k: table_size,
+        ..public_input.clone()
+    };
+    let full_precomputed =
+        ProverPrecomputedData::index(&pk, &mimc7.cts, &[index], &group.polynomial(), table_size);
+    let full_proof = Prover::prove_prefix(
+        &pk,
+        &lagrange_comms,
+        group.members(),
+        &witness,
+        &assignment,
+        &full_public_input,
+        &full_precomputed,
+        &mut rng,
+        table_size,
+    )
+    .unwrap();
+    assert!(!Verifier::verify_prefix(
+        &full_proof,
+        vk.srs_g1_t,
+        vk.srs_g2_1,
+        &lagrange_comms,
+        group.members(),
+        &public_input
+    ));
+
+    // Members which do not open the group accumulator are rejected
+    let mut wrong_members = group.members().to_vec();
+    wrong_members.swap(0, index);
+    assert!(!Verifier::verify_prefix(
+        &proof,
+        vk.srs_g1_t,
+        vk.srs_g2_1,
+        &lagrange_comms,
+        &wrong_members,
+        &public_input
+    ));
+}
+
+#[test]
+pub fn test_prefix_rejects_late_member() {
+    let mut rng = test_rng();
+    let log_2_table_size = 10;
+    let table_size: usize = 1 << log_2_table_size;
+    let (pk, lagrange_comms) = setup(log_2_table_size, "./11.ptau").unwrap();
+    let mimc7 = init_mimc7::<Fr>();
+
+    let identity = Identity::new(Fr::from(100u64), Fr::from(200u64));
+    let mut group = Group::new(&lagrange_comms);
+    group.add_member(Fr::from(1u64)).unwrap();
+    let index = group.add_member(identity.commitment()).unwrap();
+
+    let k = 1;
+    let precomputed = ProverPrecomputedData::precompute_fixed(&mimc7.cts);
+    let witness = WitnessInput {
+        identity_nullifier: identity.nullifier,
+        identity_trapdoor: identity.trapdoor,
+        identity_commitment: identity.commitment(),
+        index,
+    };
+    let external_nullifier = Fr::from(300u64);
+    let public_input = PrefixPublicData::<Bn254> {
+        accumulator: group.accumulator(),
+        k,
+        external_nullifier,
+        signal_hash: hash_signal("airdrop"),
+        nullifier_hash: identity.nullifier_hash(external_nullifier),
+    };
+    let assignment = Layouter::assign(
+        identity.nullifier,
+        identity.trapdoor,
+        external_nullifier,
+        &mimc7.cts,
+        &mut rng,
+    );
+
+    assert_eq!(
+        Prover::prove_prefix(
+            &pk,
+            &lagrange_comms,
+            group.members(),
+            &witness,
+            &assignment,
+            &public_input,
+            &precomputed,
+            &mut rng,
+            table_size,
+        )
+        .err(),
+        Some(Error::IndexNotInPrefix)
+    );
+}
@@ -1,12 +1,13 @@
+use crate::error::Error;
 use crate::prover::prover::{Prover, WitnessInput};
 use crate::setup::setup;
 use crate::utils::construct_lagrange_basis_poly;
-use crate::verifier::Verifier;
+use crate::verifier::{Verifier, VerifyingKey};
 use crate::{
     kzg::commit,
     layouter::Layouter,
     mimc7::init_mimc7,
-    prover::{ProverPrecomputedData, PublicData},
+    prover::{ProverPrecomputedData, ProvingKey, PublicData, TrimConfig},
 };
 use ark_bn254::{Bn254, Fr};
 use ark_ec::{AffineCurve, ProjectiveCurve};
@@ -15,6 +16,7 @@ use ark_poly::{
     univariate::DensePolynomial, EvaluationDomain, GeneralEvaluationDomain, Polynomial,
     UVPolynomial,
 };
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 use ark_std::{test_rng, One};
 
 #[test]
@@ -22,7 +24,7 @@ pub fn test_prover_and_verifier() {
     let mut rng = test_rng();
     let log_2_table_size = 10;
     let table_size: usize = 1 << log_2_table_size;
-    let (pk, _) = setup(log_2_table_size, "./11.ptau");
+    let (pk, _) = setup(log_2_table_size, "./11.ptau").unwrap();
 
     let domain = GeneralEvaluationDomain::<Fr>::new(table_size).unwrap();
 
@@ -91,12 +93,101 @@ pub fn test_prover_and_verifier() {
     assert!(is_valid);
 }
 
+#[test]
+pub fn test_trimmed_proving_key() {
+    let mut rng = test_rng();
+    let log_2_table_size = 10;
+    let table_size: usize = 1 << log_2_table_size;
+    let (pk, _) = setup(log_2_table_size, "./11.ptau").unwrap();
+
+    let domain = GeneralEvaluationDomain::<Fr>::new(table_size).unwrap();
+    let mimc7 = init_mimc7::<Fr>();
+
+    let identity_nullifier = Fr::from(100u64);
+    let identity_trapdoor = Fr::from(200u64);
+    let external_nullifier = Fr::from(300u64);
+    let signal_hash = Fr::from(888u64);
+    let nullifier_hash = mimc7.multi_hash(&[identity_nullifier, external_nullifier], Fr::zero());
+    let identity_commitment =
+        mimc7.multi_hash(&[identity_nullifier, identity_trapdoor], Fr::zero());
+
+    let assignment = Layouter::assign(
+        identity_nullifier,
+        identity_trapdoor,
+        external_nullifier,
+        &mimc7.cts,
+        &mut rng,
+    );
+
+    let mut identity_commitments: Vec<_> = (0..table_size).map(|_| Fr::rand(&mut rng)).collect();
+    let index = 10;
+    identity_commitments[index] = identity_commitment;
+    let c = DensePolynomial::from_coefficients_slice(&domain.ifft(&identity_commitments));
+    let accumulator = commit(&pk.srs_g1, &c).into_affine();
+
+    // The W1 and W2 values are computed with the full key, e.g. by a precompute server
+    let mut precomputed = ProverPrecomputedData::precompute_fixed(&mimc7.cts);
+    precomputed.precompute_w1(&pk, &[index], &c, table_size);
+    precomputed.precompute_w2(&pk, &[index], table_size);
+
+    // The light prover only loads the trimmed key
+    let trimmed = pk.trim(TrimConfig::default()).unwrap();
+    let mut bytes = vec![];
+    trimmed.serialize(&mut bytes).unwrap();
+    assert!(bytes.len() < 64 * 1024);
+    let trimmed = ProvingKey::<Bn254>::deserialize(bytes.as_slice()).unwrap();
+    assert_eq!(trimmed.srs_g1[..], pk.srs_g1[..trimmed.srs_g1.len()]);
+
+    let witness = WitnessInput {
+        identity_nullifier,
+        identity_trapdoor,
+        identity_commitment,
+        index,
+    };
+    let public_input = PublicData::<Bn254> {
+        accumulator,
+        external_nullifier,
+        nullifier_hash,
+        signal_hash,
+    };
+    let proof = Prover::prove(
+        &trimmed,
+        &witness,
+        &assignment,
+        &public_input,
+        &precomputed,
+        &mut rng,
+        table_size,
+    );
+
+    // The trimmed key has no [X^table_size]_1, so the verifying key comes from the full SRS
+    assert_eq!(
+        VerifyingKey::from_proving_key(&trimmed, table_size),
+        Err(Error::SrsTooShort)
+    );
+    let vk = VerifyingKey::from_proving_key(&pk, table_size).unwrap();
+    assert!(Verifier::verify(
+        &proof,
+        vk.srs_g1_t,
+        vk.srs_g2_1,
+        accumulator,
+        &public_input,
+    ));
+
+    // A key cannot be trimmed to more points than it has
+    let config = TrimConfig {
+        num_g1_points: pk.srs_g1.len() + 1,
+        ..TrimConfig::default()
+    };
+    assert_eq!(pk.trim(config), Err(Error::SrsTooShort));
+}
+
 #[test]
 pub fn test_update_precomputed_w1() {
     let mut rng = test_rng();
     let log_2_table_size = 10;
     let table_size: usize = 1 << log_2_table_size;
-    let (pk, lagrange_comms) = setup(log_2_table_size, "./11.ptau");
+    let (pk, lagrange_comms) = setup(log_2_table_size, "./11.ptau").unwrap();
     let zero = Fr::from(0);
 
     let domain = GeneralEvaluationDomain::<Fr>::new(table_size).unwrap();
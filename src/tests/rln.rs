@@ -0,0 +1,205 @@
+use crate::error::Error;
+use crate::identity::Identity;
+use crate::layouter::Layouter;
+use crate::mimc7::init_mimc7;
+use crate::prover::prover::{Prover, WitnessInput};
+use crate::prover::{ProverPrecomputedData, RlnPublicData};
+use crate::rln::{compute_rln_nullifier, compute_share, recover_secret, rln_external_nullifier};
+use crate::semaphore_compat::{hash_signal, Group};
+use crate::setup::setup;
+use crate::verifier::{Verifier, VerifyingKey};
+use ark_bn254::{Bn254, Fr};
+use ark_std::test_rng;
+
+#[test]
+pub fn test_rln_prove_verify_and_recover() {
+    let mut rng = test_rng();
+    let log_2_table_size = 10;
+    let table_size: usize = 1 << log_2_table_size;
+    let (pk, lagrange_comms) = setup(log_2_table_size, "./11.ptau").unwrap();
+    let vk = VerifyingKey::from_proving_key(&pk, table_size).unwrap();
+    let mimc7 = init_mimc7::<Fr>();
+
+    let identity = Identity::new(Fr::from(100u64), Fr::from(200u64));
+    let mut group = Group::new(&lagrange_comms);
+    let index = group.add_member(identity.commitment()).unwrap();
+
+    let precomputed =
+        ProverPrecomputedData::index(&pk, &mimc7.cts, &[index], &group.polynomial(), table_size);
+    let witness = WitnessInput {
+        identity_nullifier: identity.nullifier,
+        identity_trapdoor: identity.trapdoor,
+        identity_commitment: identity.commitment(),
+        index,
+    };
+
+    // Two messages in the same epoch with the same message ID
+    let epoch = Fr::from(1u64);
+    let message_limit = 1;
+    let external_nullifier = rln_external_nullifier(epoch, 0, message_limit).unwrap();
+    let mut shares = vec![];
+    for signal in ["hello", "goodbye"] {
+        let signal_hash = hash_signal(signal);
+        let share = compute_share(&identity, external_nullifier, signal_hash);
+
+        let public_input = RlnPublicData::<Bn254> {
+            accumulator: group.accumulator(),
+            epoch,
+            message_limit,
+            signal_hash,
+            share_y: share.y,
+            rln_nullifier: compute_rln_nullifier(&identity, external_nullifier),
+        };
+
+        let assignment = Layouter::assign_rln(
+            identity.nullifier,
+            identity.trapdoor,
+            epoch,
+            0,
+            message_limit,
+            &mimc7.cts,
+            &mut rng,
+        )
+        .unwrap();
+        let proof = Prover::prove_rln(
+            &pk,
+            &witness,
+            &assignment,
+            &public_input,
+            &precomputed,
+            &mut rng,
+            table_size,
+        );
+
+        assert!(Verifier::verify_rln(
+            &proof,
+            vk.srs_g1_t,
+            vk.srs_g2_1,
+            group.accumulator(),
+            &public_input,
+            epoch,
+            message_limit,
+        ));
+
+        // The proof is bound to the share
+        let mut wrong_public_input = public_input.clone();
+        wrong_public_input.share_y += Fr::from(1u64);
+        assert!(!Verifier::verify_rln(
+            &proof,
+            vk.srs_g1_t,
+            vk.srs_g2_1,
+            group.accumulator(),
+            &wrong_public_input,
+            epoch,
+            message_limit,
+        ));
+
+        // The proof is for this epoch only
+        assert!(!Verifier::verify_rln(
+            &proof,
+            vk.srs_g1_t,
+            vk.srs_g2_1,
+            group.accumulator(),
+            &public_input,
+            epoch + Fr::from(1u64),
+            message_limit,
+        ));
+
+        shares.push(share);
+    }
+
+    // Exceeding the limit reveals the identity nullifier
+    assert_eq!(
+        recover_secret(&shares[0], &shares[1]),
+        Some(identity.nullifier)
+    );
+
+    // A message ID beyond the limit cannot be assigned
+    assert_eq!(
+        Layouter::assign_rln(
+            identity.nullifier,
+            identity.trapdoor,
+            epoch,
+            1,
+            message_limit,
+            &mimc7.cts,
+            &mut rng,
+        )
+        .err(),
+        Some(Error::MessageLimitExceeded)
+    );
+
+    // Messages with different message IDs have different RLN nullifiers, which do not reveal the
+    // message IDs
+    let message_limit = 2;
+    let mut rln_nullifiers = vec![];
+    for message_id in 0..message_limit {
+        let external_nullifier = rln_external_nullifier(epoch, message_id, message_limit).unwrap();
+        let signal_hash = hash_signal("hello again");
+        let share = compute_share(&identity, external_nullifier, signal_hash);
+        let public_input = RlnPublicData::<Bn254> {
+            accumulator: group.accumulator(),
+            epoch,
+            message_limit,
+            signal_hash,
+            share_y: share.y,
+            rln_nullifier: compute_rln_nullifier(&identity, external_nullifier),
+        };
+        let assignment = Layouter::assign_rln(
+            identity.nullifier,
+            identity.trapdoor,
+            epoch,
+            message_id,
+            message_limit,
+            &mimc7.cts,
+            &mut rng,
+        )
+        .unwrap();
+        let proof = Prover::prove_rln(
+            &pk,
+            &witness,
+            &assignment,
+            &public_input,
+            &precomputed,
+            &mut rng,
+            table_size,
+        );
+        assert!(Verifier::verify_rln(
+            &proof,
+            vk.srs_g1_t,
+            vk.srs_g2_1,
+            group.accumulator(),
+            &public_input,
+            epoch,
+            message_limit,
+        ));
+
+        // The circuit enforces the limit: a proof of the message ID 1 under a public limit of 1
+        // is rejected
+        if message_id == 1 {
+            let mut wrong_public_input = public_input.clone();
+            wrong_public_input.message_limit = 1;
+            let proof = Prover::prove_rln(
+                &pk,
+                &witness,
+                &assignment,
+                &wrong_public_input,
+                &precomputed,
+                &mut rng,
+                table_size,
+            );
+            assert!(!Verifier::verify_rln(
+                &proof,
+                vk.srs_g1_t,
+                vk.srs_g2_1,
+                group.accumulator(),
+                &wrong_public_input,
+                epoch,
+                1,
+            ));
+        }
+
+        rln_nullifiers.push(public_input.rln_nullifier);
+    }
+    assert_ne!(rln_nullifiers[0], rln_nullifiers[1]);
+}
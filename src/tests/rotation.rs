@@ -0,0 +1,99 @@
+use crate::error::Error;
+use crate::identity::Identity;
+use crate::prover::RotationPublicData;
+use crate::semaphore_compat::{
+    generate_proof, generate_rotation_proof, verify_proof, verify_rotation_proof, Group,
+};
+use crate::setup::setup;
+use crate::verifier::VerifyingKey;
+use ark_bn254::{Bn254, Fr};
+use ark_std::test_rng;
+
+#[test]
+pub fn test_rotation_prove_and_verify() {
+    let mut rng = test_rng();
+    let log_2_table_size = 10;
+    let table_size: usize = 1 << log_2_table_size;
+    let (pk, lagrange_comms) = setup(log_2_table_size, "./11.ptau").unwrap();
+    let vk = VerifyingKey::from_proving_key(&pk, table_size).unwrap();
+
+    let alice = Identity::new(Fr::from(100u64), Fr::from(200u64));
+    let bob = Identity::new(Fr::from(300u64), Fr::from(400u64));
+    let new_bob = Identity::new(Fr::from(700u64), Fr::from(800u64));
+
+    let mut group = Group::new(&lagrange_comms);
+    group.add_member(alice.commitment()).unwrap();
+    let index = group.add_member(bob.commitment()).unwrap();
+
+    let (proof, public_input) =
+        generate_rotation_proof(&pk, &bob, new_bob.commitment(), &group, &mut rng).unwrap();
+    assert!(verify_rotation_proof(&vk, &group, &public_input, &proof));
+
+    assert_eq!(public_input.index, index);
+
+    // The proof is bound to the index and to the old and new identity commitments
+    let wrong_public_input = RotationPublicData::<Bn254> {
+        index: 0,
+        ..public_input.clone()
+    };
+    assert!(!verify_rotation_proof(
+        &vk,
+        &group,
+        &wrong_public_input,
+        &proof
+    ));
+    let wrong_public_input = RotationPublicData::<Bn254> {
+        old_identity_commitment: alice.commitment(),
+        ..public_input.clone()
+    };
+    assert!(!verify_rotation_proof(
+        &vk,
+        &group,
+        &wrong_public_input,
+        &proof
+    ));
+    let wrong_public_input = RotationPublicData::<Bn254> {
+        new_identity_commitment: alice.commitment(),
+        ..public_input.clone()
+    };
+    assert!(!verify_rotation_proof(
+        &vk,
+        &group,
+        &wrong_public_input,
+        &proof
+    ));
+
+    // A rotation can only be applied to the slot which holds the old identity commitment
+    let wrong_public_input = RotationPublicData::<Bn254> {
+        index: 0,
+        ..public_input.clone()
+    };
+    assert_eq!(
+        group.clone().rotate_member(&wrong_public_input).err(),
+        Some(Error::IdentityNotInGroup)
+    );
+
+    // Applying the delta of the proven rotation gives the accumulator of a group in which the
+    // new identity was inserted at the same index
+    let accumulator = group.accumulator();
+    let delta = group.rotate_member(&public_input).unwrap();
+    assert_eq!(delta, public_input.delta(&lagrange_comms).unwrap());
+    assert_eq!(accumulator + delta, group.accumulator());
+
+    let mut expected = Group::new(&lagrange_comms);
+    expected.add_member(alice.commitment()).unwrap();
+    expected.add_member(new_bob.commitment()).unwrap();
+    assert_eq!(group.accumulator(), expected.accumulator());
+
+    // The old identity can no longer prove membership, but the new one can
+    assert!(generate_proof(&pk, &bob, &group, Fr::from(1u64), "vote", &mut rng).is_err());
+    let proof = generate_proof(&pk, &new_bob, &group, Fr::from(1u64), "vote", &mut rng).unwrap();
+    assert!(verify_proof(
+        &vk,
+        group.accumulator(),
+        new_bob.nullifier_hash(Fr::from(1u64)),
+        "vote",
+        Fr::from(1u64),
+        &proof
+    ));
+}
@@ -0,0 +1,65 @@
+use crate::error::Error;
+use crate::identity::Identity;
+use crate::semaphore_compat::{generate_nullifier_hash, generate_proof, verify_proof, Group};
+use crate::setup::setup;
+use crate::verifier::VerifyingKey;
+use ark_bn254::Fr;
+use ark_std::test_rng;
+
+#[test]
+pub fn test_generate_and_verify_proof() {
+    let mut rng = test_rng();
+    let log_2_table_size = 10;
+    let table_size: usize = 1 << log_2_table_size;
+    let (pk, lagrange_comms) = setup(log_2_table_size, "./11.ptau").unwrap();
+    let vk = VerifyingKey::from_proving_key(&pk, table_size).unwrap();
+
+    let alice = Identity::new(Fr::from(100u64), Fr::from(200u64));
+    let bob = Identity::new(Fr::from(300u64), Fr::from(400u64));
+    let carol = Identity::new(Fr::from(500u64), Fr::from(600u64));
+
+    let mut group = Group::new(&lagrange_comms);
+    assert_eq!(group.add_member(alice.commitment()), Ok(0));
+    assert_eq!(group.add_member(bob.commitment()), Ok(1));
+    assert_eq!(group.size(), 2);
+    assert_eq!(group.capacity(), table_size);
+
+    let external_nullifier = Fr::from(1234u64);
+    let signal = "hello";
+
+    let proof = generate_proof(&pk, &bob, &group, external_nullifier, signal, &mut rng).unwrap();
+    let nullifier_hash = generate_nullifier_hash(&bob, external_nullifier);
+
+    assert!(verify_proof(
+        &vk,
+        group.accumulator(),
+        nullifier_hash,
+        signal,
+        external_nullifier,
+        &proof,
+    ));
+
+    // The proof is bound to the signal and the nullifier hash
+    assert!(!verify_proof(
+        &vk,
+        group.accumulator(),
+        nullifier_hash,
+        "goodbye",
+        external_nullifier,
+        &proof,
+    ));
+    assert!(!verify_proof(
+        &vk,
+        group.accumulator(),
+        generate_nullifier_hash(&alice, external_nullifier),
+        signal,
+        external_nullifier,
+        &proof,
+    ));
+
+    // Non-members cannot generate proofs
+    assert_eq!(
+        generate_proof(&pk, &carol, &group, external_nullifier, signal, &mut rng).unwrap_err(),
+        Error::IdentityNotInGroup
+    );
+}
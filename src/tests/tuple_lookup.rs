@@ -0,0 +1,107 @@
+use crate::accumulator::{compute_zero_leaf, TupleAccumulator};
+use crate::error::Error;
+use crate::identity::Identity;
+use crate::layouter::Layouter;
+use crate::mimc7::init_mimc7;
+use crate::prover::prover::{Prover, WitnessInput};
+use crate::prover::{ProverPrecomputedData, TuplePublicData};
+use crate::semaphore_compat::hash_signal;
+use crate::setup::setup;
+use crate::verifier::{Verifier, VerifyingKey};
+use ark_bn254::{Bn254, Fr};
+use ark_ff::Zero;
+use ark_std::test_rng;
+
+#[test]
+pub fn test_tuple_lookup_prove_and_verify() {
+    let mut rng = test_rng();
+    let log_2_table_size = 10;
+    let table_size: usize = 1 << log_2_table_size;
+    let (pk, lagrange_comms) = setup(log_2_table_size, "./11.ptau").unwrap();
+    let vk = VerifyingKey::from_proving_key(&pk, table_size).unwrap();
+    let mimc7 = init_mimc7::<Fr>();
+
+    // Identity commitments in column 0 and voting weights in column 1
+    let mut acc =
+        TupleAccumulator::<Bn254>::new(&[compute_zero_leaf::<Fr>(), Fr::zero()], &lagrange_comms);
+    let other = Identity::new(Fr::from(1u64), Fr::from(2u64));
+    acc.update(0, &[other.commitment(), Fr::from(10u64)]);
+
+    let identity = Identity::new(Fr::from(100u64), Fr::from(200u64));
+    let weight = Fr::from(5u64);
+    let index = 1;
+    acc.update(index, &[identity.commitment(), weight]);
+
+    let mut precomputed =
+        ProverPrecomputedData::index(&pk, &mimc7.cts, &[index], &acc.polynomial(0), table_size);
+    precomputed.precompute_column_w1(&pk, 1, &[index], &acc.polynomial(1), table_size);
+
+    let witness = WitnessInput {
+        identity_nullifier: identity.nullifier,
+        identity_trapdoor: identity.trapdoor,
+        identity_commitment: identity.commitment(),
+        index,
+    };
+
+    let external_nullifier = Fr::from(300u64);
+    let public_input = TuplePublicData::<Bn254> {
+        accumulators: acc.points(),
+        values: vec![weight],
+        external_nullifier,
+        signal_hash: hash_signal("yes"),
+        nullifier_hash: identity.nullifier_hash(external_nullifier),
+    };
+
+    let assignment = Layouter::assign(
+        identity.nullifier,
+        identity.trapdoor,
+        external_nullifier,
+        &mimc7.cts,
+        &mut rng,
+    );
+    let proof = Prover::prove_tuple(
+        &pk,
+        &witness,
+        &assignment,
+        &public_input,
+        &precomputed,
+        &mut rng,
+        table_size,
+    )
+    .unwrap();
+    assert!(Verifier::verify_tuple(
+        &proof,
+        vk.srs_g1_t,
+        vk.srs_g2_1,
+        &public_input
+    ));
+
+    // The public input must have one value per accumulator other than the first
+    let mut wrong_public_input = public_input.clone();
+    wrong_public_input.values.push(weight);
+    assert_eq!(
+        Prover::prove_tuple(
+            &pk,
+            &witness,
+            &assignment,
+            &wrong_public_input,
+            &precomputed,
+            &mut rng,
+            table_size,
+        )
+        .err(),
+        Some(Error::TupleColumnCountMismatch)
+    );
+
+    // The proof is bound to the weight at the member's index
+    for wrong_weight in [Fr::from(10u64), Fr::from(6u64)] {
+        let mut wrong_public_input = public_input.clone();
+        wrong_public_input.values = vec![wrong_weight];
+        assert!(!Verifier::verify_tuple(
+            &proof,
+            vk.srs_g1_t,
+            vk.srs_g2_1,
+            &wrong_public_input
+        ));
+    }
+}
@@ -33,6 +33,13 @@ impl Transcript {
         }
     }
 
+    // The commitments to w0, key and w1 in membership-only mode, which has no w2 column
+    pub fn round_1_membership(&mut self, g1_vals: [&G1Affine; 3]) {
+        for val in g1_vals {
+            self.update_with_g1(val);
+        }
+    }
+
     pub fn round_2(&mut self, g1_vals: [&G1Affine; 4]) {
         for val in g1_vals {
             self.update_with_g1(val);
@@ -50,6 +57,13 @@ impl Transcript {
         }
     }
 
+    // The openings in membership-only mode, which has no w2 openings
+    pub fn round_4_membership(&mut self, f_vals: [Fr; 14]) {
+        for val in f_vals {
+            self.update_with_f(val);
+        }
+    }
+
     pub fn round_5(&mut self, f_cm: &G1Affine) {
         self.update_with_g1(f_cm);
     }
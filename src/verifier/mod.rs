@@ -1,14 +1,117 @@
-use crate::constants::{NUMBER_OF_MIMC_ROUNDS, SUBGROUP_SIZE};
+use crate::accumulator::{combine_accumulators, compute_prefix_accumulator, compute_zero_leaf};
+use crate::constants::{
+    NUMBER_OF_MIMC_ROUNDS, POSEIDON_SINGLE_WIDTH, POSEIDON_WIDTH, SUBGROUP_SIZE,
+};
+use crate::error::Error;
+use crate::layouter::Layouter;
 use crate::multiopen::verifier::Verifier as MultiopenVerifier;
-use crate::prover::{Proof, PublicData};
+use crate::poseidon::{init_poseidon, init_poseidon_single};
+use crate::prover::{
+    AttributeProof, AttributePublicData, LinkedLookupProof, LinkedProof, LinkedPublicData,
+    MembershipProof, MembershipPublicData, PoseidonProof, PrefixPublicData, Proof, ProvingKey,
+    PublicData, RlnProof, RlnPublicData, RotationProof, RotationPublicData, TuplePublicData,
+};
 use crate::transcript::Transcript;
 use ark_bn254::{Bn254, Fq12, Fr, G1Affine, G2Affine};
 use ark_ec::{AffineCurve, PairingEngine, ProjectiveCurve};
 use ark_ff::Field;
 use ark_poly::{EvaluationDomain, GeneralEvaluationDomain};
-use ark_std::One;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Read, SerializationError, Write};
+use ark_std::{One, Zero};
 use std::ops::Neg;
 
+/*
+ * The SRS points which the verifier needs, i.e. SRS_G1_T and SRS_G2_1 in Constants.sol.
+ */
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct VerifyingKey<E: PairingEngine> {
+    // [X^table_size]_1
+    pub srs_g1_t: E::G1Affine,
+    // [X]_2
+    pub srs_g2_1: E::G2Affine,
+}
+
+impl<E: PairingEngine> VerifyingKey<E> {
+    /*
+     * The verifying key of a proving key with at least table_size + 1 G1 points. A trimmed key
+     * (see ProvingKey::trim()) does not have [X^table_size]_1, so this returns
+     * Error::SrsTooShort for it, and its verifying key must come from the full SRS, e.g. a setup
+     * artifact or Constants.sol.
+     */
+    pub fn from_proving_key(pk: &ProvingKey<E>, table_size: usize) -> Result<Self, Error> {
+        if pk.srs_g1.len() <= table_size || pk.srs_g2.len() < 2 {
+            return Err(Error::SrsTooShort);
+        }
+        Ok(Self {
+            srs_g1_t: pk.srs_g1[table_size],
+            srs_g2_1: pk.srs_g2[1],
+        })
+    }
+}
+
+// The extra public inputs of the RLN and attribute variants. The Poseidon variant has none, but
+// replaces the gates of the regular circuit.
+enum ExtensionPublicInputs {
+    Rln {
+        share_y: Fr,
+        message_limit: Fr,
+    },
+    Attribute {
+        threshold: Fr,
+        reveal_attribute_hash: bool,
+        attribute_hash: Fr,
+    },
+    Poseidon,
+}
+
+// The commitments to and openings of the extra columns, and the extra public inputs
+struct Extension {
+    commitments: Vec<G1Affine>,
+    openings: Vec<[Fr; 3]>,
+    public_inputs: ExtensionPublicInputs,
+}
+
+impl Extension {
+    fn public_inputs(&self) -> Vec<Fr> {
+        match self.public_inputs {
+            ExtensionPublicInputs::Rln {
+                share_y,
+                message_limit,
+            } => vec![share_y, message_limit],
+            ExtensionPublicInputs::Attribute {
+                threshold,
+                reveal_attribute_hash,
+                attribute_hash,
+            } => vec![
+                threshold,
+                Fr::from(reveal_attribute_hash as u64),
+                attribute_hash,
+            ],
+            ExtensionPublicInputs::Poseidon => vec![],
+        }
+    }
+}
+
+// The accumulator which the Caulk+ lookup is performed against
+enum Lookup<'a> {
+    Accumulator(G1Affine),
+    // The prefix accumulator of the first k slots, which is appended to the transcript with k
+    Prefix {
+        prefix_accumulator: G1Affine,
+        k: usize,
+    },
+    // Parallel accumulators and the public values at the member's index, which are appended to
+    // the transcript and combined with the challenge beta
+    Tuple(&'a TuplePublicData<Bn254>),
+    // Two accumulators, both of which are appended to the transcript, and the second Caulk+
+    // lookup of the same identity commitment against the second accumulator
+    Linked {
+        accumulator: G1Affine,
+        second_accumulator: G1Affine,
+        second_lookup: &'a LinkedLookupProof<Bn254>,
+    },
+}
+
 pub struct Verifier {}
 
 impl Verifier {
@@ -18,6 +121,430 @@ impl Verifier {
         x_g2: G2Affine,
         accumulator: G1Affine,
         public_input: &PublicData<Bn254>,
+    ) -> bool {
+        Self::verify_inner(
+            proof,
+            None,
+            Lookup::Accumulator(accumulator),
+            a2_srs_g1,
+            x_g2,
+            public_input,
+        )
+    }
+
+    /*
+     * Verify a proof generated with Prover::prove_rln(). This is not supported by Semacaulk.sol.
+     * The circuit derives the external nullifier from the epoch and a private message ID, and
+     * checks that the message ID is below the limit, so a proof for another epoch or limit is
+     * rejected. The message ID is not revealed, so the proofs of a member in an epoch can only be
+     * linked when they reuse a message ID, which reveals the member's identity nullifier.
+     * @param epoch: The current epoch, which the verifier determines rather than the prover.
+     * @param message_limit: The number of messages a member may send per epoch.
+     */
+    pub fn verify_rln(
+        proof: &RlnProof<Bn254>,
+        a2_srs_g1: G1Affine,
+        x_g2: G2Affine,
+        accumulator: G1Affine,
+        public_input: &RlnPublicData<Bn254>,
+        epoch: Fr,
+        message_limit: u64,
+    ) -> bool {
+        if public_input.epoch != epoch || public_input.message_limit != message_limit {
+            return false;
+        }
+        if proof.extra_commitments.len() != 6 || proof.extra_openings.len() != 18 {
+            return false;
+        }
+
+        let extension = Extension {
+            commitments: proof.extra_commitments.clone(),
+            openings: proof
+                .extra_openings
+                .chunks(3)
+                .map(|o| [o[0], o[1], o[2]])
+                .collect(),
+            public_inputs: ExtensionPublicInputs::Rln {
+                share_y: public_input.share_y,
+                message_limit: Fr::from(message_limit),
+            },
+        };
+
+        Self::verify_inner(
+            &proof.proof,
+            Some(extension),
+            Lookup::Accumulator(accumulator),
+            a2_srs_g1,
+            x_g2,
+            &public_input.to_public_data(),
+        )
+    }
+
+    /*
+     * Verify a proof generated with Prover::prove_with_attribute(). This is not supported by
+     * Semacaulk.sol.
+     */
+    pub fn verify_with_attribute(
+        proof: &AttributeProof<Bn254>,
+        a2_srs_g1: G1Affine,
+        x_g2: G2Affine,
+        accumulator: G1Affine,
+        public_input: &AttributePublicData<Bn254>,
+    ) -> bool {
+        if proof.extra_commitments.len() != 4 || proof.extra_openings.len() != 12 {
+            return false;
+        }
+
+        let extension = Extension {
+            commitments: proof.extra_commitments.clone(),
+            openings: proof
+                .extra_openings
+                .chunks(3)
+                .map(|o| [o[0], o[1], o[2]])
+                .collect(),
+            public_inputs: ExtensionPublicInputs::Attribute {
+                threshold: public_input.threshold,
+                reveal_attribute_hash: public_input.reveal_attribute_hash,
+                attribute_hash: public_input.attribute_hash,
+            },
+        };
+
+        Self::verify_inner(
+            &proof.proof,
+            Some(extension),
+            Lookup::Accumulator(accumulator),
+            a2_srs_g1,
+            x_g2,
+            &public_input.to_public_data(),
+        )
+    }
+
+    /*
+     * Verify a proof generated with Prover::prove_poseidon(), for an accumulator of Semaphore v2
+     * Poseidon identity commitments. This is not supported by Semacaulk.sol.
+     */
+    pub fn verify_poseidon(
+        proof: &PoseidonProof<Bn254>,
+        a2_srs_g1: G1Affine,
+        x_g2: G2Affine,
+        accumulator: G1Affine,
+        public_input: &PublicData<Bn254>,
+    ) -> bool {
+        let num_of_columns = 2 * POSEIDON_WIDTH + POSEIDON_SINGLE_WIDTH;
+        if proof.extra_commitments.len() != num_of_columns
+            || proof.extra_openings.len() != 3 * num_of_columns
+        {
+            return false;
+        }
+
+        let extension = Extension {
+            commitments: proof.extra_commitments.clone(),
+            openings: proof
+                .extra_openings
+                .chunks(3)
+                .map(|o| [o[0], o[1], o[2]])
+                .collect(),
+            public_inputs: ExtensionPublicInputs::Poseidon,
+        };
+
+        Self::verify_inner(
+            &proof.proof,
+            Some(extension),
+            Lookup::Accumulator(accumulator),
+            a2_srs_g1,
+            x_g2,
+            public_input,
+        )
+    }
+
+    /*
+     * Verify a proof generated with Prover::prove_tuple(), which shows that the identity
+     * commitment and public_input.values sit at the same hidden index of
+     * public_input.accumulators. This is not supported by Semacaulk.sol.
+     */
+    pub fn verify_tuple(
+        proof: &Proof<Bn254>,
+        a2_srs_g1: G1Affine,
+        x_g2: G2Affine,
+        public_input: &TuplePublicData<Bn254>,
+    ) -> bool {
+        if public_input.accumulators.len() != public_input.values.len() + 1 {
+            return false;
+        }
+
+        Self::verify_inner(
+            proof,
+            None,
+            Lookup::Tuple(public_input),
+            a2_srs_g1,
+            x_g2,
+            &public_input.to_public_data(),
+        )
+    }
+
+    /*
+     * Verify a proof generated with Prover::prove_membership(). This is not supported by
+     * Semacaulk.sol.
+     */
+    pub fn verify_membership(
+        proof: &MembershipProof<Bn254>,
+        a2_srs_g1: G1Affine,
+        x_g2: G2Affine,
+        accumulator: G1Affine,
+        public_input: &MembershipPublicData<Bn254>,
+    ) -> bool {
+        let mut transcript = Transcript::new_transcript();
+
+        // Update transcript and derive challenges
+        transcript.update_with_f(public_input.signal_hash);
+        transcript.round_1_membership([
+            &proof.commitments.w0,
+            &proof.commitments.key,
+            &proof.commitments.w1,
+        ]);
+
+        let v = transcript.get_challenge();
+
+        transcript.round_2([
+            &proof.commitments.quotient,
+            &proof.commitments.zi,
+            &proof.commitments.ci,
+            &proof.commitments.u_prime,
+        ]);
+
+        let _hi_1 = transcript.get_challenge();
+        let hi_2 = transcript.get_challenge();
+
+        transcript.round_3(&proof.commitments.w, &proof.commitments.h);
+
+        let alpha = transcript.get_challenge();
+
+        let domain_h = GeneralEvaluationDomain::new(SUBGROUP_SIZE).unwrap();
+
+        let omega: Fr = domain_h.element(1);
+        let omega_n = domain_h.element(NUMBER_OF_MIMC_ROUNDS);
+
+        let omega_alpha = omega * alpha;
+        let omega_n_alpha = omega_n * alpha;
+
+        let openings = &proof.openings;
+        let w0_openings = [openings.w0_0, openings.w0_1, openings.w0_2];
+        let w1_openings = [openings.w1_0, openings.w1_1, openings.w1_2];
+        let key_openings = [openings.key_0, openings.key_1];
+
+        let l0_eval = domain_h.evaluate_all_lagrange_coefficients(alpha)[0];
+
+        let pow_7 = |x: Fr| x.pow([7, 0, 0, 0]);
+
+        // Gate 0: q_mimc_opening * ((w0_openings[0] + mimc_cts_opening) ^ 7 - w0_openings[1])
+        let gate_0_eval =
+            openings.q_mimc * (pow_7(w0_openings[0] + openings.mimc_cts) - w0_openings[1]);
+
+        // Gate 1: q_mimc_opening * ((w1_openings[0] + key_openings[0] + mimc_cts_opening) ^ 7 - w1_openings[1])
+        let gate_1_eval = openings.q_mimc
+            * (pow_7(w1_openings[0] + key_openings[0] + openings.mimc_cts) - w1_openings[1]);
+
+        // Gate 3:
+        // q_mimc_opening * (key_openings[0] - key_openings[1])
+        let gate_3_eval = openings.q_mimc * (key_openings[0] - key_openings[1]);
+
+        // Gate 4:
+        // l0 * (key_openings[0] - w0_openings[0] - w0_openings[2])
+        let gate_4_eval = l0_eval * (key_openings[0] - w0_openings[0] - w0_openings[2]);
+
+        let lhs = gate_0_eval
+            + (v * gate_1_eval)
+            + (v.pow([2, 0, 0, 0]) * gate_3_eval)
+            + (v.pow([3, 0, 0, 0]) * gate_4_eval);
+
+        let zh_eval = alpha.pow([SUBGROUP_SIZE as u64, 0, 0, 0]) - Fr::one();
+        let rhs = zh_eval * openings.quotient;
+
+        if lhs != rhs {
+            return false;
+        }
+
+        transcript.round_4_membership([
+            openings.w0_0,
+            openings.w0_1,
+            openings.w0_2,
+            openings.w1_0,
+            openings.w1_1,
+            openings.w1_2,
+            openings.key_0,
+            openings.key_1,
+            openings.q_mimc,
+            openings.mimc_cts,
+            openings.quotient,
+            openings.u_prime,
+            openings.p1,
+            openings.p2,
+        ]);
+
+        // The multiopen argument opens the zero polynomial in place of w2
+        let multiopen_final_poly = MultiopenVerifier::compute_final_poly(
+            &mut transcript,
+            &proof.multiopen_proof,
+            &proof.commitments.w0,
+            &w0_openings,
+            &proof.commitments.w1,
+            &w1_openings,
+            &G1Affine::zero(),
+            &[Fr::zero(); 3],
+            &proof.commitments.key,
+            &key_openings,
+            &proof.commitments.q_mimc,
+            openings.q_mimc,
+            &proof.commitments.mimc_cts,
+            openings.mimc_cts,
+            &proof.commitments.quotient,
+            openings.quotient,
+            &proof.commitments.u_prime,
+            openings.u_prime,
+            &proof.commitments.p1,
+            openings.p1,
+            &proof.commitments.p2,
+            openings.p2,
+            openings.u_prime,
+            alpha,
+            omega_alpha,
+            omega_n_alpha,
+        );
+
+        Self::final_pairing_check(
+            &mut transcript,
+            multiopen_final_poly,
+            proof.multiopen_proof.final_poly_proof,
+            accumulator,
+            proof.commitments.ci,
+            proof.commitments.zi,
+            proof.commitments.w,
+            hi_2,
+            a2_srs_g1,
+            x_g2,
+        )
+    }
+
+    /*
+     * Verify a proof generated with Prover::prove_prefix(), which shows that the member's index
+     * is less than public_input.k. The members must open public_input.accumulator, and the
+     * prefix accumulator of their first k slots is computed here, so the lookup is bound to the
+     * group. This is not supported by Semacaulk.sol.
+     * @param lagrange_comms: The commitments to the Lagrange basis polynomials.
+     * @param members: The identity commitments of the group, in index order.
+     */
+    pub fn verify_prefix(
+        proof: &Proof<Bn254>,
+        a2_srs_g1: G1Affine,
+        x_g2: G2Affine,
+        lagrange_comms: &[G1Affine],
+        members: &[Fr],
+        public_input: &PrefixPublicData<Bn254>,
+    ) -> bool {
+        if members.len() > lagrange_comms.len()
+            || compute_prefix_accumulator::<Bn254>(
+                compute_zero_leaf::<Fr>(),
+                lagrange_comms,
+                members,
+                members.len(),
+            ) != public_input.accumulator
+        {
+            return false;
+        }
+        let prefix_accumulator = match public_input.prefix_accumulator(lagrange_comms, members) {
+            Ok(prefix_accumulator) => prefix_accumulator,
+            Err(_) => return false,
+        };
+
+        Self::verify_inner(
+            proof,
+            None,
+            Lookup::Prefix {
+                prefix_accumulator,
+                k: public_input.k,
+            },
+            a2_srs_g1,
+            x_g2,
+            &public_input.to_public_data(prefix_accumulator),
+        )
+    }
+
+    /*
+     * Verify a proof generated with Prover::prove_linked(), which shows that the same identity
+     * is in the groups of public_input.accumulator and public_input.second_accumulator, with a
+     * single nullifier hash. This is not supported by Semacaulk.sol.
+     */
+    pub fn verify_linked(
+        proof: &LinkedProof<Bn254>,
+        a2_srs_g1: G1Affine,
+        x_g2: G2Affine,
+        public_input: &LinkedPublicData<Bn254>,
+    ) -> bool {
+        Self::verify_inner(
+            &proof.proof,
+            None,
+            Lookup::Linked {
+                accumulator: public_input.accumulator,
+                second_accumulator: public_input.second_accumulator,
+                second_lookup: &proof.second_lookup,
+            },
+            a2_srs_g1,
+            x_g2,
+            &public_input.to_public_data(),
+        )
+    }
+
+    /*
+     * Verify a proof generated with Prover::prove_rotation(), which authorises the replacement
+     * of public_input.old_identity_commitment at public_input.index with
+     * public_input.new_identity_commitment. The caller must check that the nullifier hash has
+     * not been used, and must apply the delta from RotationPublicData::delta(), which is bound
+     * to the index and the identity commitments checked here. This is not supported by
+     * Semacaulk.sol.
+     * @param lagrange_comms: The commitments to the Lagrange basis polynomials.
+     */
+    pub fn verify_rotation(
+        proof: &RotationProof<Bn254>,
+        a2_srs_g1: G1Affine,
+        x_g2: G2Affine,
+        lagrange_comms: &[G1Affine],
+        public_input: &RotationPublicData<Bn254>,
+    ) -> bool {
+        if public_input.index >= lagrange_comms.len() {
+            return false;
+        }
+
+        // The KZG opening of the accumulator at omega ^ index is the old identity commitment
+        let domain_t = match GeneralEvaluationDomain::<Fr>::new(lagrange_comms.len()) {
+            Some(domain_t) => domain_t,
+            None => return false,
+        };
+        let g1 = G1Affine::prime_subgroup_generator();
+        let g2 = G2Affine::prime_subgroup_generator();
+        let lhs_g1 = public_input.accumulator.into_projective()
+            - g1.mul(public_input.old_identity_commitment);
+        let rhs_g2 = x_g2.into_projective() - g2.mul(domain_t.element(public_input.index));
+        if Bn254::pairing(lhs_g1, g2) != Bn254::pairing(proof.slot_opening, rhs_g2) {
+            return false;
+        }
+
+        let base_public_input = public_input.to_public_data(lagrange_comms);
+        Self::verify(
+            &proof.proof,
+            a2_srs_g1,
+            x_g2,
+            base_public_input.accumulator,
+            &base_public_input,
+        )
+    }
+
+    fn verify_inner(
+        proof: &Proof<Bn254>,
+        extension: Option<Extension>,
+        lookup: Lookup,
+        a2_srs_g1: G1Affine,
+        x_g2: G2Affine,
+        public_input: &PublicData<Bn254>,
     ) -> bool {
         let external_nullifier = public_input.external_nullifier;
         let nullifier_hash = public_input.nullifier_hash;
@@ -27,26 +554,84 @@ impl Verifier {
 
         // Update transcript and derive challenges
         transcript.round_0_public_inputs([external_nullifier, nullifier_hash, signal_hash]);
+        if let Some(extension) = &extension {
+            for x in extension.public_inputs() {
+                transcript.update_with_f(x);
+            }
+        }
+        match lookup {
+            Lookup::Accumulator(_) => {}
+            Lookup::Prefix {
+                prefix_accumulator,
+                k,
+            } => {
+                transcript.update_with_g1(&prefix_accumulator);
+                transcript.update_with_f(Fr::from(k as u64));
+            }
+            Lookup::Tuple(tuple) => {
+                for accumulator in tuple.accumulators.iter() {
+                    transcript.update_with_g1(accumulator);
+                }
+                for value in tuple.values.iter() {
+                    transcript.update_with_f(*value);
+                }
+            }
+            Lookup::Linked {
+                accumulator,
+                second_accumulator,
+                ..
+            } => {
+                transcript.update_with_g1(&accumulator);
+                transcript.update_with_g1(&second_accumulator);
+            }
+        }
         transcript.round_1([
             &proof.commitments.w0,
             &proof.commitments.key,
             &proof.commitments.w1,
             &proof.commitments.w2,
         ]);
+        if let Some(extension) = &extension {
+            for commitment in extension.commitments.iter() {
+                transcript.update_with_g1(commitment);
+            }
+        }
 
         let v = transcript.get_challenge();
 
+        // The lookup of a tuple is performed against the random linear combination of its
+        // accumulators
+        let accumulator = match lookup {
+            Lookup::Accumulator(accumulator) => accumulator,
+            Lookup::Prefix {
+                prefix_accumulator, ..
+            } => prefix_accumulator,
+            Lookup::Tuple(tuple) => {
+                let beta = transcript.get_challenge();
+                combine_accumulators::<Bn254>(&tuple.accumulators, beta)
+            }
+            Lookup::Linked { accumulator, .. } => accumulator,
+        };
+
         transcript.round_2([
             &proof.commitments.quotient,
             &proof.commitments.zi,
             &proof.commitments.ci,
             &proof.commitments.u_prime,
         ]);
+        if let Lookup::Linked { second_lookup, .. } = lookup {
+            transcript.update_with_g1(&second_lookup.zi);
+            transcript.update_with_g1(&second_lookup.ci);
+            transcript.update_with_g1(&second_lookup.u_prime);
+        }
 
         let _hi_1 = transcript.get_challenge();
         let hi_2 = transcript.get_challenge();
 
         transcript.round_3(&proof.commitments.w, &proof.commitments.h);
+        if let Lookup::Linked { second_lookup, .. } = lookup {
+            transcript.round_3(&second_lookup.w, &second_lookup.h);
+        }
 
         let alpha = transcript.get_challenge();
 
@@ -81,60 +666,308 @@ impl Verifier {
 
         // Check if the gate equations are valid
 
-        let l0_eval = domain_h.evaluate_all_lagrange_coefficients(alpha)[0];
+        let lagrange_coeffs = domain_h.evaluate_all_lagrange_coefficients(alpha);
+        let l0_eval = lagrange_coeffs[0];
 
         let n = domain_h.size();
         let test =
             (alpha.pow([n as u64, 0, 0, 0]) - Fr::one()) / Fr::from(n as u64) / (alpha - Fr::one());
         assert_eq!(test, l0_eval);
 
-        // Compute the zh_eval - quotient_opening value, which is what the challenge-separated linear
-        // combination of the gate evaluations should equal
-        let pow_7 = |x: Fr| x.pow([7, 0, 0, 0]);
+        // The Poseidon variant replaces all the gates
+        let lhs = match &extension {
+            Some(Extension {
+                openings,
+                public_inputs: ExtensionPublicInputs::Poseidon,
+                ..
+            }) => Self::poseidon_gates_eval(
+                openings,
+                &lagrange_coeffs,
+                v,
+                nullifier_hash,
+                external_nullifier,
+            ),
+            _ => {
+                // Compute the zh_eval - quotient_opening value, which is what the challenge-separated linear
+                // combination of the gate evaluations should equal
+                let pow_7 = |x: Fr| x.pow([7, 0, 0, 0]);
 
-        // Gate 0: q_mimc_opening * ((w0_openings[0] + mimc_cts_opening) ^ 7 - w0_openings[1])
-        let gate_0_eval =
-            q_mimc_opening * (pow_7(w0_openings[0] + mimc_cts_opening) - w0_openings[1]);
+                // Gate 0: q_mimc_opening * ((w0_openings[0] + mimc_cts_opening) ^ 7 - w0_openings[1])
+                let gate_0_eval =
+                    q_mimc_opening * (pow_7(w0_openings[0] + mimc_cts_opening) - w0_openings[1]);
 
-        // Gate 1: q_mimc_opening * ((w1_openings[0] + key_openings[0] + mimc_cts_opening) ^ 7 - w1_openings[1])
-        let gate_1_eval = q_mimc_opening
-            * (pow_7(w1_openings[0] + key_openings[0] + mimc_cts_opening) - w1_openings[1]);
+                // Gate 1: q_mimc_opening * ((w1_openings[0] + key_openings[0] + mimc_cts_opening) ^ 7 - w1_openings[1])
+                let gate_1_eval = q_mimc_opening
+                    * (pow_7(w1_openings[0] + key_openings[0] + mimc_cts_opening) - w1_openings[1]);
 
-        // Gate 2:
-        // q_mimc_opening * ((w2_openings[0] + key_openings[0] + mimc_cts_opening) ^ 7 - w2_openings[1])
-        let gate_2_eval = q_mimc_opening
-            * (pow_7(w2_openings[0] + key_openings[0] + mimc_cts_opening) - w2_openings[1]);
+                // Gate 2:
+                // q_mimc_opening * ((w2_openings[0] + key_openings[0] + mimc_cts_opening) ^ 7 - w2_openings[1])
+                let gate_2_eval = q_mimc_opening
+                    * (pow_7(w2_openings[0] + key_openings[0] + mimc_cts_opening) - w2_openings[1]);
 
-        // Gate 3:
-        // q_mimc_opening * (key_openings[0] - key_openings[1])
-        let gate_3_eval = q_mimc_opening * (key_openings[0] - key_openings[1]);
+                // Gate 3:
+                // q_mimc_opening * (key_openings[0] - key_openings[1])
+                let gate_3_eval = q_mimc_opening * (key_openings[0] - key_openings[1]);
 
-        // Gate 4:
-        // l0 * (key_openings[0] - w0_openings[0] - w0_openings[2])
-        let gate_4_eval = l0_eval * (key_openings[0] - w0_openings[0] - w0_openings[2]);
+                // Gate 4:
+                // l0 * (key_openings[0] - w0_openings[0] - w0_openings[2])
+                let gate_4_eval = l0_eval * (key_openings[0] - w0_openings[0] - w0_openings[2]);
 
-        // Gate 5:
-        // l0 * (nullifierHash - w2_openings[0] - w2_openings[2] - (2 * key_openings[0]))
-        let gate_5_eval = l0_eval
-            * (nullifier_hash - w2_openings[0] - w2_openings[2] - (Fr::from(2) * key_openings[0]));
+                // Gate 5:
+                // l0 * (nullifierHash - w2_openings[0] - w2_openings[2] - (2 * key_openings[0]))
+                // In RLN mode, w3_openings[0] takes the place of nullifierHash
+                let gate_5_nullifier = match &extension {
+                    Some(Extension {
+                        openings,
+                        public_inputs: ExtensionPublicInputs::Rln { .. },
+                        ..
+                    }) => openings[0][0],
+                    _ => nullifier_hash,
+                };
+                let gate_5_eval = l0_eval
+                    * (gate_5_nullifier
+                        - w2_openings[0]
+                        - w2_openings[2]
+                        - (Fr::from(2) * key_openings[0]));
 
-        // Gate 6:
-        // l0 * (w2_openings[0] - external_nullifier)
-        let gate_6_eval = l0_eval * (w2_openings[0] - external_nullifier);
+                // Gate 6:
+                // l0 * (w2_openings[0] - external_nullifier)
+                // In RLN mode, the external nullifier is the multihash of the epoch and the
+                // message ID: l0 * (w2_openings[0] - message_id[2] - 2 * message_key[0] - message_id[0])
+                let gate_6_eval = match &extension {
+                    Some(Extension {
+                        openings,
+                        public_inputs: ExtensionPublicInputs::Rln { .. },
+                        ..
+                    }) => {
+                        l0_eval
+                            * (w2_openings[0]
+                                - openings[3][2]
+                                - Fr::from(2) * openings[2][0]
+                                - openings[3][0])
+                    }
+                    _ => l0_eval * (w2_openings[0] - external_nullifier),
+                };
 
-        let v_pow_2 = v.pow([2, 0, 0, 0]);
-        let v_pow_3 = v.pow([3, 0, 0, 0]);
-        let v_pow_4 = v.pow([4, 0, 0, 0]);
-        let v_pow_5 = v.pow([5, 0, 0, 0]);
-        let v_pow_6 = v.pow([6, 0, 0, 0]);
+                let v_pow_2 = v.pow([2, 0, 0, 0]);
+                let v_pow_3 = v.pow([3, 0, 0, 0]);
+                let v_pow_4 = v.pow([4, 0, 0, 0]);
+                let v_pow_5 = v.pow([5, 0, 0, 0]);
+                let v_pow_6 = v.pow([6, 0, 0, 0]);
 
-        let lhs = gate_0_eval
-            + (v * gate_1_eval)
-            + (v_pow_2 * gate_2_eval)
-            + (v_pow_3 * gate_3_eval)
-            + (v_pow_4 * gate_4_eval)
-            + (v_pow_5 * gate_5_eval)
-            + (v_pow_6 * gate_6_eval);
+                let mut lhs = gate_0_eval
+                    + (v * gate_1_eval)
+                    + (v_pow_2 * gate_2_eval)
+                    + (v_pow_3 * gate_3_eval)
+                    + (v_pow_4 * gate_4_eval)
+                    + (v_pow_5 * gate_5_eval)
+                    + (v_pow_6 * gate_6_eval);
+
+                // The evaluations of the extra gates, from gate 7 onwards
+                let extension_gate_evals = match &extension {
+                    None
+                    | Some(Extension {
+                        public_inputs: ExtensionPublicInputs::Poseidon,
+                        ..
+                    }) => vec![],
+                    Some(Extension {
+                        openings,
+                        public_inputs:
+                            ExtensionPublicInputs::Rln {
+                                share_y,
+                                message_limit,
+                            },
+                        ..
+                    }) => {
+                        let w3_openings = openings[0];
+                        let epoch_openings = openings[1];
+                        let message_key_openings = openings[2];
+                        let message_id_openings = openings[3];
+                        let message_id_range_openings = openings[4];
+                        let message_limit_range_openings = openings[5];
+
+                        // Gate 7:
+                        // q_mimc_opening * ((w3_openings[0] + mimc_cts_opening) ^ 7 - w3_openings[1])
+                        let gate_7_eval = q_mimc_opening
+                            * (pow_7(w3_openings[0] + mimc_cts_opening) - w3_openings[1]);
+
+                        // Gate 8:
+                        // l0 * (rlnNullifier - w3_openings[0] - w3_openings[2])
+                        let gate_8_eval =
+                            l0_eval * (nullifier_hash - w3_openings[0] - w3_openings[2]);
+
+                        // Gate 9:
+                        // l0 * (shareY - w0_openings[0] - w3_openings[0] * signalHash)
+                        let gate_9_eval =
+                            l0_eval * (*share_y - w0_openings[0] - w3_openings[0] * signal_hash);
+
+                        // Gate 10:
+                        // q_mimc_opening * ((epoch[0] + mimc_cts_opening) ^ 7 - epoch[1])
+                        let gate_10_eval = q_mimc_opening
+                            * (pow_7(epoch_openings[0] + mimc_cts_opening) - epoch_openings[1]);
+
+                        // Gate 11:
+                        // l0 * (epoch[0] - epoch), where the epoch takes the place of the
+                        // external nullifier
+                        let gate_11_eval = l0_eval * (epoch_openings[0] - external_nullifier);
+
+                        // Gate 12:
+                        // q_mimc_opening * (message_key[0] - message_key[1])
+                        let gate_12_eval =
+                            q_mimc_opening * (message_key_openings[0] - message_key_openings[1]);
+
+                        // Gate 13:
+                        // l0 * (message_key[0] - epoch[0] - epoch[2])
+                        let gate_13_eval = l0_eval
+                            * (message_key_openings[0] - epoch_openings[0] - epoch_openings[2]);
+
+                        // Gate 14:
+                        // q_mimc_opening * ((message_id[0] + message_key[0] + mimc_cts_opening) ^ 7 - message_id[1])
+                        let gate_14_eval = q_mimc_opening
+                            * (pow_7(
+                                message_id_openings[0] + message_key_openings[0] + mimc_cts_opening,
+                            ) - message_id_openings[1]);
+
+                        // Gates 15 and 18:
+                        // q_mimc_opening * b * (b - 1), where b = range[1] - 2 * range[0]
+                        let range_bit_eval = |range_openings: [Fr; 3]| {
+                            let bit = range_openings[1] - Fr::from(2) * range_openings[0];
+                            q_mimc_opening * bit * (bit - Fr::one())
+                        };
+                        let gate_15_eval = range_bit_eval(message_id_range_openings);
+
+                        // Gate 16:
+                        // l0 * message_id_range[0]
+                        let gate_16_eval = l0_eval * message_id_range_openings[0];
+
+                        // Gate 17:
+                        // l0 * (message_id_range[2] - message_id[0])
+                        let gate_17_eval =
+                            l0_eval * (message_id_range_openings[2] - message_id_openings[0]);
+
+                        let gate_18_eval = range_bit_eval(message_limit_range_openings);
+
+                        // Gate 19:
+                        // l0 * message_limit_range[0]
+                        let gate_19_eval = l0_eval * message_limit_range_openings[0];
+
+                        // Gate 20:
+                        // l0 * (message_limit_range[2] + message_id[0] + 1 - messageLimit)
+                        let gate_20_eval = l0_eval
+                            * (message_limit_range_openings[2]
+                                + message_id_openings[0]
+                                + Fr::one()
+                                - *message_limit);
+
+                        vec![
+                            gate_7_eval,
+                            gate_8_eval,
+                            gate_9_eval,
+                            gate_10_eval,
+                            gate_11_eval,
+                            gate_12_eval,
+                            gate_13_eval,
+                            gate_14_eval,
+                            gate_15_eval,
+                            gate_16_eval,
+                            gate_17_eval,
+                            gate_18_eval,
+                            gate_19_eval,
+                            gate_20_eval,
+                        ]
+                    }
+                    Some(Extension {
+                        openings,
+                        public_inputs:
+                            ExtensionPublicInputs::Attribute {
+                                threshold,
+                                reveal_attribute_hash,
+                                attribute_hash,
+                            },
+                        ..
+                    }) => {
+                        let attribute_key_openings = openings[0];
+                        let attribute_openings = openings[1];
+                        let range_openings = openings[2];
+                        let attribute_hash_openings = openings[3];
+
+                        // Gate 7:
+                        // q_mimc_opening * ((attribute[0] + attribute_key[0] + mimc_cts_opening) ^ 7 - attribute[1])
+                        let gate_7_eval = q_mimc_opening
+                            * (pow_7(
+                                attribute_openings[0]
+                                    + attribute_key_openings[0]
+                                    + mimc_cts_opening,
+                            ) - attribute_openings[1]);
+
+                        // Gate 8:
+                        // q_mimc_opening * (attribute_key[0] - attribute_key[1])
+                        let gate_8_eval = q_mimc_opening
+                            * (attribute_key_openings[0] - attribute_key_openings[1]);
+
+                        // Gate 9:
+                        // l0 * (attribute_key[0] - w1_openings[2] - w1_openings[0] - 2 * key_openings[0])
+                        let gate_9_eval = l0_eval
+                            * (attribute_key_openings[0]
+                                - w1_openings[2]
+                                - w1_openings[0]
+                                - Fr::from(2) * key_openings[0]);
+
+                        // Gate 10:
+                        // q_mimc_opening * b * (b - 1), where b = range[1] - 2 * range[0]
+                        let bit = range_openings[1] - Fr::from(2) * range_openings[0];
+                        let gate_10_eval = q_mimc_opening * bit * (bit - Fr::one());
+
+                        // Gate 11:
+                        // l0 * range[0]
+                        let gate_11_eval = l0_eval * range_openings[0];
+
+                        // Gate 12:
+                        // l0 * (range[2] - attribute[0] + threshold)
+                        let gate_12_eval =
+                            l0_eval * (range_openings[2] - attribute_openings[0] + threshold);
+
+                        // Gate 13:
+                        // q_mimc_opening * ((attribute_hash[0] + mimc_cts_opening) ^ 7 - attribute_hash[1])
+                        let gate_13_eval = q_mimc_opening
+                            * (pow_7(attribute_hash_openings[0] + mimc_cts_opening)
+                                - attribute_hash_openings[1]);
+
+                        // Gate 14:
+                        // l0 * (attribute_hash[0] - attribute[0])
+                        let gate_14_eval =
+                            l0_eval * (attribute_hash_openings[0] - attribute_openings[0]);
+
+                        // Gate 15:
+                        // l0 * reveal * (attributeHash - attribute_hash[2] - attribute_hash[0])
+                        let gate_15_eval = l0_eval
+                            * Fr::from(*reveal_attribute_hash as u64)
+                            * (*attribute_hash
+                                - attribute_hash_openings[2]
+                                - attribute_hash_openings[0]);
+
+                        vec![
+                            gate_7_eval,
+                            gate_8_eval,
+                            gate_9_eval,
+                            gate_10_eval,
+                            gate_11_eval,
+                            gate_12_eval,
+                            gate_13_eval,
+                            gate_14_eval,
+                            gate_15_eval,
+                        ]
+                    }
+                };
+
+                let mut v_pow = v_pow_6;
+                for gate_eval in extension_gate_evals {
+                    v_pow *= v;
+                    lhs += v_pow * gate_eval;
+                }
+                lhs
+            }
+        };
 
         let zh_eval = alpha.pow([SUBGROUP_SIZE as u64, 0, 0, 0]) - Fr::one();
         let quotient_opening = proof.openings.quotient;
@@ -164,6 +997,32 @@ impl Verifier {
             proof.openings.p2,
         ]);
 
+        // The multiopen argument opens w2 + gamma * extra[0] + gamma ^ 2 * extra[1] + ... in place
+        // of w2
+        let (w2, w2_openings) = match &extension {
+            None => (proof.commitments.w2, w2_openings),
+            Some(extension) => {
+                for opening in extension.openings.iter().flatten() {
+                    transcript.update_with_f(*opening);
+                }
+                let gamma = transcript.get_challenge();
+
+                let mut w2 = proof.commitments.w2.into_projective();
+                let mut w2_openings = w2_openings;
+                let mut gamma_pow = gamma;
+                for (commitment, openings) in
+                    extension.commitments.iter().zip(extension.openings.iter())
+                {
+                    w2 += commitment.mul(gamma_pow);
+                    for (w2_opening, opening) in w2_openings.iter_mut().zip(openings.iter()) {
+                        *w2_opening += gamma_pow * opening;
+                    }
+                    gamma_pow *= gamma;
+                }
+                (w2.into_affine(), w2_openings)
+            }
+        };
+
         let multiopen_final_poly = MultiopenVerifier::compute_final_poly(
             &mut transcript,
             &proof.multiopen_proof,
@@ -179,12 +1038,8 @@ impl Verifier {
                 proof.openings.w1_1,
                 proof.openings.w1_2,
             ],
-            &proof.commitments.w2,
-            &[
-                proof.openings.w2_0,
-                proof.openings.w2_1,
-                proof.openings.w2_2,
-            ],
+            &w2,
+            &w2_openings,
             &proof.commitments.key,
             &[proof.openings.key_0, proof.openings.key_1],
             &proof.commitments.q_mimc,
@@ -205,6 +1060,198 @@ impl Verifier {
             omega_n_alpha,
         );
 
+        // The second lookup of a linked proof has its own multiopen proof, which opens its
+        // Caulk+ polynomials together with the same circuit polynomials
+        let second_multiopen_final_poly = match lookup {
+            Lookup::Linked { second_lookup, .. } => {
+                if second_lookup.p2_opening != Fr::zero() {
+                    return false;
+                }
+                transcript.update_with_f(second_lookup.u_prime_opening);
+                transcript.update_with_f(second_lookup.p1_opening);
+                transcript.update_with_f(second_lookup.p2_opening);
+
+                Some(MultiopenVerifier::compute_final_poly(
+                    &mut transcript,
+                    &second_lookup.multiopen_proof,
+                    &proof.commitments.w0,
+                    &[
+                        proof.openings.w0_0,
+                        proof.openings.w0_1,
+                        proof.openings.w0_2,
+                    ],
+                    &proof.commitments.w1,
+                    &[
+                        proof.openings.w1_0,
+                        proof.openings.w1_1,
+                        proof.openings.w1_2,
+                    ],
+                    &w2,
+                    &w2_openings,
+                    &proof.commitments.key,
+                    &[proof.openings.key_0, proof.openings.key_1],
+                    &proof.commitments.q_mimc,
+                    q_mimc_opening,
+                    &proof.commitments.mimc_cts,
+                    mimc_cts_opening,
+                    &proof.commitments.quotient,
+                    quotient_opening,
+                    &second_lookup.u_prime,
+                    second_lookup.u_prime_opening,
+                    &second_lookup.p1,
+                    second_lookup.p1_opening,
+                    &second_lookup.p2,
+                    second_lookup.p2_opening,
+                    second_lookup.u_prime_opening,
+                    alpha,
+                    omega_alpha,
+                    omega_n_alpha,
+                ))
+            }
+            _ => None,
+        };
+
+        let first_check = Self::final_pairing_check(
+            &mut transcript,
+            multiopen_final_poly,
+            proof.multiopen_proof.final_poly_proof,
+            accumulator,
+            proof.commitments.ci,
+            proof.commitments.zi,
+            proof.commitments.w,
+            hi_2,
+            a2_srs_g1,
+            x_g2,
+        );
+
+        match (lookup, second_multiopen_final_poly) {
+            (
+                Lookup::Linked {
+                    second_accumulator,
+                    second_lookup,
+                    ..
+                },
+                Some(second_multiopen_final_poly),
+            ) => {
+                first_check
+                    && Self::final_pairing_check(
+                        &mut transcript,
+                        second_multiopen_final_poly,
+                        second_lookup.multiopen_proof.final_poly_proof,
+                        second_accumulator,
+                        second_lookup.ci,
+                        second_lookup.zi,
+                        second_lookup.w,
+                        hi_2,
+                        a2_srs_g1,
+                        x_g2,
+                    )
+            }
+            _ => first_check,
+        }
+    }
+
+    /*
+     * The linear combination of the gates of the Poseidon circuit at alpha, separated by v ^ 0 to
+     * v ^ 14. The fixed columns are evaluated with the Lagrange basis polynomials of the subgroup,
+     * so they are not part of the proof.
+     * @param openings: The openings of the state columns of the identity secret, identity
+     *                  commitment and nullifier hash instances.
+     * @param lagrange_coeffs: The Lagrange basis polynomials of the subgroup at alpha.
+     */
+    fn poseidon_gates_eval(
+        openings: &[[Fr; 3]],
+        lagrange_coeffs: &[Fr],
+        v: Fr,
+        nullifier_hash: Fr,
+        external_nullifier: Fr,
+    ) -> Fr {
+        let poseidon = Layouter::assign_poseidon_fixed(&init_poseidon::<Fr>());
+        let poseidon_single = Layouter::assign_poseidon_fixed(&init_poseidon_single::<Fr>());
+        let eval_at_alpha = |evals: &[Fr]| {
+            evals
+                .iter()
+                .zip(lagrange_coeffs.iter())
+                .fold(Fr::zero(), |acc, (e, l)| acc + *e * l)
+        };
+
+        let l0_eval = lagrange_coeffs[0];
+        let pow_5 = |x: Fr| x.pow([5, 0, 0, 0]);
+
+        let (s, rest) = openings.split_at(POSEIDON_WIDTH);
+        let (t, u) = rest.split_at(POSEIDON_SINGLE_WIDTH);
+
+        // Gates 0 to 7, for each instance and state column k:
+        // q_full * (sum_j(M[k][j] * (s_j[0] + c_j) ^ 5) - s_k[1]) +
+        // q_partial * (M[k][0] * (s_0[0] + c_0) ^ 5 + sum_{j > 0}(M[k][j] * (s_j[0] + c_j)) - s_k[1]) +
+        // q_pass * (s_k[0] - s_k[1])
+        let mut gate_evals = vec![];
+        for (instance, fixed) in [(s, &poseidon), (t, &poseidon_single), (u, &poseidon)] {
+            let q_full = eval_at_alpha(&fixed.q_full);
+            let q_partial = eval_at_alpha(&fixed.q_partial);
+            let q_pass = eval_at_alpha(&fixed.q_pass);
+            let cts: Vec<Fr> = fixed.cts.iter().map(|c| eval_at_alpha(c)).collect();
+
+            for k in 0..instance.len() {
+                let mut full = Fr::zero();
+                let mut partial = Fr::zero();
+                for (j, (openings, c)) in instance.iter().zip(cts.iter()).enumerate() {
+                    let x = openings[0] + c;
+                    full += fixed.mds[k][j] * pow_5(x);
+                    partial += fixed.mds[k][j] * if j == 0 { pow_5(x) } else { x };
+                }
+                let s_next = instance[k][1];
+                gate_evals.push(
+                    q_full * (full - s_next)
+                        + q_partial * (partial - s_next)
+                        + q_pass * (instance[k][0] - s_next),
+                );
+            }
+        }
+
+        // Gates 8 to 10:
+        // l0 * s_0[0], l0 * t_0[0] and l0 * u_0[0]
+        for instance in [s, t, u] {
+            gate_evals.push(l0_eval * instance[0][0]);
+        }
+
+        // Gate 11:
+        // l0 * (s_1[0] - u_2[0])
+        gate_evals.push(l0_eval * (s[1][0] - u[2][0]));
+
+        // Gate 12:
+        // l0 * (t_1[0] - s_0[2])
+        gate_evals.push(l0_eval * (t[1][0] - s[0][2]));
+
+        // Gate 13:
+        // l0 * (nullifierHash - u_0[2])
+        gate_evals.push(l0_eval * (nullifier_hash - u[0][2]));
+
+        // Gate 14:
+        // l0 * (u_1[0] - externalNullifier)
+        gate_evals.push(l0_eval * (u[1][0] - external_nullifier));
+
+        gate_evals
+            .iter()
+            .rev()
+            .fold(Fr::zero(), |acc, gate_eval| acc * v + gate_eval)
+    }
+
+    // The Caulk+ pairing check and the multiopen pairing check, combined with the separator
+    // challenge s
+    #[allow(clippy::too_many_arguments)]
+    fn final_pairing_check(
+        transcript: &mut Transcript,
+        multiopen_final_poly: (G1Affine, Fr, Fr),
+        final_poly_proof: G1Affine,
+        accumulator: G1Affine,
+        ci: G1Affine,
+        zi: G1Affine,
+        w: G2Affine,
+        hi_2: Fr,
+        a2_srs_g1: G1Affine,
+        x_g2: G2Affine,
+    ) -> bool {
         // Perform this using product_of_pairings(): A * B * C and check that
         // the result equals Fq12::one().
         //
@@ -232,7 +1279,7 @@ impl Verifier {
         //     p is final_poly
         //
         // B:
-        //   -zi is proof.commitments.zi.neg()
+        //   -zi is zi.neg()
         //   w is w_commitment from caulk_second_round
         //
         // C:
@@ -244,11 +1291,10 @@ impl Verifier {
         let g1_gen = G1Affine::prime_subgroup_generator();
         let g2_gen = G2Affine::prime_subgroup_generator();
         let (final_poly, final_poly_eval, x3) = multiopen_final_poly;
-        let final_poly_proof = proof.multiopen_proof.final_poly_proof;
         let minus_y = g1_gen.mul(final_poly_eval).neg();
         let zq = final_poly_proof.mul(x3);
 
-        let a1 = accumulator + proof.commitments.ci.neg();
+        let a1 = accumulator + ci.neg();
         let a2 = (a2_srs_g1 + g1_gen.neg()).mul(hi_2).into_affine();
         let a3 = (zq + minus_y)
             .add_mixed(&final_poly)
@@ -259,8 +1305,8 @@ impl Verifier {
         let a_lhs = a1 + a2 + a3;
         let a_rhs = g2_gen;
 
-        let b_lhs = proof.commitments.zi.neg();
-        let b_rhs = proof.commitments.w;
+        let b_lhs = zi.neg();
+        let b_rhs = w;
 
         let c_lhs = final_poly_proof.neg().mul(s).into_affine();
         let c_rhs = x_g2;